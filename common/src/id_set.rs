@@ -1,13 +1,19 @@
 use num_traits::sign::Unsigned;
-use optional::Noned;
+use num_traits::PrimInt;
+pub use optional::Noned;
 use optional::Optioned;
-use std::default::Default;
 use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
 
-pub trait IdIndex: Unsigned + Into<usize> + From<usize> + Copy {}
+pub trait IdIndex: PrimInt + Unsigned {}
 
-pub trait IdGeneration: Unsigned + Into<usize> + From<usize> + Copy {}
+impl<T: PrimInt + Unsigned> IdIndex for T {}
 
+pub trait IdGeneration: PrimInt + Unsigned {}
+
+impl<T: PrimInt + Unsigned> IdGeneration for T {}
+
+// generation zero is never stored in the set, so the null id never exists
 pub trait Id<TIndex: IdIndex, TGeneration: IdGeneration>: Noned + Clone + Copy + Sized {
     fn new(index: TIndex, generation: TGeneration) -> Self;
 
@@ -20,110 +26,446 @@ pub trait Id<TIndex: IdIndex, TGeneration: IdGeneration>: Noned + Clone + Copy +
     }
 }
 
+// declares a copyable id type usable with `IdSet`, the null id is the `None` of `Optioned`
+//
+// define_id! {
+//     #[derive(Serialize, Deserialize)]
+//     pub struct MaterialId(u32, u32);
+// }
+#[macro_export]
+macro_rules! define_id {
+    ($(#[$meta:meta])* $visibility:vis struct $name:ident($index:ty, $generation:ty);) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+        $visibility struct $name {
+            index: $index,
+            generation: $generation,
+        }
+
+        impl $crate::id_set::Id<$index, $generation> for $name {
+            fn new(index: $index, generation: $generation) -> Self {
+                $name { index, generation }
+            }
+
+            fn get_index(&self) -> $index {
+                self.index
+            }
+
+            fn get_generation(&self) -> $generation {
+                self.generation
+            }
+        }
+
+        impl $crate::id_set::Noned for $name {
+            fn is_none(&self) -> bool {
+                self.generation == 0
+            }
+
+            fn get_none() -> Self {
+                $name { index: 0, generation: 0 }
+            }
+        }
+    };
+}
+
+fn to_usize<T: PrimInt>(value: T) -> usize {
+    value.to_usize().expect("id index doesn't fit usize")
+}
+
+// generational arena: removing a value bumps the generation of its slot,
+// so ids of removed values never refer to values added later
 pub struct IdSet<T, TIndex, TGeneration, TId>
 where
-    T: Default,
     TIndex: IdIndex,
     TGeneration: IdGeneration,
     TId: Id<TIndex, TGeneration>,
 {
-    _generations: Vec<TGeneration>,
-    _free_indicies: Vec<TIndex>,
-    _filled_indicies_count: usize,
-    _values: Vec<T>,
+    generations: Vec<TGeneration>,
+    free_indices: Vec<TIndex>,
+    // slots whose generation overflowed, they are never reused
+    retired_indices_count: usize,
+    values: Vec<Option<T>>,
     phantom: PhantomData<TId>,
 }
 
+impl<T, TIndex, TGeneration, TId> Default for IdSet<T, TIndex, TGeneration, TId>
+where
+    TIndex: IdIndex,
+    TGeneration: IdGeneration,
+    TId: Id<TIndex, TGeneration>,
+{
+    fn default() -> Self {
+        IdSet::new()
+    }
+}
+
 impl<T, TIndex, TGeneration, TId> IdSet<T, TIndex, TGeneration, TId>
 where
-    T: Default,
     TIndex: IdIndex,
     TGeneration: IdGeneration,
     TId: Id<TIndex, TGeneration>,
 {
     pub fn new() -> Self {
         IdSet {
-            _generations: Vec::new(),
-            _free_indicies: Vec::new(),
-            _filled_indicies_count: 0,
-            _values: Vec::new(),
+            generations: Vec::new(),
+            free_indices: Vec::new(),
+            retired_indices_count: 0,
+            values: Vec::new(),
             phantom: PhantomData,
         }
     }
 
     pub fn len(&self) -> usize {
-        self._generations.len() - self._free_indicies.len() - self._filled_indicies_count
+        self.generations.len() - self.free_indices.len() - self.retired_indices_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     pub fn exists(&self, id: TId) -> bool {
-        let index = id.get_index().into();
-        index < self._generations.len() && self._generations[index] == id.get_generation()
+        self.get(id).is_some()
     }
 
     pub fn add(&mut self, value: T) -> TId {
-        let index = if self._free_indicies.is_empty() {
-            self._generations.len()
-        } else {
-            self._free_indicies.pop().unwrap().into()
+        let index = match self.free_indices.pop() {
+            Some(index) => to_usize(index),
+            None => {
+                let index = self.generations.len();
+                assert!(TIndex::from(index).is_some(), "IdSet is out of indices");
+                self.generations.push(TGeneration::one());
+                index
+            }
         };
 
-        // assert!(index != TIndex::MAX);
+        if index >= self.values.len() {
+            self.values.resize_with(index + 1, || None);
+        }
+        self.values[index] = Some(value);
+        TId::new(TIndex::from(index).unwrap(), self.generations[index])
+    }
+
+    // puts the value back under an id given before, used to undo removals and to load saved ids,
+    // fails if the slot is taken or was reused after the id had been removed
+    pub fn add_with_id(&mut self, id: TId, value: T) -> bool {
+        if id.is_none() {
+            return false;
+        }
 
-        if index == self._generations.len() {
-            self._generations.push(TGeneration::zero());
-            self._values.push(value);
-            TId::new(index.into(), TGeneration::zero())
+        let index = to_usize(id.get_index());
+        let generation = id.get_generation();
+        if index >= self.generations.len() {
+            for free_index in self.generations.len()..index {
+                self.generations.push(TGeneration::one());
+                self.free_indices.push(TIndex::from(free_index).unwrap());
+            }
+            self.generations.push(generation);
         } else {
-            self._values[index] = value;
-            TId::new(index.into(), TGeneration::zero())
+            if matches!(self.values.get(index), Some(Some(_))) {
+                return false;
+            }
+            let current = self.generations[index];
+            let free_position = self
+                .free_indices
+                .iter()
+                .position(|free_index| *free_index == id.get_index());
+            match free_position {
+                // a removal bumps the generation, a slot reused after that has a greater one
+                Some(position)
+                    if current == generation
+                        || Some(current) == generation.checked_add(&TGeneration::one()) =>
+                {
+                    self.free_indices.remove(position);
+                }
+                // retired slot, only its last id can come back
+                None if current == generation => self.retired_indices_count -= 1,
+                _ => return false,
+            }
+            self.generations[index] = generation;
         }
+
+        if index >= self.values.len() {
+            self.values.resize_with(index + 1, || None);
+        }
+        self.values[index] = Some(value);
+        true
     }
 
-    pub fn remove(&mut self, id: TId) {
-        assert!(!self.exists(id));
+    // returns the removed value, None if the id doesn't exist
+    pub fn remove(&mut self, id: TId) -> Option<T> {
+        if !self.exists(id) {
+            return None;
+        }
 
-        self._free_indicies.push(id.get_index());
-        let index = id.get_index().into();
-        self._generations[index] = self._generations[index] + TGeneration::one();
-        self._values[index] = T::default();
+        let index = to_usize(id.get_index());
+        let value = self.values[index].take();
+        if self.generations[index] == TGeneration::max_value() {
+            self.retired_indices_count += 1;
+        } else {
+            self.generations[index] = self.generations[index] + TGeneration::one();
+            self.free_indices.push(id.get_index());
+        }
+        value
     }
 
+    // removes all values, ids given before stay invalid
     pub fn clear(&mut self) {
-        unimplemented!()
+        for id in self.get_ids() {
+            self.remove(id);
+        }
     }
 
+    // forgets everything, ids given before may refer to values added later
     pub fn clear_all(&mut self) {
-        self._generations.clear();
-        self._free_indicies.clear();
-        self._values.clear();
-        self._filled_indicies_count = 0;
+        self.generations.clear();
+        self.free_indices.clear();
+        self.values.clear();
+        self.retired_indices_count = 0;
+    }
+
+    pub fn get(&self, id: TId) -> Option<&T> {
+        let index = to_usize(id.get_index());
+        match self.generations.get(index) {
+            Some(generation) if *generation == id.get_generation() => {
+                self.values.get(index).and_then(Option::as_ref)
+            }
+            _ => None,
+        }
     }
 
-    pub fn get(&self, _id: TId) -> &T {
-        unimplemented!()
+    pub fn get_mut(&mut self, id: TId) -> Option<&mut T> {
+        let index = to_usize(id.get_index());
+        match self.generations.get(index) {
+            Some(generation) if *generation == id.get_generation() => {
+                self.values.get_mut(index).and_then(Option::as_mut)
+            }
+            _ => None,
+        }
+    }
+
+    // returns false if the id doesn't exist
+    pub fn set(&mut self, id: TId, value: T) -> bool {
+        match self.get_mut(id) {
+            Some(stored) => {
+                *stored = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn find<F: Fn(&T) -> bool>(&self, predicate: F) -> Optioned<TId> {
+        match self.iter().find(|(_, value)| predicate(value)) {
+            Some((id, _)) => Optioned::some(id),
+            None => Optioned::none(),
+        }
     }
 
-    pub fn set(&mut self, _id: TId, _value: T) {
-        unimplemented!()
+    pub fn find_all<F: Fn(&T) -> bool>(&self, predicate: F) -> Vec<TId> {
+        self.iter()
+            .filter(|(_, value)| predicate(value))
+            .map(|(id, _)| id)
+            .collect()
     }
 
-    pub fn find() -> Optioned<TId> {
-        unimplemented!()
+    pub fn get_ids(&self) -> Vec<TId> {
+        self.iter().map(|(id, _)| id).collect()
     }
 
-    pub fn find_all() -> Vec<TId> {
-        unimplemented!()
+    // values in the index order
+    pub fn iter(&self) -> impl Iterator<Item = (TId, &T)> + '_ {
+        let generations = &self.generations;
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, value)| {
+                value.as_ref().map(|value| {
+                    (
+                        TId::new(TIndex::from(index).unwrap(), generations[index]),
+                        value,
+                    )
+                })
+            })
     }
 
-    pub fn optimize() {
-        unimplemented!()
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (TId, &mut T)> + '_ {
+        let generations = &self.generations;
+        self.values
+            .iter_mut()
+            .enumerate()
+            .filter_map(move |(index, value)| {
+                value.as_mut().map(|value| {
+                    (
+                        TId::new(TIndex::from(index).unwrap(), generations[index]),
+                        value,
+                    )
+                })
+            })
+    }
+
+    // releases the storage of the trailing free slots and makes the lowest free indices reused first,
+    // generations are kept, so ids given before stay invalid
+    pub fn optimize(&mut self) {
+        let used = self
+            .values
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |last| last + 1);
+        self.values.truncate(used);
+        self.values.shrink_to_fit();
+        self.free_indices.sort_by(|a, b| b.cmp(a));
+        self.free_indices.shrink_to_fit();
+        self.generations.shrink_to_fit();
+    }
+}
+
+impl<T, TIndex, TGeneration, TId> Index<TId> for IdSet<T, TIndex, TGeneration, TId>
+where
+    TIndex: IdIndex,
+    TGeneration: IdGeneration,
+    TId: Id<TIndex, TGeneration>,
+{
+    type Output = T;
+
+    fn index(&self, id: TId) -> &T {
+        self.get(id).expect("id doesn't exist in IdSet")
+    }
+}
+
+impl<T, TIndex, TGeneration, TId> IndexMut<TId> for IdSet<T, TIndex, TGeneration, TId>
+where
+    TIndex: IdIndex,
+    TGeneration: IdGeneration,
+    TId: Id<TIndex, TGeneration>,
+{
+    fn index_mut(&mut self, id: TId) -> &mut T {
+        self.get_mut(id).expect("id doesn't exist in IdSet")
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    define_id! {
+        struct TestId(u16, u8);
+    }
+
+    type TestSet = IdSet<String, u16, u8, TestId>;
+
+    #[test]
+    fn add_remove_test() {
+        let mut set = TestSet::new();
+        let a = set.add(String::from("a"));
+        let b = set.add(String::from("b"));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get(a).map(String::as_str), Some("a"));
+        assert!(!set.exists(TestId::null()));
+        assert!(TestId::null().is_none());
+
+        assert_eq!(set.remove(a), Some(String::from("a")));
+        assert_eq!(set.remove(a), None);
+        assert!(set.get(a).is_none());
+        assert!(!set.set(a, String::from("stale")));
+
+        // the slot is reused with the next generation
+        let c = set.add(String::from("c"));
+        assert_eq!(c.get_index(), a.get_index());
+        assert_eq!(c.get_generation(), a.get_generation() + 1);
+        assert!(set.get(a).is_none());
+        assert!(set.set(c, String::from("d")));
+        set.get_mut(b).unwrap().push('!');
+        assert_eq!(
+            set.iter()
+                .map(|(_, value)| value.as_str())
+                .collect::<Vec<_>>(),
+            vec!["d", "b!"]
+        );
+
+        assert_eq!(
+            set.find(|value| value.starts_with('b')).into_option(),
+            Some(b)
+        );
+        assert!(set.find(|value| value.is_empty()).is_none());
+        assert_eq!(set.find_all(|_| true), vec![c, b]);
+
+        set.clear();
+        assert!(set.is_empty());
+        assert!(set.get(b).is_none());
+        assert_ne!(set.add(String::from("e")), b);
+    }
+
+    #[test]
+    fn add_with_id_test() {
+        let mut set = TestSet::new();
+        let a = set.add(String::from("a"));
+        let b = set.add(String::from("b"));
+        assert!(!set.add_with_id(a, String::from("taken")));
+
+        set.remove(a);
+        assert!(set.add_with_id(a, String::from("restored")));
+        assert_eq!(set[a], "restored");
+        assert_eq!(set.len(), 2);
+
+        // the slot was reused, so the removed id can't come back
+        set.remove(b);
+        let c = set.add(String::from("c"));
+        set.remove(c);
+        assert!(!set.add_with_id(b, String::from("stale")));
+        assert!(!set.add_with_id(TestId::null(), String::new()));
+
+        // ids beyond the end leave free slots behind
+        let mut loaded = TestSet::new();
+        assert!(loaded.add_with_id(TestId::new(2, 7), String::from("far")));
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            loaded.get(TestId::new(2, 7)).map(String::as_str),
+            Some("far")
+        );
+        let gap = loaded.add(String::from("gap"));
+        assert!(gap.get_index() < 2);
+        assert_eq!(loaded.len(), 2);
+    }
+
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn generation_overflow_test() {
+        let mut set = TestSet::new();
+        let mut id = set.add(String::new());
+        for _ in 1..u8::MAX {
+            set.remove(id);
+            id = set.add(String::new());
+            assert_eq!(id.get_index(), 0);
+        }
+        assert_eq!(id.get_generation(), u8::MAX);
+
+        // the exhausted slot is retired instead of wrapping to the null generation
+        set.remove(id);
+        assert!(set.is_empty());
+        assert_eq!(set.add(String::new()).get_index(), 1);
+    }
+
+    #[test]
+    fn optimize_test() {
+        let mut set = TestSet::new();
+        let ids: Vec<TestId> = (0..8).map(|i| set.add(i.to_string())).collect();
+        for id in ids.iter().skip(2) {
+            set.remove(*id);
+        }
+        set.remove(ids[0]);
+        set.optimize();
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.values.len(), 2);
+        assert_eq!(set.get(ids[1]).map(String::as_str), Some("1"));
+
+        let reused = set.add(String::from("new"));
+        assert_eq!(reused.get_index(), 0);
+        assert!(set.get(ids[0]).is_none());
+        let next = set.add(String::from("next"));
+        assert_eq!(next.get_index(), 2);
+        assert!(set.get(ids[2]).is_none());
+        for (_, value) in set.iter_mut() {
+            value.clear();
+        }
+        assert!(set.iter().all(|(_, value)| value.is_empty()));
     }
 }
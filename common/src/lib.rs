@@ -2,6 +2,8 @@
 pub mod trace;
 pub mod id_set;
 pub mod mesh;
+pub mod mesh_io;
+pub mod mesh_processing;
 #[macro_use]
 pub mod init_logger;
 
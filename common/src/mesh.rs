@@ -1,5 +1,37 @@
-use nalgebra::{Vector2, Vector3, Vector4};
+use nalgebra::{Matrix3, Matrix4, Point3, Vector2, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug)]
+pub enum MeshError {
+    Io(std::io::Error),
+    Parse {
+        line: usize,
+        message: String,
+    },
+    InvalidFormat(String),
+    Unsupported(String),
+    AttributeLength {
+        attribute: String,
+        expected: usize,
+        actual: usize,
+    },
+    IncompleteTriangle(usize),
+    IndexOutOfRange {
+        index: u32,
+        vertices_count: usize,
+    },
+    MissingAttribute(String),
+}
+
+impl From<std::io::Error> for MeshError {
+    fn from(error: std::io::Error) -> MeshError {
+        MeshError::Io(error)
+    }
+}
+
+// triangle list, per-vertex attributes are either empty or have one value per position,
+// front faces are counter-clockwise
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
 pub struct Mesh {
     pub indices: Vec<u32>,
     pub positions: Vec<Vector3<f32>>,
@@ -9,3 +41,387 @@ pub struct Mesh {
     pub colors: [Vec<Vector4<f32>>; 4],
     pub uvs: [Vec<Vector2<f32>>; 8],
 }
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh::default()
+    }
+
+    // square in the XZ plane facing +Y
+    pub fn plane(width: f32, depth: f32) -> Mesh {
+        let mut mesh = Mesh::new();
+        mesh.add_quad(
+            Vector3::zeros(),
+            Vector3::x() * width * 0.5,
+            -Vector3::z() * depth * 0.5,
+        );
+        mesh
+    }
+
+    // box centered at the origin, faces don't share vertices to keep normals sharp
+    pub fn cuboid(size: Vector3<f32>) -> Mesh {
+        let mut mesh = Mesh::new();
+        let half = size * 0.5;
+        for axis in 0..3 {
+            for sign in [1., -1.].iter() {
+                let mut normal = Vector3::zeros();
+                normal[axis] = *sign;
+                let mut u = Vector3::zeros();
+                u[(axis + 1) % 3] = 1.;
+                let v = normal.cross(&u);
+                mesh.add_quad(
+                    normal.component_mul(&half),
+                    u.component_mul(&half),
+                    v.component_mul(&half),
+                );
+            }
+        }
+        mesh
+    }
+
+    // uv sphere, `segments` around the Y axis and `rings` from pole to pole
+    pub fn sphere(radius: f32, segments: u32, rings: u32) -> Mesh {
+        let segments = segments.max(3);
+        let rings = rings.max(2);
+        let mut mesh = Mesh::new();
+        for ring in 0..=rings {
+            let theta = std::f32::consts::PI * ring as f32 / rings as f32;
+            for segment in 0..=segments {
+                let phi = 2. * std::f32::consts::PI * segment as f32 / segments as f32;
+                let normal = Vector3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                mesh.positions.push(normal * radius);
+                mesh.normals.push(normal);
+                mesh.uvs[0].push(Vector2::new(
+                    segment as f32 / segments as f32,
+                    ring as f32 / rings as f32,
+                ));
+            }
+        }
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let a = ring * (segments + 1) + segment;
+                let b = a + segments + 1;
+                // triangles touching the poles would be degenerate
+                if ring != 0 {
+                    mesh.indices.extend_from_slice(&[a, a + 1, b]);
+                }
+                if ring != rings - 1 {
+                    mesh.indices.extend_from_slice(&[a + 1, b + 1, b]);
+                }
+            }
+        }
+        mesh
+    }
+
+    // cylinder along the Y axis with both caps
+    pub fn cylinder(radius: f32, height: f32, segments: u32) -> Mesh {
+        let segments = segments.max(3);
+        let mut mesh = Mesh::new();
+        let half_height = height * 0.5;
+        for segment in 0..=segments {
+            let phi = 2. * std::f32::consts::PI * segment as f32 / segments as f32;
+            let normal = Vector3::new(phi.cos(), 0., phi.sin());
+            for (y, v) in [(-half_height, 0.), (half_height, 1.)].iter() {
+                mesh.positions
+                    .push(Vector3::new(normal.x * radius, *y, normal.z * radius));
+                mesh.normals.push(normal);
+                mesh.uvs[0].push(Vector2::new(segment as f32 / segments as f32, *v));
+            }
+        }
+        for segment in 0..segments {
+            let bottom = 2 * segment;
+            let top = bottom + 1;
+            mesh.indices
+                .extend_from_slice(&[bottom, top, bottom + 2, top, top + 2, bottom + 2]);
+        }
+
+        for (y, normal) in [(half_height, Vector3::y()), (-half_height, -Vector3::y())].iter() {
+            let center = mesh.positions.len() as u32;
+            mesh.positions.push(Vector3::new(0., *y, 0.));
+            mesh.normals.push(*normal);
+            mesh.uvs[0].push(Vector2::new(0.5, 0.5));
+            for segment in 0..=segments {
+                let phi = 2. * std::f32::consts::PI * segment as f32 / segments as f32;
+                mesh.positions
+                    .push(Vector3::new(phi.cos() * radius, *y, phi.sin() * radius));
+                mesh.normals.push(*normal);
+                mesh.uvs[0].push(Vector2::new(0.5 + 0.5 * phi.cos(), 0.5 + 0.5 * phi.sin()));
+            }
+            for segment in 0..segments {
+                let current = center + 1 + segment;
+                if normal.y > 0. {
+                    mesh.indices
+                        .extend_from_slice(&[center, current + 1, current]);
+                } else {
+                    mesh.indices
+                        .extend_from_slice(&[center, current, current + 1]);
+                }
+            }
+        }
+        mesh
+    }
+
+    pub fn get_vertices_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    // attribute lengths match the positions and every index refers to a vertex
+    pub fn validate(&self) -> Result<(), MeshError> {
+        let count = self.get_vertices_count();
+        let mut lengths = vec![
+            (String::from("normals"), self.normals.len()),
+            (String::from("tangents"), self.tangents.len()),
+            (String::from("bitangents"), self.bitangents.len()),
+        ];
+        lengths.extend(
+            self.colors
+                .iter()
+                .enumerate()
+                .map(|(i, colors)| (format!("colors[{}]", i), colors.len())),
+        );
+        lengths.extend(
+            self.uvs
+                .iter()
+                .enumerate()
+                .map(|(i, uvs)| (format!("uvs[{}]", i), uvs.len())),
+        );
+        for (attribute, length) in lengths {
+            if length != 0 && length != count {
+                return Err(MeshError::AttributeLength {
+                    attribute,
+                    expected: count,
+                    actual: length,
+                });
+            }
+        }
+
+        if self.indices.len() % 3 != 0 {
+            return Err(MeshError::IncompleteTriangle(self.indices.len()));
+        }
+        match self.indices.iter().find(|index| **index as usize >= count) {
+            Some(index) => Err(MeshError::IndexOutOfRange {
+                index: *index,
+                vertices_count: count,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // normals use the inverse transpose, tangent frame vectors are renormalized
+    pub fn transform(&mut self, matrix: &Matrix4<f32>) {
+        let linear: Matrix3<f32> = matrix
+            .fixed_slice::<nalgebra::U3, nalgebra::U3>(0, 0)
+            .into_owned();
+        let normal_matrix = linear
+            .try_inverse()
+            .map(|m| m.transpose())
+            .unwrap_or(linear);
+        for position in self.positions.iter_mut() {
+            *position = matrix.transform_point(&Point3::from(*position)).coords;
+        }
+        for normal in self.normals.iter_mut() {
+            *normal = (normal_matrix * *normal)
+                .try_normalize(f32::EPSILON)
+                .unwrap_or(*normal);
+        }
+        for vector in self.tangents.iter_mut().chain(self.bitangents.iter_mut()) {
+            *vector = (linear * *vector)
+                .try_normalize(f32::EPSILON)
+                .unwrap_or(*vector);
+        }
+        // mirroring flips the winding
+        if linear.determinant() < 0. {
+            for triangle in self.indices.chunks_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+    }
+
+    // appends `other`, attributes present in only one of the meshes are filled with zeros
+    pub fn merge(&mut self, other: &Mesh) {
+        let count = self.get_vertices_count();
+        let other_count = other.get_vertices_count();
+        merge_attribute(
+            &mut self.normals,
+            count,
+            &other.normals,
+            other_count,
+            Vector3::zeros(),
+        );
+        merge_attribute(
+            &mut self.tangents,
+            count,
+            &other.tangents,
+            other_count,
+            Vector3::zeros(),
+        );
+        merge_attribute(
+            &mut self.bitangents,
+            count,
+            &other.bitangents,
+            other_count,
+            Vector3::zeros(),
+        );
+        for (colors, other_colors) in self.colors.iter_mut().zip(other.colors.iter()) {
+            merge_attribute(colors, count, other_colors, other_count, Vector4::zeros());
+        }
+        for (uvs, other_uvs) in self.uvs.iter_mut().zip(other.uvs.iter()) {
+            merge_attribute(uvs, count, other_uvs, other_count, Vector2::zeros());
+        }
+        self.positions.extend_from_slice(&other.positions);
+        self.indices
+            .extend(other.indices.iter().map(|index| index + count as u32));
+    }
+
+    // smooth normals weighted by the triangle areas
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vector3::zeros(); self.get_vertices_count()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = self.get_triangle_positions(triangle);
+            let normal = (b - a).cross(&(c - a));
+            for index in triangle {
+                normals[*index as usize] += normal;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|normal| {
+                normal
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(Vector3::zeros)
+            })
+            .collect();
+    }
+
+    fn get_triangle_positions(&self, triangle: &[u32]) -> [Vector3<f32>; 3] {
+        [
+            self.positions[triangle[0] as usize],
+            self.positions[triangle[1] as usize],
+            self.positions[triangle[2] as usize],
+        ]
+    }
+
+    // quad with corners center ± u ± v facing u × v
+    fn add_quad(&mut self, center: Vector3<f32>, u: Vector3<f32>, v: Vector3<f32>) {
+        let first = self.positions.len() as u32;
+        let normal = u.cross(&v).normalize();
+        let corners = [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.)];
+        for (x, y) in corners.iter() {
+            self.positions.push(center + u * *x + v * *y);
+            self.normals.push(normal);
+            self.uvs[0].push(Vector2::new((x + 1.) * 0.5, (y + 1.) * 0.5));
+        }
+        self.indices
+            .extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+}
+
+fn merge_attribute<T: Clone>(
+    attribute: &mut Vec<T>,
+    count: usize,
+    other: &[T],
+    other_count: usize,
+    zero: T,
+) {
+    if attribute.is_empty() && other.is_empty() {
+        return;
+    }
+    attribute.resize(count, zero.clone());
+    attribute.extend_from_slice(other);
+    attribute.resize(count + other_count, zero);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_winding(mesh: &Mesh) {
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = mesh.get_triangle_positions(triangle);
+            let face_normal = (b - a).cross(&(c - a));
+            for index in triangle {
+                assert!(face_normal.dot(&mesh.normals[*index as usize]) > 0.);
+            }
+        }
+    }
+
+    #[test]
+    fn primitives_test() {
+        let primitives = [
+            Mesh::plane(2., 1.),
+            Mesh::cuboid(Vector3::new(1., 2., 3.)),
+            Mesh::sphere(1., 8, 6),
+            Mesh::cylinder(0.5, 2., 8),
+        ];
+        for mesh in primitives.iter() {
+            assert!(mesh.validate().is_ok());
+            assert_eq!(mesh.normals.len(), mesh.get_vertices_count());
+            assert_eq!(mesh.uvs[0].len(), mesh.get_vertices_count());
+            assert_eq!(mesh.indices.len() % 3, 0);
+            assert!(mesh
+                .indices
+                .iter()
+                .all(|i| (*i as usize) < mesh.get_vertices_count()));
+            check_winding(mesh);
+        }
+        assert_eq!(primitives[1].indices.len(), 36);
+        assert!(primitives[2]
+            .positions
+            .iter()
+            .all(|p| (p.norm() - 1.).abs() < 1e-5));
+
+        let mut cuboid = primitives[1].clone();
+        let normals = cuboid.normals.clone();
+        cuboid.compute_normals();
+        assert_eq!(cuboid.normals, normals);
+    }
+
+    #[test]
+    fn transform_merge_test() {
+        let mut mesh = Mesh::plane(1., 1.);
+        mesh.transform(&Matrix4::new_nonuniform_scaling(&Vector3::new(2., -1., 1.)));
+        assert!(mesh
+            .normals
+            .iter()
+            .all(|n| (n - (-Vector3::y())).norm() < 1e-6));
+        check_winding(&mesh);
+
+        let mut colored = Mesh::plane(1., 1.);
+        colored.colors[0] = vec![Vector4::new(1., 0., 0., 1.); 4];
+        mesh.merge(&colored);
+        assert_eq!(mesh.get_vertices_count(), 8);
+        assert_eq!(mesh.indices[6..], [4, 5, 6, 4, 6, 7]);
+        assert_eq!(mesh.colors[0][0], Vector4::zeros());
+        assert_eq!(mesh.colors[0][4], Vector4::new(1., 0., 0., 1.));
+        assert!(mesh.colors[1].is_empty());
+        assert!(mesh.validate().is_ok());
+
+        mesh.uvs[2].push(Vector2::zeros());
+        match mesh.validate() {
+            Err(MeshError::AttributeLength {
+                attribute,
+                expected: 8,
+                actual: 1,
+            }) => assert_eq!(attribute, "uvs[2]"),
+            result => panic!("unexpected {:?}", result),
+        }
+        mesh.uvs[2].clear();
+        mesh.indices.push(8);
+        assert!(matches!(
+            mesh.validate(),
+            Err(MeshError::IncompleteTriangle(13))
+        ));
+        mesh.indices.extend_from_slice(&[0, 1]);
+        assert!(matches!(
+            mesh.validate(),
+            Err(MeshError::IndexOutOfRange {
+                index: 8,
+                vertices_count: 8
+            })
+        ));
+    }
+}
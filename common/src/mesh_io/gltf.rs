@@ -0,0 +1,694 @@
+use crate::mesh::{Mesh, MeshError};
+use nalgebra::{Vector2, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+const BYTE: u32 = 5120;
+const UNSIGNED_BYTE: u32 = 5121;
+const SHORT: u32 = 5122;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const TRIANGLES: u32 = 4;
+
+// the subset of the glTF 2.0 schema describing mesh geometry, unknown properties are skipped
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Root {
+    asset: Asset,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scene: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    scenes: Vec<Scene>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    nodes: Vec<Node>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    meshes: Vec<GltfMesh>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    accessors: Vec<Accessor>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    buffer_views: Vec<BufferView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    buffers: Vec<Buffer>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Asset {
+    version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    generator: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Scene {
+    nodes: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Node {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mesh: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GltfMesh {
+    primitives: Vec<Primitive>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Primitive {
+    attributes: BTreeMap<String, usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    indices: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Accessor {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    buffer_view: Option<usize>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    byte_offset: usize,
+    component_type: u32,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    accessor_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min: Option<Vec<f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max: Option<Vec<f32>>,
+    #[serde(default, skip_serializing)]
+    sparse: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferView {
+    buffer: usize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    byte_offset: usize,
+    byte_length: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    byte_stride: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Buffer {
+    byte_length: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uri: Option<String>,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+fn format_error<S: ToString>(message: S) -> MeshError {
+    MeshError::InvalidFormat(message.to_string())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, MeshError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| format_error("unexpected end of the binary glTF"))
+}
+
+// external buffers are resolved relative to `base_path`
+pub fn read_gltf(data: &[u8], base_path: Option<&Path>) -> Result<Mesh, MeshError> {
+    let root: Root = serde_json::from_slice(data).map_err(format_error)?;
+    let buffers = load_buffers(&root, None, base_path)?;
+    read_root(&root, &buffers)
+}
+
+pub fn read_glb(data: &[u8], base_path: Option<&Path>) -> Result<Mesh, MeshError> {
+    if read_u32(data, 0)? != GLB_MAGIC {
+        return Err(format_error("binary glTF magic is missing"));
+    }
+    if read_u32(data, 4)? != 2 {
+        return Err(MeshError::Unsupported(format!(
+            "binary glTF version {}",
+            read_u32(data, 4)?
+        )));
+    }
+    let length = (read_u32(data, 8)? as usize).min(data.len());
+
+    let mut json = None;
+    let mut binary = None;
+    let mut offset = 12;
+    while offset < length {
+        let chunk_length = read_u32(data, offset)? as usize;
+        let chunk_type = read_u32(data, offset + 4)?;
+        let chunk = data
+            .get(offset + 8..offset + 8 + chunk_length)
+            .ok_or_else(|| format_error("binary glTF chunk is out of the file"))?;
+        match chunk_type {
+            GLB_JSON_CHUNK if json.is_none() => json = Some(chunk),
+            GLB_BIN_CHUNK if binary.is_none() => binary = Some(chunk),
+            _ => {}
+        }
+        offset += 8 + chunk_length;
+    }
+
+    let json = json.ok_or_else(|| format_error("binary glTF has no JSON chunk"))?;
+    let root: Root = serde_json::from_slice(json).map_err(format_error)?;
+    let buffers = load_buffers(&root, binary, base_path)?;
+    read_root(&root, &buffers)
+}
+
+fn load_buffers(
+    root: &Root,
+    binary: Option<&[u8]>,
+    base_path: Option<&Path>,
+) -> Result<Vec<Vec<u8>>, MeshError> {
+    let mut buffers = Vec::with_capacity(root.buffers.len());
+    for (i, buffer) in root.buffers.iter().enumerate() {
+        let data = match &buffer.uri {
+            None if i == 0 => binary
+                .map(|binary| binary.to_vec())
+                .ok_or_else(|| format_error("buffer 0 has no uri and there is no binary chunk"))?,
+            None => return Err(format_error(format!("buffer {} has no uri", i))),
+            Some(uri) if uri.starts_with("data:") => {
+                let comma = uri
+                    .find(',')
+                    .ok_or_else(|| format_error("data uri has no data"))?;
+                if !uri[..comma].ends_with(";base64") {
+                    return Err(MeshError::Unsupported(String::from(
+                        "data uri is not base64 encoded",
+                    )));
+                }
+                base64::decode(&uri[comma + 1..]).map_err(format_error)?
+            }
+            Some(uri) => {
+                let base_path = base_path.ok_or_else(|| {
+                    MeshError::Unsupported(format!("external buffer {:?} without a base path", uri))
+                })?;
+                std::fs::read(base_path.join(uri))?
+            }
+        };
+        if data.len() < buffer.byte_length {
+            return Err(format_error(format!(
+                "buffer {} is shorter than {} bytes",
+                i, buffer.byte_length
+            )));
+        }
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+fn decode_component(component_type: u32, normalized: bool, bytes: &[u8]) -> f64 {
+    match component_type {
+        BYTE if normalized => (f64::from(bytes[0] as i8) / 127.).max(-1.),
+        BYTE => f64::from(bytes[0] as i8),
+        UNSIGNED_BYTE if normalized => f64::from(bytes[0]) / 255.,
+        UNSIGNED_BYTE => f64::from(bytes[0]),
+        SHORT if normalized => {
+            (f64::from(i16::from_le_bytes([bytes[0], bytes[1]])) / 32767.).max(-1.)
+        }
+        SHORT => f64::from(i16::from_le_bytes([bytes[0], bytes[1]])),
+        UNSIGNED_SHORT if normalized => {
+            f64::from(u16::from_le_bytes([bytes[0], bytes[1]])) / 65535.
+        }
+        UNSIGNED_SHORT => f64::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+        UNSIGNED_INT => f64::from(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        _ => f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+    }
+}
+
+// components of all elements of the accessor in a row, f64 keeps u32 indices exact
+fn read_accessor(
+    root: &Root,
+    buffers: &[Vec<u8>],
+    index: usize,
+    types: &[&str],
+) -> Result<(usize, Vec<f64>), MeshError> {
+    let accessor = root
+        .accessors
+        .get(index)
+        .ok_or_else(|| format_error(format!("accessor {} doesn't exist", index)))?;
+    if accessor.sparse.is_some() {
+        return Err(MeshError::Unsupported(String::from("sparse accessors")));
+    }
+    if !types.contains(&accessor.accessor_type.as_str()) {
+        return Err(format_error(format!(
+            "accessor {} has type {} instead of {:?}",
+            index, accessor.accessor_type, types
+        )));
+    }
+    let components = match accessor.accessor_type.as_str() {
+        "SCALAR" => 1,
+        "VEC2" => 2,
+        "VEC3" => 3,
+        _ => 4,
+    };
+    let component_size = match accessor.component_type {
+        BYTE | UNSIGNED_BYTE => 1,
+        SHORT | UNSIGNED_SHORT => 2,
+        UNSIGNED_INT | FLOAT => 4,
+        component_type => {
+            return Err(format_error(format!(
+                "unknown component type {}",
+                component_type
+            )))
+        }
+    };
+
+    let view = match accessor.buffer_view {
+        Some(view) => root
+            .buffer_views
+            .get(view)
+            .ok_or_else(|| format_error(format!("buffer view {} doesn't exist", view)))?,
+        // accessors without a view are zero-initialized
+        None => return Ok((components, vec![0.; accessor.count * components])),
+    };
+    let buffer = buffers
+        .get(view.buffer)
+        .ok_or_else(|| format_error(format!("buffer {} doesn't exist", view.buffer)))?;
+    let element_size = components * component_size;
+    let stride = view.byte_stride.unwrap_or(element_size);
+    let start = view.byte_offset + accessor.byte_offset;
+    let view_end = view.byte_offset + view.byte_length;
+    let end = start + stride * accessor.count.saturating_sub(1) + element_size;
+    if accessor.count > 0 && (end > view_end || view_end > buffer.len()) {
+        return Err(format_error(format!(
+            "accessor {} is out of its buffer view",
+            index
+        )));
+    }
+
+    let mut values = Vec::with_capacity(accessor.count * components);
+    for element in 0..accessor.count {
+        for component in 0..components {
+            let offset = start + element * stride + component * component_size;
+            let bytes = &buffer[offset..offset + component_size];
+            values.push(decode_component(
+                accessor.component_type,
+                accessor.normalized,
+                bytes,
+            ));
+        }
+    }
+    Ok((components, values))
+}
+
+// triangle primitives of all meshes are merged into one, node transforms are not applied
+fn read_root(root: &Root, buffers: &[Vec<u8>]) -> Result<Mesh, MeshError> {
+    if !root.asset.version.starts_with("2.") {
+        return Err(MeshError::Unsupported(format!(
+            "glTF version {}",
+            root.asset.version
+        )));
+    }
+    let read = |attribute: &str, primitive: &Primitive, types: &[&str]| match primitive
+        .attributes
+        .get(attribute)
+    {
+        Some(index) => read_accessor(root, buffers, *index, types).map(Some),
+        None => Ok(None),
+    };
+
+    let mut mesh = Mesh::new();
+    for gltf_mesh in root.meshes.iter() {
+        for primitive in gltf_mesh.primitives.iter() {
+            if primitive.mode.unwrap_or(TRIANGLES) != TRIANGLES {
+                return Err(MeshError::Unsupported(String::from(
+                    "only triangle primitives are supported",
+                )));
+            }
+            let mut part = Mesh::new();
+            let (_, positions) = read("POSITION", primitive, &["VEC3"])?
+                .ok_or_else(|| format_error("primitive has no POSITION"))?;
+            part.positions = to_vectors3(&positions);
+            if let Some((_, normals)) = read("NORMAL", primitive, &["VEC3"])? {
+                part.normals = to_vectors3(&normals);
+            }
+            // bitangents are restored from the handedness stored in w
+            if let Some((_, tangents)) = read("TANGENT", primitive, &["VEC4"])? {
+                let tangents = to_vectors4(&tangents);
+                part.tangents = tangents.iter().map(|t| t.xyz()).collect();
+                if part.normals.len() == tangents.len() {
+                    part.bitangents = part
+                        .normals
+                        .iter()
+                        .zip(tangents.iter())
+                        .map(|(n, t)| n.cross(&t.xyz()) * t.w)
+                        .collect();
+                }
+            }
+            for (set, uvs) in part.uvs.iter_mut().enumerate() {
+                if let Some((_, values)) = read(&format!("TEXCOORD_{}", set), primitive, &["VEC2"])?
+                {
+                    *uvs = values
+                        .chunks_exact(2)
+                        .map(|c| Vector2::new(c[0] as f32, c[1] as f32))
+                        .collect();
+                }
+            }
+            for (set, colors) in part.colors.iter_mut().enumerate() {
+                match read(&format!("COLOR_{}", set), primitive, &["VEC3", "VEC4"])? {
+                    Some((3, values)) => {
+                        *colors = to_vectors3(&values)
+                            .into_iter()
+                            .map(|c| Vector4::new(c.x, c.y, c.z, 1.))
+                            .collect()
+                    }
+                    Some((_, values)) => *colors = to_vectors4(&values),
+                    None => {}
+                }
+            }
+            part.indices = match primitive.indices {
+                Some(index) => read_accessor(root, buffers, index, &["SCALAR"])?
+                    .1
+                    .into_iter()
+                    .map(|index| index as u32)
+                    .collect(),
+                None => (0..part.positions.len() as u32).collect(),
+            };
+            part.validate()?;
+            mesh.merge(&part);
+        }
+    }
+    Ok(mesh)
+}
+
+fn to_vectors3(values: &[f64]) -> Vec<Vector3<f32>> {
+    values
+        .chunks_exact(3)
+        .map(|c| Vector3::new(c[0] as f32, c[1] as f32, c[2] as f32))
+        .collect()
+}
+
+fn to_vectors4(values: &[f64]) -> Vec<Vector4<f32>> {
+    values
+        .chunks_exact(4)
+        .map(|c| Vector4::new(c[0] as f32, c[1] as f32, c[2] as f32, c[3] as f32))
+        .collect()
+}
+
+// one buffer holding every attribute in its own tightly packed view
+struct RootBuilder {
+    root: Root,
+    data: Vec<u8>,
+}
+
+impl RootBuilder {
+    fn add_view(&mut self, bytes: &[u8], target: u32) -> usize {
+        self.root.buffer_views.push(BufferView {
+            buffer: 0,
+            byte_offset: self.data.len(),
+            byte_length: bytes.len(),
+            byte_stride: None,
+            target: Some(target),
+        });
+        self.data.extend_from_slice(bytes);
+        self.root.buffer_views.len() - 1
+    }
+
+    fn add_accessor(&mut self, accessor: Accessor) -> usize {
+        self.root.accessors.push(accessor);
+        self.root.accessors.len() - 1
+    }
+
+    fn add_floats(
+        &mut self,
+        accessor_type: &str,
+        count: usize,
+        values: &[f32],
+        bounds: bool,
+    ) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes().to_vec())
+            .collect();
+        let view = self.add_view(&bytes, ARRAY_BUFFER);
+        let components = values.len() / count;
+        let bound = |select: fn(f32, f32) -> f32| {
+            (0..components)
+                .map(|component| {
+                    values
+                        .iter()
+                        .skip(component)
+                        .step_by(components)
+                        .copied()
+                        .fold(values[component], select)
+                })
+                .collect()
+        };
+        self.add_accessor(Accessor {
+            buffer_view: Some(view),
+            byte_offset: 0,
+            component_type: FLOAT,
+            normalized: false,
+            count,
+            accessor_type: String::from(accessor_type),
+            min: if bounds { Some(bound(f32::min)) } else { None },
+            max: if bounds { Some(bound(f32::max)) } else { None },
+            sparse: None,
+        })
+    }
+
+    fn add_indices(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices
+            .iter()
+            .flat_map(|index| index.to_le_bytes().to_vec())
+            .collect();
+        let view = self.add_view(&bytes, ELEMENT_ARRAY_BUFFER);
+        self.add_accessor(Accessor {
+            buffer_view: Some(view),
+            byte_offset: 0,
+            component_type: UNSIGNED_INT,
+            normalized: false,
+            count: indices.len(),
+            accessor_type: String::from("SCALAR"),
+            min: None,
+            max: None,
+            sparse: None,
+        })
+    }
+}
+
+// one mesh with one primitive, tangents are written only together with normals
+// and bitangents are kept as the tangent handedness
+fn build_root(mesh: &Mesh) -> Result<(Root, Vec<u8>), MeshError> {
+    mesh.validate()?;
+    let mut builder = RootBuilder {
+        root: Root {
+            asset: Asset {
+                version: String::from("2.0"),
+                generator: Some(String::from("common::mesh_io")),
+            },
+            ..Root::default()
+        },
+        data: Vec::new(),
+    };
+    let count = mesh.get_vertices_count();
+    if count == 0 {
+        return Ok((builder.root, builder.data));
+    }
+
+    let flatten3 = |vectors: &[Vector3<f32>]| -> Vec<f32> {
+        vectors
+            .iter()
+            .flat_map(|v| v.iter().copied().collect::<Vec<f32>>())
+            .collect()
+    };
+    let mut attributes = BTreeMap::new();
+    attributes.insert(
+        String::from("POSITION"),
+        builder.add_floats("VEC3", count, &flatten3(&mesh.positions), true),
+    );
+    if !mesh.normals.is_empty() {
+        attributes.insert(
+            String::from("NORMAL"),
+            builder.add_floats("VEC3", count, &flatten3(&mesh.normals), false),
+        );
+        if !mesh.tangents.is_empty() {
+            let mut tangents = Vec::with_capacity(count * 4);
+            for (i, (n, t)) in mesh.normals.iter().zip(mesh.tangents.iter()).enumerate() {
+                let negative = matches!(mesh.bitangents.get(i), Some(b) if n.cross(t).dot(b) < 0.);
+                tangents.extend_from_slice(&[t.x, t.y, t.z, if negative { -1. } else { 1. }]);
+            }
+            attributes.insert(
+                String::from("TANGENT"),
+                builder.add_floats("VEC4", count, &tangents, false),
+            );
+        }
+    }
+    for (set, uvs) in mesh
+        .uvs
+        .iter()
+        .enumerate()
+        .filter(|(_, uvs)| !uvs.is_empty())
+    {
+        let values: Vec<f32> = uvs.iter().flat_map(|uv| vec![uv.x, uv.y]).collect();
+        attributes.insert(
+            format!("TEXCOORD_{}", set),
+            builder.add_floats("VEC2", count, &values, false),
+        );
+    }
+    for (set, colors) in mesh
+        .colors
+        .iter()
+        .enumerate()
+        .filter(|(_, colors)| !colors.is_empty())
+    {
+        let values: Vec<f32> = colors
+            .iter()
+            .flat_map(|c| c.iter().copied().collect::<Vec<f32>>())
+            .collect();
+        attributes.insert(
+            format!("COLOR_{}", set),
+            builder.add_floats("VEC4", count, &values, false),
+        );
+    }
+    let indices = if mesh.indices.is_empty() {
+        None
+    } else {
+        Some(builder.add_indices(&mesh.indices))
+    };
+
+    builder.root.meshes.push(GltfMesh {
+        primitives: vec![Primitive {
+            attributes,
+            indices,
+            mode: None,
+        }],
+    });
+    builder.root.nodes.push(Node { mesh: Some(0) });
+    builder.root.scenes.push(Scene { nodes: vec![0] });
+    builder.root.scene = Some(0);
+    builder.root.buffers.push(Buffer {
+        byte_length: builder.data.len(),
+        uri: None,
+    });
+    Ok((builder.root, builder.data))
+}
+
+// the buffer is embedded as a base64 data uri
+pub fn write_gltf<W: Write>(mesh: &Mesh, writer: &mut W) -> Result<(), MeshError> {
+    let (mut root, data) = build_root(mesh)?;
+    if let Some(buffer) = root.buffers.first_mut() {
+        buffer.uri = Some(format!(
+            "data:application/octet-stream;base64,{}",
+            base64::encode(&data)
+        ));
+    }
+    serde_json::to_writer_pretty(writer, &root).map_err(format_error)
+}
+
+pub fn write_glb<W: Write>(mesh: &Mesh, writer: &mut W) -> Result<(), MeshError> {
+    let (root, mut data) = build_root(mesh)?;
+    let mut json = serde_json::to_vec(&root).map_err(format_error)?;
+    // chunks are 4-byte aligned, json is padded with spaces and binary data with zeros
+    while json.len() % 4 != 0 {
+        json.push(b' ');
+    }
+    while data.len() % 4 != 0 {
+        data.push(0);
+    }
+
+    let mut length = 12 + 8 + json.len();
+    if !data.is_empty() {
+        length += 8 + data.len();
+    }
+    for value in [
+        GLB_MAGIC,
+        2,
+        length as u32,
+        json.len() as u32,
+        GLB_JSON_CHUNK,
+    ]
+    .iter()
+    {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.write_all(&json)?;
+    if !data.is_empty() {
+        writer.write_all(&(data.len() as u32).to_le_bytes())?;
+        writer.write_all(&GLB_BIN_CHUNK.to_le_bytes())?;
+        writer.write_all(&data)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_mesh() -> Mesh {
+        let mut mesh = Mesh::cuboid(Vector3::new(1., 2., 3.));
+        mesh.generate_tangents().unwrap();
+        let count = mesh.get_vertices_count();
+        mesh.colors[1] = (0..count)
+            .map(|i| Vector4::new(i as f32 / count as f32, 0.5, 0., 1.))
+            .collect();
+        mesh.uvs[3] = (0..count).map(|i| Vector2::new(i as f32, -1.)).collect();
+        mesh
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let mesh = test_mesh();
+        let mut gltf = Vec::new();
+        write_gltf(&mesh, &mut gltf).unwrap();
+        assert_eq!(read_gltf(&gltf, None).unwrap(), mesh);
+
+        let mut glb = Vec::new();
+        write_glb(&mesh, &mut glb).unwrap();
+        assert_eq!(glb.len() % 4, 0);
+        assert_eq!(read_glb(&glb, None).unwrap(), mesh);
+
+        let mut empty = Vec::new();
+        write_glb(&Mesh::new(), &mut empty).unwrap();
+        assert_eq!(read_glb(&empty, None).unwrap(), Mesh::new());
+    }
+
+    #[test]
+    fn invalid_files_test() {
+        let mut gltf = Vec::new();
+        write_gltf(&Mesh::plane(1., 1.), &mut gltf).unwrap();
+        let text = String::from_utf8(gltf).unwrap();
+
+        let out_of_view = text.replacen("\"count\": 4", "\"count\": 40", 1);
+        assert!(matches!(
+            read_gltf(out_of_view.as_bytes(), None),
+            Err(MeshError::InvalidFormat(_))
+        ));
+        let lines = text.replacen(
+            "\"primitives\": [\n        {",
+            "\"primitives\": [\n        {\n          \"mode\": 1,",
+            1,
+        );
+        assert!(matches!(
+            read_gltf(lines.as_bytes(), None),
+            Err(MeshError::Unsupported(_))
+        ));
+        assert!(matches!(
+            read_glb(text.as_bytes(), None),
+            Err(MeshError::InvalidFormat(_))
+        ));
+
+        let mut mesh = Mesh::plane(1., 1.);
+        mesh.indices[0] = 7;
+        assert!(matches!(
+            write_glb(&mesh, &mut Vec::new()),
+            Err(MeshError::IndexOutOfRange { index: 7, .. })
+        ));
+    }
+}
@@ -0,0 +1,40 @@
+pub mod gltf;
+pub mod obj;
+
+use crate::mesh::{Mesh, MeshError};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+fn get_extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+// the format is picked by the file extension: obj, gltf or glb
+pub fn load_mesh(path: &Path) -> Result<Mesh, MeshError> {
+    match get_extension(path).as_str() {
+        "obj" => obj::read_obj(BufReader::new(File::open(path)?)),
+        "gltf" => gltf::read_gltf(&std::fs::read(path)?, path.parent()),
+        "glb" => gltf::read_glb(&std::fs::read(path)?, path.parent()),
+        extension => Err(MeshError::Unsupported(format!(
+            "unknown mesh file extension {:?}",
+            extension
+        ))),
+    }
+}
+
+pub fn save_mesh(mesh: &Mesh, path: &Path) -> Result<(), MeshError> {
+    let writer = || File::create(path).map(BufWriter::new);
+    match get_extension(path).as_str() {
+        "obj" => obj::write_obj(mesh, &mut writer()?),
+        "gltf" => gltf::write_gltf(mesh, &mut writer()?),
+        "glb" => gltf::write_glb(mesh, &mut writer()?),
+        extension => Err(MeshError::Unsupported(format!(
+            "unknown mesh file extension {:?}",
+            extension
+        ))),
+    }
+}
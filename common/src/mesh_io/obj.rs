@@ -0,0 +1,262 @@
+use crate::mesh::{Mesh, MeshError};
+use nalgebra::{Vector2, Vector3, Vector4};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+fn parse_error(line: usize, message: String) -> MeshError {
+    MeshError::Parse { line, message }
+}
+
+fn parse_floats<'a, I: Iterator<Item = &'a str>>(
+    tokens: I,
+    line: usize,
+) -> Result<Vec<f32>, MeshError> {
+    tokens
+        .map(|token| {
+            token
+                .parse::<f32>()
+                .map_err(|_| parse_error(line, format!("{:?} is not a number", token)))
+        })
+        .collect()
+}
+
+// obj indices are one-based, negative ones count from the end of the list
+fn resolve_index(token: &str, count: usize, line: usize) -> Result<usize, MeshError> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| parse_error(line, format!("{:?} is not an index", token)))?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved >= 0 && (resolved as usize) < count {
+        Ok(resolved as usize)
+    } else {
+        Err(parse_error(
+            line,
+            format!("index {} is out of {} elements", index, count),
+        ))
+    }
+}
+
+// positions, vertex colors, uvs[0] and normals are read, vertices are ordered by their first use,
+// groups, materials and free-form geometry are ignored, polygons are triangulated as fans
+pub fn read_obj<R: BufRead>(reader: R) -> Result<Mesh, MeshError> {
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+    let mut vertices: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+    let mut has_uvs = false;
+    let mut has_normals = false;
+    let mut mesh = Mesh::new();
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = number + 1;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let values = parse_floats(tokens, line_number)?;
+                match values.len() {
+                    3 | 4 => positions.push(Vector3::new(values[0], values[1], values[2])),
+                    6 | 7 => {
+                        positions.push(Vector3::new(values[0], values[1], values[2]));
+                        let alpha = values.get(6).copied().unwrap_or(1.);
+                        colors.push(Vector4::new(values[3], values[4], values[5], alpha));
+                    }
+                    count => {
+                        return Err(parse_error(
+                            line_number,
+                            format!("vertex has {} values", count),
+                        ))
+                    }
+                }
+            }
+            Some("vt") => {
+                let values = parse_floats(tokens, line_number)?;
+                if values.is_empty() || values.len() > 3 {
+                    return Err(parse_error(
+                        line_number,
+                        format!("uv has {} values", values.len()),
+                    ));
+                }
+                uvs.push(Vector2::new(
+                    values[0],
+                    values.get(1).copied().unwrap_or(0.),
+                ));
+            }
+            Some("vn") => {
+                let values = parse_floats(tokens, line_number)?;
+                if values.len() != 3 {
+                    return Err(parse_error(
+                        line_number,
+                        format!("normal has {} values", values.len()),
+                    ));
+                }
+                normals.push(Vector3::new(values[0], values[1], values[2]));
+            }
+            Some("f") => {
+                let mut face = Vec::new();
+                for corner in tokens {
+                    let mut parts = corner.split('/');
+                    let position = resolve_index(
+                        parts.next().unwrap_or_default(),
+                        positions.len(),
+                        line_number,
+                    )?;
+                    let uv = match parts.next() {
+                        Some(token) if !token.is_empty() => {
+                            Some(resolve_index(token, uvs.len(), line_number)?)
+                        }
+                        _ => None,
+                    };
+                    let normal = match parts.next() {
+                        Some(token) if !token.is_empty() => {
+                            Some(resolve_index(token, normals.len(), line_number)?)
+                        }
+                        _ => None,
+                    };
+                    has_uvs = has_uvs || uv.is_some();
+                    has_normals = has_normals || normal.is_some();
+
+                    let key = (position, uv, normal);
+                    let index = match vertices.get(&key) {
+                        Some(index) => *index,
+                        None => {
+                            let index = mesh.positions.len() as u32;
+                            mesh.positions.push(positions[position]);
+                            mesh.colors[0].push(
+                                colors
+                                    .get(position)
+                                    .copied()
+                                    .unwrap_or_else(|| Vector4::new(1., 1., 1., 1.)),
+                            );
+                            mesh.uvs[0].push(uv.map(|uv| uvs[uv]).unwrap_or_else(Vector2::zeros));
+                            mesh.normals.push(
+                                normal
+                                    .map(|normal| normals[normal])
+                                    .unwrap_or_else(Vector3::zeros),
+                            );
+                            vertices.insert(key, index);
+                            index
+                        }
+                    };
+                    face.push(index);
+                }
+                if face.len() < 3 {
+                    return Err(parse_error(
+                        line_number,
+                        format!("face has {} vertices", face.len()),
+                    ));
+                }
+                for i in 1..face.len() - 1 {
+                    mesh.indices
+                        .extend_from_slice(&[face[0], face[i], face[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if colors.is_empty() {
+        mesh.colors[0].clear();
+    }
+    if !has_uvs {
+        mesh.uvs[0].clear();
+    }
+    if !has_normals {
+        mesh.normals.clear();
+    }
+    mesh.validate()?;
+    Ok(mesh)
+}
+
+// obj keeps only positions, rgb of colors[0], uvs[0] and normals
+pub fn write_obj<W: Write>(mesh: &Mesh, writer: &mut W) -> Result<(), MeshError> {
+    mesh.validate()?;
+    let has_colors = !mesh.colors[0].is_empty();
+    let has_uvs = !mesh.uvs[0].is_empty();
+    let has_normals = !mesh.normals.is_empty();
+
+    for (i, p) in mesh.positions.iter().enumerate() {
+        if has_colors {
+            let c = mesh.colors[0][i];
+            writeln!(writer, "v {} {} {} {} {} {}", p.x, p.y, p.z, c.x, c.y, c.z)?;
+        } else {
+            writeln!(writer, "v {} {} {}", p.x, p.y, p.z)?;
+        }
+    }
+    for uv in mesh.uvs[0].iter() {
+        writeln!(writer, "vt {} {}", uv.x, uv.y)?;
+    }
+    for n in mesh.normals.iter() {
+        writeln!(writer, "vn {} {} {}", n.x, n.y, n.z)?;
+    }
+
+    for triangle in mesh.indices.chunks_exact(3) {
+        write!(writer, "f")?;
+        for index in triangle {
+            let index = index + 1;
+            match (has_uvs, has_normals) {
+                (false, false) => write!(writer, " {}", index)?,
+                (true, false) => write!(writer, " {}/{}", index, index)?,
+                (false, true) => write!(writer, " {}//{}", index, index)?,
+                (true, true) => write!(writer, " {}/{}/{}", index, index, index)?,
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUAD: &str = "
+# quad with a shared uv
+o quad
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 1
+vn 0 0 1
+f 1/1/1 2/1/1 3/2/1 -1/2/-1
+";
+
+    #[test]
+    fn read_test() {
+        let mesh = read_obj(QUAD.as_bytes()).unwrap();
+        assert_eq!(mesh.get_vertices_count(), 4);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.uvs[0][3], Vector2::new(1., 1.));
+        assert_eq!(mesh.normals, vec![Vector3::z(); 4]);
+        assert!(mesh.colors[0].is_empty());
+
+        let error = read_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n".as_bytes());
+        assert!(matches!(error, Err(MeshError::Parse { line: 3, .. })));
+        assert!(matches!(
+            read_obj("vn 0 x 1\n".as_bytes()),
+            Err(MeshError::Parse { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let mut mesh = Mesh::cuboid(Vector3::new(1., 2., 3.));
+        mesh.colors[0] = vec![Vector4::new(0.25, 0.5, 1., 1.); mesh.get_vertices_count()];
+        let mut buffer = Vec::new();
+        write_obj(&mesh, &mut buffer).unwrap();
+        assert_eq!(read_obj(buffer.as_slice()).unwrap(), mesh);
+
+        mesh.normals.pop();
+        assert!(matches!(
+            write_obj(&mesh, &mut Vec::new()),
+            Err(MeshError::AttributeLength { .. })
+        ));
+    }
+}
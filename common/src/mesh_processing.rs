@@ -0,0 +1,595 @@
+use crate::mesh::{Mesh, MeshError};
+use nalgebra::Vector3;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+
+// the cache size the vertex order is optimized for, matches most of the desktop GPUs
+pub const VERTEX_CACHE_SIZE: usize = 32;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BoundingBox {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl BoundingBox {
+    pub fn get_center(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn get_size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BoundingSphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct MeshStatistics {
+    pub vertices_count: usize,
+    pub triangles_count: usize,
+    // triangles with repeated indices or zero area
+    pub degenerate_triangles_count: usize,
+    pub unused_vertices_count: usize,
+    pub surface_area: f32,
+    // average cache miss ratio for `VERTEX_CACHE_SIZE`
+    pub acmr: f32,
+}
+
+// per-corner MikkTSpace output
+struct TangentSpaceGeometry<'a> {
+    mesh: &'a Mesh,
+    tangents: Vec<[f32; 4]>,
+}
+
+impl<'a> TangentSpaceGeometry<'a> {
+    fn get_index(&self, face: usize, vert: usize) -> usize {
+        self.mesh.indices[face * 3 + vert] as usize
+    }
+}
+
+impl<'a> mikktspace::Geometry for TangentSpaceGeometry<'a> {
+    fn num_faces(&self) -> usize {
+        self.mesh.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        let p = self.mesh.positions[self.get_index(face, vert)];
+        [p.x, p.y, p.z]
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        let n = self.mesh.normals[self.get_index(face, vert)];
+        [n.x, n.y, n.z]
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        let uv = self.mesh.uvs[0][self.get_index(face, vert)];
+        [uv.x, uv.y]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = tangent;
+    }
+}
+
+fn get_vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.;
+    }
+    // the last triangle's vertices get a fixed score, so the next triangle doesn't reuse all of them
+    let cache_score = match cache_position {
+        Some(position) if position < 3 => 0.75,
+        Some(position) => (1. - (position - 3) as f32 / (VERTEX_CACHE_SIZE - 3) as f32).powf(1.5),
+        None => 0.,
+    };
+    // vertices with few triangles left are finished first
+    cache_score + 2. / (remaining_triangles as f32).sqrt()
+}
+
+fn compare_scores(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+impl Mesh {
+    // MikkTSpace tangents, the convention of the most of baking tools and glTF renderers,
+    // vertices shared by triangles with different tangents are split and unused vertices are dropped
+    pub fn generate_tangents(&mut self) -> Result<(), MeshError> {
+        self.validate()?;
+        let count = self.get_vertices_count();
+        if self.normals.len() != count {
+            return Err(MeshError::MissingAttribute(String::from("normals")));
+        }
+        if self.uvs[0].len() != count {
+            return Err(MeshError::MissingAttribute(String::from("uvs[0]")));
+        }
+
+        let mut geometry = TangentSpaceGeometry {
+            mesh: self,
+            tangents: vec![[0.; 4]; self.indices.len()],
+        };
+        if !mikktspace::generate_tangents(&mut geometry) {
+            return Err(MeshError::InvalidFormat(String::from(
+                "tangent space can't be generated",
+            )));
+        }
+        let tangents = geometry.tangents;
+
+        let corners: Vec<usize> = self.indices.iter().map(|index| *index as usize).collect();
+        self.reorder_vertices(&corners);
+        self.indices = (0..corners.len() as u32).collect();
+        self.tangents = tangents
+            .iter()
+            .map(|t| Vector3::new(t[0], t[1], t[2]))
+            .collect();
+        self.bitangents = self
+            .normals
+            .iter()
+            .zip(tangents.iter())
+            .map(|(n, t)| n.cross(&Vector3::new(t[0], t[1], t[2])) * t[3])
+            .collect();
+        self.weld(0.)?;
+        Ok(())
+    }
+
+    // merges vertices whose attributes differ by at most `tolerance` per component,
+    // drops unused vertices and triangles which became degenerate,
+    // returns the number of removed vertices
+    pub fn weld(&mut self, tolerance: f32) -> Result<usize, MeshError> {
+        self.validate()?;
+        let count = self.get_vertices_count();
+        let mut used = vec![false; count];
+        for index in self.indices.iter() {
+            used[*index as usize] = true;
+        }
+
+        // any two welded positions are in the same or in the neighbour cells
+        let cell_size = if tolerance > 0. { tolerance } else { 1. };
+        let neighbours = if tolerance > 0. { -1..=1 } else { 0..=0 };
+        let get_cell = |i: usize| {
+            let p = self.positions[i] / cell_size;
+            [p.x.floor() as i64, p.y.floor() as i64, p.z.floor() as i64]
+        };
+
+        let mut cells: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        let mut components: Vec<Vec<f32>> = Vec::new();
+        let mut order = Vec::new();
+        let mut remap = vec![0; count];
+        for vertex in (0..count).filter(|vertex| used[*vertex]) {
+            let vertex_components = self.get_vertex_components(vertex);
+            let cell = get_cell(vertex);
+            let mut found = None;
+            'search: for x in neighbours.clone() {
+                for y in neighbours.clone() {
+                    for z in neighbours.clone() {
+                        let key = [cell[0] + x, cell[1] + y, cell[2] + z];
+                        for candidate in cells.get(&key).into_iter().flatten() {
+                            let equal = components[*candidate]
+                                .iter()
+                                .zip(vertex_components.iter())
+                                .all(|(a, b)| (a - b).abs() <= tolerance);
+                            if equal {
+                                found = Some(*candidate);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+
+            remap[vertex] = match found {
+                Some(welded) => welded as u32,
+                None => {
+                    cells.entry(cell).or_default().push(order.len());
+                    components.push(vertex_components);
+                    order.push(vertex);
+                    order.len() as u32 - 1
+                }
+            };
+        }
+
+        self.reorder_vertices(&order);
+        let indices: Vec<u32> = self
+            .indices
+            .iter()
+            .map(|index| remap[*index as usize])
+            .collect();
+        self.indices = indices
+            .chunks_exact(3)
+            .filter(|t| t[0] != t[1] && t[1] != t[2] && t[0] != t[2])
+            .flatten()
+            .copied()
+            .collect();
+        Ok(count - order.len())
+    }
+
+    // Forsyth's triangle reordering for the post-transform cache,
+    // then vertices are reordered by their first use for the pre-transform fetch
+    pub fn optimize_vertex_cache(&mut self) -> Result<(), MeshError> {
+        self.validate()?;
+        let count = self.get_vertices_count();
+        let triangles_count = self.indices.len() / 3;
+        let mut vertex_triangles = vec![Vec::new(); count];
+        for (triangle, indices) in self.indices.chunks_exact(3).enumerate() {
+            for index in indices {
+                vertex_triangles[*index as usize].push(triangle);
+            }
+        }
+
+        let mut remaining: Vec<usize> = vertex_triangles.iter().map(Vec::len).collect();
+        let mut cache_positions: Vec<Option<usize>> = vec![None; count];
+        let mut vertex_scores: Vec<f32> = remaining
+            .iter()
+            .map(|remaining| get_vertex_score(None, *remaining))
+            .collect();
+        let get_triangle_score = |triangle: usize, vertex_scores: &[f32]| -> f32 {
+            self.indices[3 * triangle..3 * triangle + 3]
+                .iter()
+                .map(|index| vertex_scores[*index as usize])
+                .sum()
+        };
+        let mut triangle_scores: Vec<f32> = (0..triangles_count)
+            .map(|triangle| get_triangle_score(triangle, &vertex_scores))
+            .collect();
+
+        let mut emitted = vec![false; triangles_count];
+        let mut cache: Vec<u32> = Vec::with_capacity(VERTEX_CACHE_SIZE + 3);
+        let mut indices = Vec::with_capacity(self.indices.len());
+        let mut next_triangle = 0;
+        for _ in 0..triangles_count {
+            // the best triangle using the cached vertices, the next one in the original order otherwise
+            let best = cache
+                .iter()
+                .flat_map(|vertex| vertex_triangles[*vertex as usize].iter())
+                .filter(|triangle| !emitted[**triangle])
+                .max_by(|a, b| compare_scores(triangle_scores[**a], triangle_scores[**b]))
+                .copied();
+            let best = match best {
+                Some(best) => best,
+                None => {
+                    while emitted[next_triangle] {
+                        next_triangle += 1;
+                    }
+                    next_triangle
+                }
+            };
+            emitted[best] = true;
+            let triangle = &self.indices[3 * best..3 * best + 3];
+            indices.extend_from_slice(triangle);
+            for vertex in triangle {
+                remaining[*vertex as usize] -= 1;
+            }
+
+            let mut new_cache: Vec<u32> = Vec::with_capacity(VERTEX_CACHE_SIZE + 3);
+            for vertex in triangle.iter().chain(cache.iter()) {
+                if !new_cache.contains(vertex) {
+                    new_cache.push(*vertex);
+                }
+            }
+            for vertex in new_cache.iter().skip(VERTEX_CACHE_SIZE) {
+                cache_positions[*vertex as usize] = None;
+            }
+            new_cache.truncate(VERTEX_CACHE_SIZE);
+            for (position, vertex) in new_cache.iter().enumerate() {
+                cache_positions[*vertex as usize] = Some(position);
+            }
+
+            for vertex in cache.iter().chain(triangle.iter()) {
+                let vertex = *vertex as usize;
+                vertex_scores[vertex] =
+                    get_vertex_score(cache_positions[vertex], remaining[vertex]);
+            }
+            for vertex in cache.iter().chain(triangle.iter()) {
+                for triangle in vertex_triangles[*vertex as usize].iter() {
+                    if !emitted[*triangle] {
+                        triangle_scores[*triangle] = get_triangle_score(*triangle, &vertex_scores);
+                    }
+                }
+            }
+            cache = new_cache;
+        }
+
+        self.indices = indices;
+        self.reorder_by_first_use();
+        Ok(())
+    }
+
+    // misses of a FIFO cache per triangle, from 0.5 for regular grids to 3 for no reuse at all
+    pub fn get_acmr(&self, cache_size: usize) -> f32 {
+        let triangles_count = self.indices.len() / 3;
+        if triangles_count == 0 {
+            return 0.;
+        }
+        let mut cache = VecDeque::with_capacity(cache_size + 1);
+        let mut misses = 0;
+        for index in self.indices.iter() {
+            if !cache.contains(index) {
+                misses += 1;
+                cache.push_back(*index);
+                if cache.len() > cache_size {
+                    cache.pop_front();
+                }
+            }
+        }
+        misses as f32 / triangles_count as f32
+    }
+
+    pub fn get_bounding_box(&self) -> Option<BoundingBox> {
+        let first = *self.positions.first()?;
+        let initial = BoundingBox {
+            min: first,
+            max: first,
+        };
+        Some(
+            self.positions
+                .iter()
+                .fold(initial, |bounds, p| BoundingBox {
+                    min: bounds.min.zip_map(p, f32::min),
+                    max: bounds.max.zip_map(p, f32::max),
+                }),
+        )
+    }
+
+    // Ritter's approximation, contains all positions but is slightly larger than the minimal sphere
+    pub fn get_bounding_sphere(&self) -> Option<BoundingSphere> {
+        let first = *self.positions.first()?;
+        let get_farthest = |from: Vector3<f32>| {
+            self.positions
+                .iter()
+                .max_by(|a, b| {
+                    compare_scores((*a - from).norm_squared(), (*b - from).norm_squared())
+                })
+                .copied()
+                .unwrap_or(from)
+        };
+        let a = get_farthest(first);
+        let b = get_farthest(a);
+        let mut sphere = BoundingSphere {
+            center: (a + b) * 0.5,
+            radius: (b - a).norm() * 0.5,
+        };
+        for p in self.positions.iter() {
+            let distance = (p - sphere.center).norm();
+            if distance > sphere.radius {
+                let radius = (sphere.radius + distance) * 0.5;
+                sphere.center += (p - sphere.center) * ((radius - sphere.radius) / distance);
+                sphere.radius = radius;
+            }
+        }
+        Some(sphere)
+    }
+
+    pub fn get_statistics(&self) -> MeshStatistics {
+        let mut used = vec![false; self.get_vertices_count()];
+        let mut degenerate_triangles_count = 0;
+        let mut surface_area = 0.;
+        for triangle in self.indices.chunks_exact(3) {
+            let p: Vec<Vector3<f32>> = triangle
+                .iter()
+                .map(|index| {
+                    used[*index as usize] = true;
+                    self.positions[*index as usize]
+                })
+                .collect();
+            let area = (p[1] - p[0]).cross(&(p[2] - p[0])).norm() * 0.5;
+            if area <= f32::EPSILON
+                || triangle[0] == triangle[1]
+                || triangle[1] == triangle[2]
+                || triangle[0] == triangle[2]
+            {
+                degenerate_triangles_count += 1;
+            }
+            surface_area += area;
+        }
+
+        MeshStatistics {
+            vertices_count: self.get_vertices_count(),
+            triangles_count: self.indices.len() / 3,
+            degenerate_triangles_count,
+            unused_vertices_count: used.iter().filter(|used| !**used).count(),
+            surface_area,
+            acmr: self.get_acmr(VERTEX_CACHE_SIZE),
+        }
+    }
+
+    fn get_vertex_components(&self, vertex: usize) -> Vec<f32> {
+        let mut components: Vec<f32> = self.positions[vertex].iter().copied().collect();
+        for attribute in [&self.normals, &self.tangents, &self.bitangents].iter() {
+            if let Some(v) = attribute.get(vertex) {
+                components.extend(v.iter());
+            }
+        }
+        for colors in self.colors.iter() {
+            if let Some(c) = colors.get(vertex) {
+                components.extend(c.iter());
+            }
+        }
+        for uvs in self.uvs.iter() {
+            if let Some(uv) = uvs.get(vertex) {
+                components.extend(uv.iter());
+            }
+        }
+        components
+    }
+
+    // vertex `i` of the result is the vertex `order[i]`, indices are not changed
+    fn reorder_vertices(&mut self, order: &[usize]) {
+        fn reorder<T: Copy>(attribute: &mut Vec<T>, order: &[usize]) {
+            if !attribute.is_empty() {
+                *attribute = order.iter().map(|i| attribute[*i]).collect();
+            }
+        }
+        reorder(&mut self.positions, order);
+        reorder(&mut self.normals, order);
+        reorder(&mut self.tangents, order);
+        reorder(&mut self.bitangents, order);
+        for colors in self.colors.iter_mut() {
+            reorder(colors, order);
+        }
+        for uvs in self.uvs.iter_mut() {
+            reorder(uvs, order);
+        }
+    }
+
+    // unused vertices are kept at the end
+    fn reorder_by_first_use(&mut self) {
+        let count = self.get_vertices_count();
+        let mut remap = vec![None; count];
+        let mut order = Vec::with_capacity(count);
+        for index in self.indices.iter_mut() {
+            let vertex = *index as usize;
+            let new_index = *remap[vertex].get_or_insert_with(|| {
+                order.push(vertex);
+                order.len() as u32 - 1
+            });
+            *index = new_index;
+        }
+        order.extend((0..count).filter(|vertex| remap[*vertex].is_none()));
+        self.reorder_vertices(&order);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_triangles(mesh: &Mesh) -> Vec<[[u32; 3]; 3]> {
+        let mut triangles: Vec<[[u32; 3]; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| {
+                let p = |i: usize| {
+                    let p = mesh.positions[t[i] as usize];
+                    [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]
+                };
+                [p(0), p(1), p(2)]
+            })
+            .collect();
+        triangles.sort();
+        triangles
+    }
+
+    #[test]
+    fn weld_test() {
+        let mut cuboid = Mesh::cuboid(Vector3::new(1., 2., 3.));
+        assert_eq!(cuboid.weld(0.).unwrap(), 0);
+        cuboid.normals.clear();
+        cuboid.uvs[0].clear();
+        assert_eq!(cuboid.weld(0.).unwrap(), 16);
+        assert_eq!(cuboid.indices.len(), 36);
+        assert!(cuboid.validate().is_ok());
+
+        let mut planes = Mesh::plane(1., 1.);
+        let mut shifted = Mesh::plane(1., 1.);
+        shifted.positions.iter_mut().for_each(|p| p.y += 1e-4);
+        planes.merge(&shifted);
+        assert_eq!(planes.clone().weld(0.).unwrap(), 0);
+        assert_eq!(planes.weld(1e-3).unwrap(), 4);
+        assert_eq!(planes.indices.len(), 12);
+
+        // broken indices are reported instead of panicking
+        planes.indices[0] = 100;
+        assert!(matches!(
+            planes.weld(0.),
+            Err(MeshError::IndexOutOfRange { index: 100, .. })
+        ));
+        assert!(planes.optimize_vertex_cache().is_err());
+    }
+
+    #[test]
+    fn optimize_vertex_cache_test() {
+        let mut sphere = Mesh::sphere(1., 32, 32);
+        let triangles_count = sphere.indices.len() / 3;
+        let shuffled: Vec<u32> = (0..triangles_count)
+            .flat_map(|i| {
+                let t = i * 7919 % triangles_count;
+                sphere.indices[3 * t..3 * t + 3].to_vec()
+            })
+            .collect();
+        sphere.indices = shuffled;
+        let triangles = get_triangles(&sphere);
+        let shuffled_acmr = sphere.get_acmr(VERTEX_CACHE_SIZE);
+
+        sphere.optimize_vertex_cache().unwrap();
+        assert!(sphere.validate().is_ok());
+        assert_eq!(get_triangles(&sphere), triangles);
+        let acmr = sphere.get_acmr(VERTEX_CACHE_SIZE);
+        assert!(
+            acmr < 0.8 && acmr < shuffled_acmr / 2.,
+            "{} {}",
+            acmr,
+            shuffled_acmr
+        );
+        assert_eq!(sphere.indices[0], 0);
+    }
+
+    #[test]
+    fn generate_tangents_test() {
+        let mut plane = Mesh::plane(1., 1.);
+        plane.generate_tangents().unwrap();
+        assert_eq!(plane.get_vertices_count(), 4);
+        for (t, b) in plane.tangents.iter().zip(plane.bitangents.iter()) {
+            assert!((t - Vector3::x()).norm() < 1e-6);
+            assert!((b - (-Vector3::z())).norm() < 1e-6);
+        }
+
+        // faces of a cuboid don't share vertices, so nothing is split
+        let mut cuboid = Mesh::cuboid(Vector3::new(1., 2., 3.));
+        cuboid.generate_tangents().unwrap();
+        assert_eq!(cuboid.get_vertices_count(), 24);
+        for ((n, t), b) in cuboid
+            .normals
+            .iter()
+            .zip(cuboid.tangents.iter())
+            .zip(cuboid.bitangents.iter())
+        {
+            assert!(n.dot(t).abs() < 1e-5 && n.dot(b).abs() < 1e-5 && t.dot(b).abs() < 1e-5);
+            assert!((t.norm() - 1.).abs() < 1e-5 && (b.norm() - 1.).abs() < 1e-5);
+        }
+
+        let mut sphere = Mesh::sphere(1., 16, 8);
+        sphere.generate_tangents().unwrap();
+        for (n, t) in sphere.normals.iter().zip(sphere.tangents.iter()) {
+            assert!(n.dot(t).abs() < 1e-3 && (t.norm() - 1.).abs() < 1e-3);
+        }
+
+        sphere.uvs[0].clear();
+        assert!(matches!(
+            sphere.generate_tangents(),
+            Err(MeshError::MissingAttribute(_))
+        ));
+    }
+
+    #[test]
+    fn bounds_and_statistics_test() {
+        let cuboid = Mesh::cuboid(Vector3::new(1., 2., 3.));
+        let bounds = cuboid.get_bounding_box().unwrap();
+        assert_eq!(bounds.get_size(), Vector3::new(1., 2., 3.));
+        assert_eq!(bounds.get_center(), Vector3::zeros());
+        let sphere = cuboid.get_bounding_sphere().unwrap();
+        assert!(cuboid
+            .positions
+            .iter()
+            .all(|p| (p - sphere.center).norm() <= sphere.radius + 1e-5));
+        assert!(sphere.radius < bounds.get_size().norm() * 0.5 * 1.05);
+        assert!(Mesh::new().get_bounding_box().is_none());
+
+        let mut mesh = cuboid.clone();
+        mesh.positions.push(Vector3::zeros());
+        mesh.indices.extend_from_slice(&[0, 0, 1]);
+        let statistics = mesh.get_statistics();
+        assert_eq!(statistics.vertices_count, 25);
+        assert_eq!(statistics.triangles_count, 13);
+        assert_eq!(statistics.degenerate_triangles_count, 1);
+        assert_eq!(statistics.unused_vertices_count, 1);
+        assert!((statistics.surface_area - 22.).abs() < 1e-4);
+    }
+}
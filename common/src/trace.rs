@@ -1,47 +1,29 @@
-pub struct TraceMessage {
-    pub exit_message: &'static str,
-}
-
-impl TraceMessage {
-    pub fn new(enter_message: &'static str, exit_message: &'static str) -> Self {
-        println!("{}", enter_message);
-        TraceMessage {
-            exit_message: exit_message,
-        }
-    }
-}
-
-impl Drop for TraceMessage {
-    fn drop(&mut self) {
-        println!("{}", self.exit_message);
-    }
-}
+pub use ::trace::{
+    trace_argument, trace_counter, trace_marker, trace_point, TracePoint, TracePointInfo,
+};
 
+// records `Struct::method` spans into the trace collector until the end of the enclosing block
 #[macro_export]
 macro_rules! trace {
-    // `()` indicates that the macro takes no argument.
     ($struct_name:expr, $method_name:expr) => {
-        let _trace_variable = TraceMessage::new(
-            concat!(
-                "ENTER: ",
-                std::file!(),
-                "(",
-                std::line!(),
-                "), ",
-                $struct_name,
-                "::",
-                $method_name
-            ),
-            concat!(
-                "LEAVE: ",
-                std::file!(),
-                "(",
-                std::line!(),
-                "), ",
-                $struct_name,
-                "::",
-                $method_name
-            ),
-        );
+        $crate::trace::trace_point!(concat!($struct_name, "::", $method_name));
     };
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn trace_test() {
+        {
+            trace!("Struct", "method");
+        }
+        let thread_id = ::trace::current_thread_id();
+        let names: Vec<&str> = ::trace::collect_events()
+            .into_iter()
+            .filter(|thread| thread.thread_id == thread_id)
+            .flat_map(|thread| thread.events)
+            .map(|event| event.name)
+            .collect();
+        assert_eq!(names, vec!["Struct::method", "Struct::method"]);
+    }
+}
@@ -15,7 +15,8 @@ mod style;
 mod ui;
 
 fn new_nodes_document(document: &mut NodesDocumentImpl) {
-    let handler = document.add_node(nodes_engine::std_nodes::constant_boolean::ConstantBoolean::new());
+    let node = document.create_node("std.constant_boolean").expect("std nodes are registered");
+    let handler = document.add_node(node);
     document.set_node_position(handler, Vector2::new(0., 0.));
 }
 
@@ -1,9 +1,16 @@
 pub mod variant;
 pub mod node;
+pub mod node_registry;
 pub mod nodes_program;
 pub mod std_nodes;
 pub mod nodes_document;
+pub mod nodes_document_file;
+pub mod nodes_evaluator;
+#[cfg(test)]
+mod test_nodes;
 
 pub use self::variant::*;
 pub use self::node::*;
+pub use self::node_registry::*;
 pub use self::nodes_document::*;
+pub use self::nodes_evaluator::*;
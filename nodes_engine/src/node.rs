@@ -1,9 +1,38 @@
 use crate::{ Variant, VariantType };
 
-pub trait Node {
+// nodes are shared between the threads of the parallel evaluator
+pub trait Node: Send + Sync {
 
     fn get_name(&self) -> String;
 
+    // stable identifier used to recreate the node when a document is loaded, it has to be unique
+    // and registered in the `NodeRegistry` of the document, otherwise saved documents can't be loaded;
+    // nodes of one type have to restore their state from `get_parameters` through `set_parameter`
+    fn get_type_id(&self) -> String;
+
+    // editable values of the node, the editor and the serializer access them by name
+    fn get_parameters(&self) -> Vec<(String, Variant)> {
+        Vec::new()
+    }
+
+    fn get_parameter(&self, name: &str) -> Option<Variant> {
+        self.get_parameters()
+            .into_iter()
+            .find(|(parameter_name, _)| parameter_name == name)
+            .map(|(_, value)| value)
+    }
+
+    fn get_parameters_info(&self) -> Vec<NodeParameterInfo> {
+        self.get_parameters()
+            .into_iter()
+            .map(|(name, value)| NodeParameterInfo { name, variant_type: value.get_type() })
+            .collect()
+    }
+
+    fn set_parameter(&mut self, name: &str, _value: Variant) -> Result<(), NodeError> {
+        Err(NodeError::new(&format!("unknown parameter {}", name)))
+    }
+
     fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError>;
 
     fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError>;
@@ -11,9 +40,18 @@ pub trait Node {
     fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError>;
 }
 
+pub struct NodeParameterInfo {
+
+    pub name: String,
+
+    pub variant_type: VariantType,
+}
+
 pub struct NodeOutputSlotInfo {
 
     pub name: String,
+
+    pub variant_type: VariantType,
 }
 
 pub struct NodeInputSlotInfo {
@@ -21,6 +59,7 @@ pub struct NodeInputSlotInfo {
     pub name: String,
 }
 
+#[derive(Clone, Debug)]
 pub struct NodeError {
 
     pub message: String,
@@ -29,3 +68,13 @@ pub struct NodeError {
 
     pub failed_outputs: Vec<(usize, String)>,
 }
+
+impl NodeError {
+    pub fn new(message: &str) -> NodeError {
+        NodeError {
+            message: String::from(message),
+            failed_inputs: Vec::new(),
+            failed_outputs: Vec::new(),
+        }
+    }
+}
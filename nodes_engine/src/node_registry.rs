@@ -0,0 +1,128 @@
+use crate::{ Node, Variant };
+use std::collections::BTreeMap;
+
+pub type NodeFactory = Box<dyn Fn() -> Box<dyn Node> + Send + Sync>;
+
+pub struct NodeTypeInfo {
+    pub type_id: String,
+    pub name: String,
+    pub category: String,
+    pub description: String,
+    pub default_parameters: Vec<(String, Variant)>,
+}
+
+#[derive(Debug)]
+pub enum NodeRegistryError {
+    DuplicateTypeId(String),
+}
+
+struct NodeRegistryEntry {
+    info: NodeTypeInfo,
+    factory: NodeFactory,
+}
+
+// type ids are kept sorted, so menus and listings built from the registry are stable
+pub struct NodeRegistry {
+    entries: BTreeMap<String, NodeRegistryEntry>,
+}
+
+impl NodeRegistry {
+    pub fn new() -> NodeRegistry {
+        NodeRegistry {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_std_nodes() -> NodeRegistry {
+        let mut registry = NodeRegistry::new();
+        crate::std_nodes::register_std_nodes(&mut registry);
+        registry
+    }
+
+    // type id, name and default parameters are taken from a node created by the factory
+    pub fn register<F>(&mut self, category: &str, description: &str, factory: F) -> Result<(), NodeRegistryError>
+    where
+        F: Fn() -> Box<dyn Node> + Send + Sync + 'static,
+    {
+        let node = factory();
+        let type_id = node.get_type_id();
+        if self.entries.contains_key(&type_id) {
+            return Err(NodeRegistryError::DuplicateTypeId(type_id));
+        }
+        let info = NodeTypeInfo {
+            type_id: type_id.clone(),
+            name: node.get_name(),
+            category: String::from(category),
+            description: String::from(description),
+            default_parameters: node.get_parameters(),
+        };
+        self.entries.insert(type_id, NodeRegistryEntry { info, factory: Box::new(factory) });
+        Ok(())
+    }
+
+    pub fn create(&self, type_id: &str) -> Option<Box<dyn Node>> {
+        self.entries.get(type_id).map(|entry| (entry.factory)())
+    }
+
+    pub fn get_info(&self, type_id: &str) -> Option<&NodeTypeInfo> {
+        self.entries.get(type_id).map(|entry| &entry.info)
+    }
+
+    pub fn get_types(&self) -> Vec<&NodeTypeInfo> {
+        self.entries.values().map(|entry| &entry.info).collect()
+    }
+
+    pub fn get_category_types(&self, category: &str) -> Vec<&NodeTypeInfo> {
+        self.entries.values()
+            .map(|entry| &entry.info)
+            .filter(|info| info.category == category)
+            .collect()
+    }
+
+    pub fn get_categories(&self) -> Vec<String> {
+        let mut categories: Vec<String> = self.entries.values().map(|entry| entry.info.category.clone()).collect();
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+}
+
+impl Default for NodeRegistry {
+    fn default() -> NodeRegistry {
+        NodeRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_nodes::*;
+
+    #[test]
+    fn std_nodes_registry_test() {
+        let registry = NodeRegistry::with_std_nodes();
+        let info = registry.get_info("std.constant_real").unwrap();
+        assert_eq!(info.name, "Constant Real");
+        assert_eq!(info.category, "Constants");
+        assert_eq!(info.default_parameters, vec![(String::from("value"), Variant::RealNumber(0.))]);
+
+        let node = registry.create("std.constant_integer").unwrap();
+        assert_eq!(node.get_type_id(), "std.constant_integer");
+        assert!(registry.create("std.unknown").is_none());
+        assert!(registry.get_categories().contains(&String::from("Constants")));
+    }
+
+    #[test]
+    fn register_test() {
+        let mut registry = NodeRegistry::with_std_nodes();
+        registry.register("Test", "sum of two reals", || Box::new(TestSum {})).unwrap();
+        assert!(matches!(
+            registry.register("Test", "sum of two reals", || Box::new(TestSum {})),
+            Err(NodeRegistryError::DuplicateTypeId(_))
+        ));
+
+        let types: Vec<&str> = registry.get_category_types("Test").iter().map(|info| info.type_id.as_str()).collect();
+        assert_eq!(types, vec!["test.sum"]);
+        assert_eq!(registry.create("test.sum").unwrap().get_name(), "Test Sum");
+    }
+}
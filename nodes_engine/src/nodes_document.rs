@@ -1,15 +1,28 @@
-use crate::Node;
+use crate::{ Node, NodeError, NodeRegistry, Variant, VariantType };
+use crate::nodes_evaluator::{ self, NodesEvaluation, NodesEvaluationError };
+use crate::nodes_document_file::*;
+use crate::nodes_program::{ NodesProgram, NODES_PROGRAM_TYPE_ID };
+use common::id_set::IdSet;
 use nalgebra::Vector2;
-use std::collections::HashMap;
+use serde::{ Serialize, Deserialize };
+use std::sync::Arc;
 
-pub use u64 as NodeHandler;
-pub use u64 as ConnectionHandler;
+// handlers are generational, a handler of a removed node never refers to nodes added later
+common::define_id! {
+    #[derive(Serialize, Deserialize)]
+    pub struct NodeHandler(u32, u32);
+}
+
+common::define_id! {
+    #[derive(Serialize, Deserialize)]
+    pub struct ConnectionHandler(u32, u32);
+}
 
 pub trait NodesDocument {
 
-    fn load(&mut self) -> std::io::Result<()>;
+    fn load(&mut self, reader: &mut dyn std::io::Read) -> std::io::Result<()>;
 
-    fn save(&self) -> std::io::Result<()>;
+    fn save(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()>;
 
     fn get_nodes_list(&self) -> Vec<NodeHandler>;
 
@@ -17,7 +30,11 @@ pub trait NodesDocument {
 
     fn add_node_with_handler(&mut self, node: Box<dyn Node>, handler: NodeHandler);
 
-    fn remove_node(&mut self, _handler: NodeHandler) -> Result<(), ()>;
+    fn get_node(&self, handler: NodeHandler) -> Option<&dyn Node>;
+
+    fn set_node_parameter(&mut self, handler: NodeHandler, name: &str, value: Variant) -> Result<(), NodeError>;
+
+    fn remove_node(&mut self, handler: NodeHandler) -> Option<RemovedNode>;
 
     fn set_node_position(&mut self, handler: NodeHandler, position: Vector2<f32>);
 
@@ -29,31 +46,59 @@ pub trait NodesDocument {
 
     fn add_connection_with_handler(&mut self, connection: NodeDocumentConnection, handler: ConnectionHandler);
 
+    fn try_add_connection(&mut self, connection: NodeDocumentConnection) -> Result<ConnectionHandler, ConnectionError>;
+
+    fn validate_connection(&self, connection: &NodeDocumentConnection) -> Result<(), ConnectionError>;
+
+    fn remove_connection(&mut self, handler: ConnectionHandler) -> Option<NodeDocumentConnection>;
+
     fn get_connection(&self, handler: ConnectionHandler) -> Option<NodeDocumentConnection>;
 
     fn get_input_slot_connection(&self, slot: &NodeDocumentSlot) -> Option<ConnectionHandler>;
 
     fn get_output_slot_connections(&self, slot: &NodeDocumentSlot) -> Vec<ConnectionHandler>;
+
+    fn get_node_input_connections(&self, handler: NodeHandler) -> Vec<ConnectionHandler>;
+
+    fn get_node_output_connections(&self, handler: NodeHandler) -> Vec<ConnectionHandler>;
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct NodeDocumentSlot {
     pub node: NodeHandler,
     pub slot_name: String,
     pub slot_index: u64,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct NodeDocumentConnection {
     pub from: NodeDocumentSlot,
     pub to: NodeDocumentSlot,
 }
 
+#[derive(Debug)]
+pub enum ConnectionError {
+    Cycle,
+    UnknownNode(NodeHandler),
+    SlotOutOfRange(NodeDocumentSlot),
+    TypeMismatch(VariantType, NodeError),
+    InputAlreadyBound(ConnectionHandler),
+}
+
+// everything needed to restore a removed node with `add_node_with_handler`,
+// `set_node_position` and `add_connection_with_handler`
+pub struct RemovedNode {
+    pub handler: NodeHandler,
+    pub node: Box<dyn Node>,
+    pub position: Vector2<f32>,
+    pub connections: Vec<(ConnectionHandler, NodeDocumentConnection)>,
+}
+
 pub struct NodesDocumentImpl {
-    node_handler_counter: NodeHandler,
-    cells: HashMap<NodeHandler, NodeDocumentCell>,
-    connection_handler_counter: ConnectionHandler,
-    connections: HashMap<ConnectionHandler, NodeDocumentConnection>,
+    registry: Arc<NodeRegistry>,
+    cells: IdSet<NodeDocumentCell, u32, u32, NodeHandler>,
+    connections: IdSet<NodeDocumentConnection, u32, u32, ConnectionHandler>,
+    invoke_count: u64,
 }
 
 struct NodeDocumentCell {
@@ -61,67 +106,317 @@ struct NodeDocumentCell {
     pub position: Vector2<f32>,
     pub input_connections: Vec<ConnectionHandler>,
     pub output_connections: Vec<ConnectionHandler>,
+    // a dirty cell and everything downstream of it have to be invoked on the next evaluation
+    pub dirty: bool,
+    pub cached_output_types: Vec<VariantType>,
+    pub cached_outputs: Vec<Variant>,
+}
+
+impl NodeDocumentCell {
+
+    fn new(node: Box<dyn Node>) -> NodeDocumentCell {
+        NodeDocumentCell {
+            node,
+            position: Vector2::new(0., 0.),
+            input_connections: Vec::new(),
+            output_connections: Vec::new(),
+            dirty: true,
+            cached_output_types: Vec::new(),
+            cached_outputs: Vec::new(),
+        }
+    }
 }
 
 impl NodesDocumentImpl {
 
     pub fn new() -> NodesDocumentImpl {
+        NodesDocumentImpl::with_registry(Arc::new(NodeRegistry::with_std_nodes()))
+    }
+
+    // registry is used to recreate nodes by their type ids on load
+    pub fn with_registry(registry: Arc<NodeRegistry>) -> NodesDocumentImpl {
         NodesDocumentImpl {
-            node_handler_counter: 0,
-            cells: HashMap::new(),
-            connection_handler_counter: 0,
-            connections: HashMap::new(),
+            registry,
+            cells: IdSet::new(),
+            connections: IdSet::new(),
+            invoke_count: 0,
+        }
+    }
+
+    pub fn get_registry(&self) -> &Arc<NodeRegistry> {
+        &self.registry
+    }
+
+    // programs are created here instead of the registry, their inner nodes are created by the registry of the document
+    pub fn create_node(&self, type_id: &str) -> Option<Box<dyn Node>> {
+        if type_id == NODES_PROGRAM_TYPE_ID {
+            let document = NodesDocumentImpl::with_registry(self.registry.clone());
+            return Some(Box::new(NodesProgram::with_document(document)));
         }
+        self.registry.create(type_id)
+    }
+
+    // also used for the documents nested in programs
+    pub(crate) fn from_file(registry: Arc<NodeRegistry>, file: NodesDocumentFile) -> std::io::Result<NodesDocumentImpl> {
+        check_version(file.version)?;
+
+        let mut document = NodesDocumentImpl::with_registry(registry);
+        for entry in file.nodes {
+            let mut node = document.create_node(&entry.type_id)
+                .ok_or_else(|| invalid_data(format!("unknown node type {}", entry.type_id)))?;
+            for (name, value) in entry.parameters {
+                node.set_parameter(&name, value).map_err(|e| invalid_data(e.message))?;
+            }
+            if !document.cells.add_with_id(entry.handler, NodeDocumentCell::new(node)) {
+                return Err(invalid_data(format!("invalid node handler {:?}", entry.handler)));
+            }
+            document.set_node_position(entry.handler, Vector2::new(entry.position[0], entry.position[1]));
+        }
+
+        for entry in file.connections {
+            let connection = &entry.connection;
+            if !document.cells.exists(connection.from.node)
+                || !document.cells.exists(connection.to.node)
+                || document.get_input_slot_connection(&connection.to).is_some()
+                || !document.connections.add_with_id(entry.handler, entry.connection)
+            {
+                return Err(invalid_data(format!("invalid connection {:?}", entry.handler)));
+            }
+            document.bind_connection(entry.handler);
+        }
+        Ok(document)
+    }
+
+    pub(crate) fn to_file(&self) -> NodesDocumentFile {
+        // id sets iterate in the index order, so saving is deterministic
+        let nodes: Vec<NodeFileEntry> = self.cells.iter()
+            .map(|(handler, cell)| NodeFileEntry {
+                handler,
+                type_id: cell.node.get_type_id(),
+                position: [cell.position.x, cell.position.y],
+                parameters: cell.node.get_parameters(),
+            })
+            .collect();
+
+        let connections: Vec<ConnectionFileEntry> = self.connections.iter()
+            .map(|(handler, connection)| ConnectionFileEntry {
+                handler,
+                connection: connection.clone(),
+            })
+            .collect();
+
+        NodesDocumentFile {
+            version: NODES_DOCUMENT_FILE_VERSION,
+            nodes,
+            connections,
+        }
+    }
+
+    // evaluates the document invoking only dirty nodes, outputs of the other nodes are taken from the cache
+    pub fn evaluate_incremental(&mut self) -> Result<NodesEvaluation, NodesEvaluationError> {
+        let order = nodes_evaluator::get_topological_order(self)?;
+        let mut evaluation = NodesEvaluation::new();
+        for handler in &order {
+            let handler = *handler;
+            if !self.cells[handler].dirty {
+                let cell = &self.cells[handler];
+                evaluation.insert(handler, cell.cached_output_types.clone(), cell.cached_outputs.clone());
+                continue;
+            }
+
+            let (output_types, outputs) = nodes_evaluator::evaluate_node(self, &evaluation, handler)?;
+            self.invoke_count += 1;
+            let cell = &mut self.cells[handler];
+            cell.dirty = false;
+            cell.cached_output_types = output_types.clone();
+            cell.cached_outputs = outputs.clone();
+            evaluation.invoked.push(handler);
+            evaluation.insert(handler, output_types, outputs);
+        }
+        evaluation.order = order;
+        Ok(evaluation)
+    }
+
+    // total number of successful `invoke` calls made by `evaluate_incremental`
+    pub fn get_invoke_count(&self) -> u64 {
+        self.invoke_count
+    }
+
+    pub fn is_dirty(&self, handler: NodeHandler) -> bool {
+        self.cells.get(handler).map(|cell| cell.dirty).unwrap_or(false)
+    }
+
+    // marks the node and its downstream cone, clean nodes never depend on dirty ones,
+    // so the walk stops at the nodes which are already dirty
+    fn mark_dirty(&mut self, handler: NodeHandler) {
+        let mut stack = vec![handler];
+        while let Some(node) = stack.pop() {
+            let cell = match self.cells.get_mut(node) {
+                Some(cell) if !cell.dirty => cell,
+                _ => continue,
+            };
+            cell.dirty = true;
+            for connection_handler in &cell.output_connections {
+                if let Some(connection) = self.connections.get(*connection_handler) {
+                    stack.push(connection.to.node);
+                }
+            }
+        }
+    }
+
+    // true if `to` is `from` or is located downstream of it
+    pub(crate) fn is_reachable(&self, from: NodeHandler, to: NodeHandler) -> bool {
+        let mut visited = std::collections::HashSet::new();
+        let mut stack = vec![from];
+        while let Some(node) = stack.pop() {
+            if node == to {
+                return true;
+            }
+            if !visited.insert(node) {
+                continue;
+            }
+            if let Some(cell) = self.cells.get(node) {
+                for connection_handler in &cell.output_connections {
+                    if let Some(connection) = self.connections.get(*connection_handler) {
+                        stack.push(connection.to.node);
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn validate_connection_types(&self, connection: &NodeDocumentConnection) -> Result<(), ConnectionError> {
+        // types can't be checked while the document has a cycle, evaluation will report it anyway
+        let types = match nodes_evaluator::determine_types(self) {
+            Ok(types) => types,
+            Err(_) => return Ok(()),
+        };
+        let from_type = match types.get(&connection.from.node) {
+            Some(output_types) => match output_types.get(connection.from.slot_index as usize) {
+                Some(from_type) => from_type.clone(),
+                None => return Err(ConnectionError::SlotOutOfRange(connection.from.clone())),
+            },
+            None => return Ok(()),
+        };
+
+        let to_node = self.cells[connection.to.node].node.as_ref();
+        let inputs_count = to_node.get_inputs().map(|inputs| inputs.len()).unwrap_or(0);
+        let mut input_types = vec![VariantType::Null; inputs_count];
+        for connection_handler in &self.cells[connection.to.node].input_connections {
+            let input_connection = &self.connections[*connection_handler];
+            let input_type = types.get(&input_connection.from.node)
+                .and_then(|output_types| output_types.get(input_connection.from.slot_index as usize));
+            if let (Some(slot_type), Some(input_type)) = (input_types.get_mut(input_connection.to.slot_index as usize), input_type) {
+                *slot_type = input_type.clone();
+            }
+        }
+        input_types[connection.to.slot_index as usize] = from_type.clone();
+
+        match nodes_evaluator::determine_with_conversions(to_node, &input_types) {
+            Err(error) if error.failed_inputs.iter().any(|(i, _)| *i as u64 == connection.to.slot_index) => {
+                Err(ConnectionError::TypeMismatch(from_type, error))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    // panics if the connection can't be added to the document
+    fn check_connection(&self, connection: &NodeDocumentConnection) {
+        if !self.cells.exists(connection.from.node) || !self.cells.exists(connection.to.node) {
+            panic!("connection with unexist node");
+        }
+        if self.get_input_slot_connection(&connection.to).is_some() {
+            panic!("input connection is already exists");
+        }
+    }
+
+    // registers an already stored connection in the cells it connects
+    fn bind_connection(&mut self, handler: ConnectionHandler) {
+        let connection = &self.connections[handler];
+        self.cells[connection.from.node].output_connections.push(handler);
+        self.cells[connection.to.node].input_connections.push(handler);
+        let to_node = connection.to.node;
+        self.mark_dirty(to_node);
     }
 }
 
 impl NodesDocument for NodesDocumentImpl {
 
-    fn load(&mut self) -> std::io::Result<()> {
-        unimplemented!();
+    // documents are loaded into a fresh instance so a broken file leaves `self` untouched
+    fn load(&mut self, reader: &mut dyn std::io::Read) -> std::io::Result<()> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let version: NodesDocumentFileVersion = ron::de::from_str(&text).map_err(invalid_data)?;
+        check_version(version.version)?;
+        let file: NodesDocumentFile = ron::de::from_str(&text).map_err(invalid_data)?;
+        *self = NodesDocumentImpl::from_file(self.registry.clone(), file)?;
+        Ok(())
     }
 
-    fn save(&self) -> std::io::Result<()> {
-        unimplemented!();
+    fn save(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
+        let text = ron::ser::to_string_pretty(&self.to_file(), ron::ser::PrettyConfig::default()).map_err(invalid_data)?;
+        writer.write_all(text.as_bytes())
     }
 
     fn get_nodes_list(&self) -> Vec<NodeHandler> {
-        let mut result = Vec::new();
-        for pair in &self.cells {
-            result.push(*pair.0);
-        };
-        result
+        self.cells.get_ids()
     }
 
     fn add_node(&mut self, node: Box<dyn Node>) -> NodeHandler {
-        let handler = self.node_handler_counter;
-        self.node_handler_counter = self.node_handler_counter + 1;
-        self.add_node_with_handler(node, handler);
-        handler
+        self.cells.add(NodeDocumentCell::new(node))
     }
 
     fn add_node_with_handler(&mut self, node: Box<dyn Node>, handler: NodeHandler) {
-        if handler >= self.node_handler_counter {
-            panic!("manual handler should be used by node and deleted");
+        if !self.cells.add_with_id(handler, NodeDocumentCell::new(node)) {
+            panic!("manual handler should be used by a removed node and its slot should be unused since then");
+        }
+    }
+
+    fn get_node(&self, handler: NodeHandler) -> Option<&dyn Node> {
+        if let Some(cell) = self.cells.get(handler) {
+            Some(cell.node.as_ref())
+        } else {
+            None
         }
-        if self.cells.contains_key(&handler) {
-            panic!("manual handler should be unused");
+    }
+
+    fn set_node_parameter(&mut self, handler: NodeHandler, name: &str, value: Variant) -> Result<(), NodeError> {
+        if let Some(cell) = self.cells.get_mut(handler) {
+            cell.node.set_parameter(name, value)?;
+            self.mark_dirty(handler);
+            Ok(())
+        } else {
+            Err(NodeError::new("try to set parameter of unexisting node"))
         }
-        let cell = NodeDocumentCell {
-            node: node,
-            position: Vector2::new(0., 0.),
-            input_connections: Vec::new(),
-            output_connections: Vec::new(),
-        };
-        self.cells.insert(handler, cell);
     }
 
-    fn remove_node(&mut self, _handler: NodeHandler) -> Result<(), ()> {
-        unimplemented!();
+    fn remove_node(&mut self, handler: NodeHandler) -> Option<RemovedNode> {
+        let connection_handlers = {
+            let cell = self.cells.get(handler)?;
+            let mut connection_handlers = cell.input_connections.clone();
+            connection_handlers.extend(cell.output_connections.iter());
+            connection_handlers
+        };
+
+        let mut connections = Vec::new();
+        for connection_handler in connection_handlers {
+            if let Some(connection) = self.remove_connection(connection_handler) {
+                connections.push((connection_handler, connection));
+            }
+        }
+
+        let cell = self.cells.remove(handler)?;
+        Some(RemovedNode {
+            handler,
+            node: cell.node,
+            position: cell.position,
+            connections,
+        })
     }
 
     fn set_node_position(&mut self, handler: NodeHandler, position: Vector2<f32>) {
-        if let Some(cell) = self.cells.get_mut(&handler) {
+        if let Some(cell) = self.cells.get_mut(handler) {
             cell.position = position;
         } else {
             panic!("try to change position of unexisting node");
@@ -129,7 +424,7 @@ impl NodesDocument for NodesDocumentImpl {
     }
 
     fn get_node_position(&self, handler: NodeHandler) -> Vector2<f32> {
-        if let Some(cell) = self.cells.get(&handler) {
+        if let Some(cell) = self.cells.get(handler) {
             cell.position
         } else {
             panic!("try to get position of unexisting node")
@@ -137,7 +432,7 @@ impl NodesDocument for NodesDocumentImpl {
     }
 
     fn get_node_size(&self, handler: NodeHandler) -> Vector2<f32> {
-        if let Some(_) = self.cells.get(&handler) {
+        if self.cells.exists(handler) {
             Vector2::new(100., 100.)
         } else {
             panic!("try to get position of unexisting node")
@@ -145,47 +440,76 @@ impl NodesDocument for NodesDocumentImpl {
     }
 
     fn add_connection(&mut self, connection: NodeDocumentConnection) -> ConnectionHandler {
-        let handler = self.connection_handler_counter;
-        self.connection_handler_counter = self.connection_handler_counter + 1;
-        self.add_connection_with_handler(connection, handler);
+        self.check_connection(&connection);
+        let handler = self.connections.add(connection);
+        self.bind_connection(handler);
         handler
     }
 
     fn add_connection_with_handler(&mut self, connection: NodeDocumentConnection, handler: ConnectionHandler) {
-        if handler >= self.connection_handler_counter {
-            panic!("manual handler should be used and deleted");
+        self.check_connection(&connection);
+        if !self.connections.add_with_id(handler, connection) {
+            panic!("manual handler should be used by a removed connection and its slot should be unused since then");
         }
-        if self.connections.contains_key(&handler) {
-            panic!("manual handler should be unused");
+        self.bind_connection(handler);
+    }
+
+    fn try_add_connection(&mut self, connection: NodeDocumentConnection) -> Result<ConnectionHandler, ConnectionError> {
+        self.validate_connection(&connection)?;
+        Ok(self.add_connection(connection))
+    }
+
+    fn validate_connection(&self, connection: &NodeDocumentConnection) -> Result<(), ConnectionError> {
+        if !self.cells.exists(connection.from.node) {
+            return Err(ConnectionError::UnknownNode(connection.from.node));
         }
-        if !self.cells.contains_key(&connection.from.node) || !self.cells.contains_key(&connection.to.node) {
-            panic!("connection with unexist node");
+        let to_cell = match self.cells.get(connection.to.node) {
+            Some(cell) => cell,
+            None => return Err(ConnectionError::UnknownNode(connection.to.node)),
+        };
+
+        let inputs_count = to_cell.node.get_inputs().map(|inputs| inputs.len()).unwrap_or(0);
+        if connection.to.slot_index as usize >= inputs_count {
+            return Err(ConnectionError::SlotOutOfRange(connection.to.clone()));
         }
-        if self.get_input_slot_connection(&connection.to).is_some() {
-            panic!("input connection is already exists");
+
+        if let Some(connection_handler) = self.get_input_slot_connection(&connection.to) {
+            return Err(ConnectionError::InputAlreadyBound(connection_handler));
         }
-        if let Some(cell) = self.cells.get_mut(&connection.from.node) {
-            cell.output_connections.push(handler);
+
+        if self.is_reachable(connection.to.node, connection.from.node) {
+            return Err(ConnectionError::Cycle);
+        }
+
+        self.validate_connection_types(connection)
+    }
+
+    fn remove_connection(&mut self, handler: ConnectionHandler) -> Option<NodeDocumentConnection> {
+        let connection = self.connections.remove(handler)?;
+        if let Some(cell) = self.cells.get_mut(connection.from.node) {
+            cell.output_connections.retain(|h| *h != handler);
         }
-        if let Some(cell) = self.cells.get_mut(&connection.to.node) {
-            cell.input_connections.push(handler);
+        if let Some(cell) = self.cells.get_mut(connection.to.node) {
+            cell.input_connections.retain(|h| *h != handler);
         }
-        self.connections.insert(handler, connection);
+        self.mark_dirty(connection.to.node);
+        Some(connection)
     }
 
     fn get_connection(&self, handler: ConnectionHandler) -> Option<NodeDocumentConnection> {
-        if let Some(connection) = self.connections.get(&handler) {
+        if let Some(connection) = self.connections.get(handler) {
             Some(connection.clone())
         } else {
             None
         }
     }
 
+    // inputs are identified by the node and the index, slot names are informational only
     fn get_input_slot_connection(&self, slot: &NodeDocumentSlot) -> Option<ConnectionHandler> {
-        if let Some(cell) = self.cells.get(&slot.node) {
+        if let Some(cell) = self.cells.get(slot.node) {
             for connection_handler in &cell.input_connections {
-                if let Some(connection) = self.connections.get(connection_handler) {
-                    if &connection.to == slot {
+                if let Some(connection) = self.connections.get(*connection_handler) {
+                    if connection.to.slot_index == slot.slot_index {
                         return Some(*connection_handler);
                     }
                 }
@@ -198,10 +522,10 @@ impl NodesDocument for NodesDocumentImpl {
 
     fn get_output_slot_connections(&self, slot: &NodeDocumentSlot) -> Vec<ConnectionHandler> {
         let mut result = Vec::new();
-        if let Some(cell) = self.cells.get(&slot.node) {
+        if let Some(cell) = self.cells.get(slot.node) {
             for connection_handler in &cell.output_connections {
-                if let Some(connection) = self.connections.get(connection_handler) {
-                    if &connection.to == slot {
+                if let Some(connection) = self.connections.get(*connection_handler) {
+                    if &connection.from == slot {
                         result.push(*connection_handler);
                     }
                 }
@@ -209,4 +533,203 @@ impl NodesDocument for NodesDocumentImpl {
         };
         result
     }
+
+    fn get_node_input_connections(&self, handler: NodeHandler) -> Vec<ConnectionHandler> {
+        if let Some(cell) = self.cells.get(handler) {
+            cell.input_connections.clone()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn get_node_output_connections(&self, handler: NodeHandler) -> Vec<ConnectionHandler> {
+        if let Some(cell) = self.cells.get(handler) {
+            cell.output_connections.clone()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_nodes::*;
+    use common::id_set::Id;
+    use nalgebra::Vector2;
+
+    #[test]
+    fn remove_node_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let b = document.add_node(Box::new(TestConstant { value: 2. }));
+        let sum = document.add_node(Box::new(TestSum {}));
+        let result = document.add_node(Box::new(TestSum {}));
+        let a_sum = document.add_connection(connection(a, 0, sum, 0));
+        let b_sum = document.add_connection(connection(b, 0, sum, 1));
+        let sum_result = document.add_connection(connection(sum, 0, result, 0));
+        document.set_node_position(sum, Vector2::new(10., 20.));
+
+        let removed = document.remove_node(sum).unwrap();
+        assert_eq!(removed.handler, sum);
+        assert_eq!(removed.position, Vector2::new(10., 20.));
+        assert_eq!(removed.connections.len(), 3);
+        assert!(document.get_node(sum).is_none());
+        assert!(document.get_connection(a_sum).is_none());
+        assert!(document.get_node_output_connections(a).is_empty());
+        assert!(document.get_node_output_connections(b).is_empty());
+        assert!(document.get_node_input_connections(result).is_empty());
+        assert!(document.remove_node(sum).is_none());
+
+        document.add_node_with_handler(removed.node, removed.handler);
+        document.set_node_position(removed.handler, removed.position);
+        for (connection_handler, connection) in removed.connections {
+            document.add_connection_with_handler(connection, connection_handler);
+        }
+        assert_eq!(document.get_input_slot_connection(&slot(sum, 1)), Some(b_sum));
+        assert_eq!(document.get_output_slot_connections(&slot(sum, 0)), vec![sum_result]);
+        assert_eq!(document.get_node_position(sum), Vector2::new(10., 20.));
+    }
+
+    #[test]
+    fn stale_handler_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let sum = document.add_node(Box::new(TestSum {}));
+        let a_sum = document.add_connection(connection(a, 0, sum, 0));
+        document.remove_node(a);
+
+        // the slot is reused, the old handlers don't refer to the new node and connection
+        let b = document.add_node(Box::new(TestConstant { value: 2. }));
+        let b_sum = document.add_connection(connection(b, 0, sum, 0));
+        assert_ne!(a, b);
+        assert_ne!(a_sum, b_sum);
+        assert!(document.get_node(a).is_none());
+        assert!(document.get_connection(a_sum).is_none());
+        assert!(document.remove_node(a).is_none());
+        assert_eq!(document.get_nodes_list(), vec![b, sum]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_stale_handler_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let removed = document.remove_node(a).unwrap();
+        document.add_node(Box::new(TestConstant { value: 2. }));
+        document.add_node_with_handler(removed.node, removed.handler);
+    }
+
+    #[test]
+    fn remove_connection_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let sum = document.add_node(Box::new(TestSum {}));
+        let handler = document.add_connection(connection(a, 0, sum, 0));
+
+        assert_eq!(document.remove_connection(handler), Some(connection(a, 0, sum, 0)));
+        assert!(document.remove_connection(handler).is_none());
+        assert!(document.get_input_slot_connection(&slot(sum, 0)).is_none());
+        assert!(document.get_output_slot_connections(&slot(a, 0)).is_empty());
+    }
+
+    #[test]
+    fn try_add_connection_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let b = document.add_node(Box::new(TestConstant { value: 2. }));
+        let sum = document.add_node(Box::new(TestSum {}));
+
+        let handler = document.try_add_connection(connection(a, 0, sum, 0)).unwrap();
+        assert_eq!(document.get_input_slot_connection(&slot(sum, 0)), Some(handler));
+
+        match document.try_add_connection(connection(b, 0, sum, 0)) {
+            Err(ConnectionError::InputAlreadyBound(bound)) => assert_eq!(bound, handler),
+            _ => panic!("expected already bound input"),
+        }
+        let mut named = connection(b, 0, sum, 0);
+        named.to.slot_name = String::from("a");
+        assert_eq!(document.get_input_slot_connection(&named.to), Some(handler));
+        assert!(matches!(document.try_add_connection(named), Err(ConnectionError::InputAlreadyBound(_))));
+        match document.try_add_connection(connection(b, 0, sum, 2)) {
+            Err(ConnectionError::SlotOutOfRange(slot)) => assert_eq!(slot.slot_index, 2),
+            _ => panic!("expected slot out of range"),
+        }
+        match document.try_add_connection(connection(b, 1, sum, 1)) {
+            Err(ConnectionError::SlotOutOfRange(slot)) => assert_eq!(slot.node, b),
+            _ => panic!("expected slot out of range"),
+        }
+        match document.try_add_connection(connection(b, 0, NodeHandler::null(), 0)) {
+            Err(ConnectionError::UnknownNode(node)) => assert_eq!(node, NodeHandler::null()),
+            _ => panic!("expected unknown node"),
+        }
+    }
+
+    #[test]
+    fn try_add_connection_cycle_test() {
+        let mut document = NodesDocumentImpl::new();
+        let first = document.add_node(Box::new(TestSum {}));
+        let second = document.add_node(Box::new(TestSum {}));
+        document.try_add_connection(connection(first, 0, second, 0)).unwrap();
+
+        assert!(matches!(document.try_add_connection(connection(second, 0, first, 0)), Err(ConnectionError::Cycle)));
+        assert!(matches!(document.try_add_connection(connection(first, 0, first, 1)), Err(ConnectionError::Cycle)));
+    }
+
+    #[test]
+    fn try_add_connection_type_mismatch_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let and = document.create_node("std.and").unwrap();
+        let and = document.add_node(and);
+
+        match document.try_add_connection(connection(a, 0, and, 1)) {
+            Err(ConnectionError::TypeMismatch(from_type, _)) => assert_eq!(from_type, VariantType::RealNumber),
+            _ => panic!("expected type mismatch"),
+        }
+        assert!(document.get_node_input_connections(and).is_empty());
+
+        // booleans are implicitly converted to real numbers
+        let flag = document.add_node(Box::new(TestFlag {}));
+        let sum = document.add_node(Box::new(TestSum {}));
+        assert!(document.try_add_connection(connection(flag, 0, sum, 1)).is_ok());
+    }
+
+    #[test]
+    fn evaluate_incremental_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let b = document.add_node(Box::new(TestConstant { value: 2. }));
+        let first = document.add_node(Box::new(TestSum {}));
+        let second = document.add_node(Box::new(TestSum {}));
+        connect(&mut document, a, first, 0);
+        connect(&mut document, b, first, 1);
+        connect(&mut document, b, second, 0);
+        connect(&mut document, first, second, 1);
+
+        let evaluation = document.evaluate_incremental().unwrap();
+        assert_eq!(evaluation.invoked.len(), 4);
+        assert_eq!(evaluation.get_output(second, 0), Some(&Variant::RealNumber(5.)));
+
+        // positions don't affect the result
+        document.set_node_position(a, Vector2::new(10., 10.));
+        assert!(document.evaluate_incremental().unwrap().invoked.is_empty());
+
+        document.set_node_parameter(a, "value", Variant::RealNumber(3.)).unwrap();
+        assert!(document.is_dirty(second));
+        assert!(!document.is_dirty(b));
+        let evaluation = document.evaluate_incremental().unwrap();
+        assert_eq!(evaluation.invoked, vec![a, first, second]);
+        assert_eq!(evaluation.get_output(second, 0), Some(&Variant::RealNumber(7.)));
+        assert_eq!(document.get_invoke_count(), 7);
+
+        let connection_handler = document.get_node_input_connections(second)[0];
+        document.remove_connection(connection_handler);
+        assert!(document.evaluate_incremental().is_err());
+        assert!(document.is_dirty(second));
+        connect(&mut document, a, second, 0);
+        let evaluation = document.evaluate_incremental().unwrap();
+        assert_eq!(evaluation.invoked, vec![second]);
+        assert_eq!(evaluation.get_output(second, 0), Some(&Variant::RealNumber(8.)));
+    }
 }
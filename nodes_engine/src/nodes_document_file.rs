@@ -0,0 +1,132 @@
+use crate::{ Variant, NodeHandler, ConnectionHandler, NodeDocumentConnection };
+use serde::{ Serialize, Deserialize };
+
+// increase on every incompatible change of the structures below
+pub const NODES_DOCUMENT_FILE_VERSION: u32 = 2;
+
+// only the version of a file, it is read first so files of other versions fail with a clear error
+#[derive(Deserialize)]
+pub struct NodesDocumentFileVersion {
+    pub version: u32,
+}
+
+// handlers are saved as is, handlers of nodes removed before saving are not tracked
+#[derive(Serialize, Deserialize)]
+pub struct NodesDocumentFile {
+    pub version: u32,
+    pub nodes: Vec<NodeFileEntry>,
+    pub connections: Vec<ConnectionFileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NodeFileEntry {
+    pub handler: NodeHandler,
+    pub type_id: String,
+    pub position: [f32; 2],
+    pub parameters: Vec<(String, Variant)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConnectionFileEntry {
+    pub handler: ConnectionHandler,
+    pub connection: NodeDocumentConnection,
+}
+
+// version 1 files have plain integer handlers, they can't be restored as generational ones
+pub fn check_version(version: u32) -> std::io::Result<()> {
+    if version < NODES_DOCUMENT_FILE_VERSION {
+        Err(invalid_data(format!("nodes document version {} is no longer supported, the current version is {}", version, NODES_DOCUMENT_FILE_VERSION)))
+    } else if version > NODES_DOCUMENT_FILE_VERSION {
+        Err(invalid_data(format!("unsupported nodes document version {}", version)))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn invalid_data<E: ToString>(error: E) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::nodes_document_file::*;
+    use crate::std_nodes::constant_real::ConstantReal;
+    use crate::test_nodes::*;
+    use nalgebra::Vector2;
+
+    fn save_to_string(document: &NodesDocumentImpl) -> String {
+        let mut buffer = Vec::new();
+        document.save(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn std_nodes_round_trip_test() {
+        let mut document = NodesDocumentImpl::new();
+        let mut constant = ConstantReal::new();
+        constant.set_parameter("value", Variant::RealNumber(0.25)).unwrap();
+        let handler = document.add_node(constant);
+        document.set_node_position(handler, Vector2::new(-5., 7.5));
+
+        let text = save_to_string(&document);
+        let mut loaded = NodesDocumentImpl::new();
+        loaded.load(&mut text.as_bytes()).unwrap();
+
+        let node = loaded.get_node(handler).unwrap();
+        assert_eq!(node.get_type_id(), "std.constant_real");
+        assert_eq!(node.get_parameters(), vec![(String::from("value"), Variant::RealNumber(0.25))]);
+        assert_eq!(loaded.get_node_position(handler), Vector2::new(-5., 7.5));
+        assert_eq!(save_to_string(&loaded), text);
+    }
+
+    #[test]
+    fn connections_round_trip_test() {
+        let mut document = NodesDocumentImpl::with_registry(test_registry());
+        let a = document.add_node(Box::new(TestConstant { value: 2. }));
+        let removed = document.add_node(Box::new(TestConstant { value: 0. }));
+        let b = document.add_node(Box::new(TestConstant { value: 3. }));
+        let sum = document.add_node(Box::new(TestSum {}));
+        document.remove_node(removed);
+        let a_sum = document.add_connection(connection(a, 0, sum, 0));
+        let b_sum = document.add_connection(connection(b, 0, sum, 1));
+
+        let text = save_to_string(&document);
+        let mut loaded = NodesDocumentImpl::with_registry(test_registry());
+        loaded.load(&mut text.as_bytes()).unwrap();
+
+        assert_eq!(loaded.get_connection(a_sum), Some(connection(a, 0, sum, 0)));
+        assert_eq!(loaded.get_connection(b_sum), Some(connection(b, 0, sum, 1)));
+        assert!(loaded.get_node(removed).is_none());
+        assert_eq!(evaluate(&loaded).unwrap().get_output(sum, 0), Some(&Variant::RealNumber(5.)));
+
+        // handlers are restored exactly, so new handlers never collide with saved ones
+        let new_node = loaded.add_node(Box::new(TestSum {}));
+        assert!(![a, b, sum].contains(&new_node));
+    }
+
+    #[test]
+    fn load_errors_test() {
+        let mut document = NodesDocumentImpl::new();
+        let handler = document.add_node(Box::new(TestConstant { value: 1. }));
+        let text = save_to_string(&document);
+
+        let mut loaded = NodesDocumentImpl::new();
+        assert!(loaded.load(&mut text.as_bytes()).is_err());
+        assert!(loaded.get_node(handler).is_none());
+
+        let newer = text.replace(
+            &format!("version: {}", NODES_DOCUMENT_FILE_VERSION),
+            &format!("version: {}", NODES_DOCUMENT_FILE_VERSION + 1),
+        );
+        let mut loaded = NodesDocumentImpl::with_registry(test_registry());
+        assert!(loaded.load(&mut newer.as_bytes()).is_err());
+        assert!(loaded.load(&mut "not a document".as_bytes()).is_err());
+
+        let old = "(version: 1, node_handler_counter: 1, connection_handler_counter: 0, nodes: [(handler: 0, type_id: \"test.sum\", position: (0, 0), parameters: [])], connections: [])";
+        let error = loaded.load(&mut old.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("version 1 is no longer supported"), "{}", error);
+        assert!(loaded.load(&mut text.as_bytes()).is_ok());
+        assert!(loaded.get_node(handler).is_some());
+    }
+}
@@ -0,0 +1,458 @@
+use crate::{ NodesDocument, NodeHandler, Node, NodeError, NodeOutputSlotInfo, Variant, VariantType };
+use std::collections::{ BTreeSet, HashMap };
+use std::sync::Mutex;
+
+pub struct NodesEvaluation {
+    pub order: Vec<NodeHandler>,
+    pub output_types: HashMap<NodeHandler, Vec<VariantType>>,
+    pub outputs: HashMap<NodeHandler, Vec<Variant>>,
+    // nodes whose `invoke` was called, cached and overridden nodes are not listed
+    pub invoked: Vec<NodeHandler>,
+}
+
+#[derive(Debug)]
+pub enum NodesEvaluationError {
+    // nodes which cannot be ordered because they depend on each other
+    Cycle(Vec<NodeHandler>),
+    UnknownNode(NodeHandler),
+    DetermineFailed(NodeHandler, NodeError),
+    InvokeFailed(NodeHandler, NodeError),
+}
+
+impl NodesEvaluation {
+    pub fn new() -> NodesEvaluation {
+        NodesEvaluation {
+            order: Vec::new(),
+            output_types: HashMap::new(),
+            outputs: HashMap::new(),
+            invoked: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, node: NodeHandler, output_types: Vec<VariantType>, outputs: Vec<Variant>) {
+        self.output_types.insert(node, output_types);
+        self.outputs.insert(node, outputs);
+    }
+
+    pub fn get_output(&self, node: NodeHandler, slot_index: usize) -> Option<&Variant> {
+        self.outputs.get(&node).and_then(|outputs| outputs.get(slot_index))
+    }
+
+    pub fn get_output_type(&self, node: NodeHandler, slot_index: usize) -> Option<VariantType> {
+        self.output_types.get(&node).and_then(|types| types.get(slot_index)).cloned()
+    }
+}
+
+impl Default for NodesEvaluation {
+    fn default() -> NodesEvaluation {
+        NodesEvaluation::new()
+    }
+}
+
+// Kahn's algorithm; ties are resolved by the smallest handler so the order is deterministic
+pub fn get_topological_order(document: &dyn NodesDocument) -> Result<Vec<NodeHandler>, NodesEvaluationError> {
+    let mut nodes = document.get_nodes_list();
+    nodes.sort();
+
+    let mut dependencies_count = HashMap::new();
+    for node in &nodes {
+        dependencies_count.insert(*node, document.get_node_input_connections(*node).len());
+    }
+
+    let mut ready: BTreeSet<NodeHandler> = nodes.iter()
+        .filter(|node| dependencies_count[*node] == 0)
+        .copied()
+        .collect();
+
+    let mut order = Vec::with_capacity(nodes.len());
+    while let Some(node) = ready.iter().next().copied() {
+        ready.remove(&node);
+        order.push(node);
+        for connection_handler in document.get_node_output_connections(node) {
+            if let Some(connection) = document.get_connection(connection_handler) {
+                if let Some(count) = dependencies_count.get_mut(&connection.to.node) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.insert(connection.to.node);
+                    }
+                }
+            }
+        }
+    }
+
+    if order.len() == nodes.len() {
+        Ok(order)
+    } else {
+        let cycle = nodes.into_iter()
+            .filter(|node| dependencies_count[node] > 0)
+            .collect();
+        Err(NodesEvaluationError::Cycle(cycle))
+    }
+}
+
+pub fn evaluate(document: &dyn NodesDocument) -> Result<NodesEvaluation, NodesEvaluationError> {
+    evaluate_with_overrides(document, &HashMap::new())
+}
+
+// nodes from `overrides` are not invoked, the given values are used as their outputs instead,
+// that's how arguments are passed into the graph of a `NodesProgram`
+pub fn evaluate_with_overrides(
+    document: &dyn NodesDocument,
+    overrides: &HashMap<NodeHandler, Vec<Variant>>,
+) -> Result<NodesEvaluation, NodesEvaluationError> {
+    let order = get_topological_order(document)?;
+    let mut evaluation = NodesEvaluation::new();
+
+    for handler in &order {
+        let handler = *handler;
+        if let Some(outputs) = overrides.get(&handler) {
+            evaluation.insert(handler, outputs.iter().map(|output| output.get_type()).collect(), outputs.clone());
+            continue;
+        }
+
+        let (output_types, outputs) = evaluate_node(document, &evaluation, handler)?;
+        evaluation.invoked.push(handler);
+        evaluation.insert(handler, output_types, outputs);
+    }
+
+    evaluation.order = order;
+    Ok(evaluation)
+}
+
+// determines and invokes a single node, its inputs are taken from the already evaluated upstream nodes
+pub fn evaluate_node(
+    document: &dyn NodesDocument,
+    evaluation: &NodesEvaluation,
+    handler: NodeHandler,
+) -> Result<(Vec<VariantType>, Vec<Variant>), NodesEvaluationError> {
+    let (input_types, inputs) = collect_inputs(document, evaluation, handler)?;
+    invoke_node(document, handler, input_types, inputs)
+}
+
+// schedules nodes on the rayon thread pool as soon as all their inputs are evaluated,
+// results and the reported error are the same as the ones of `evaluate`
+pub fn evaluate_parallel(document: &(dyn NodesDocument + Sync)) -> Result<NodesEvaluation, NodesEvaluationError> {
+    let order = get_topological_order(document)?;
+    let positions: HashMap<NodeHandler, usize> = order.iter().enumerate().map(|(i, node)| (*node, i)).collect();
+
+    let mut dependencies_count = HashMap::new();
+    for node in &order {
+        dependencies_count.insert(*node, document.get_node_input_connections(*node).len());
+    }
+    let state = Mutex::new(ParallelEvaluationState {
+        evaluation: NodesEvaluation::new(),
+        dependencies_count,
+        errors: Vec::new(),
+    });
+
+    let ready: Vec<NodeHandler> = order.iter()
+        .filter(|node| document.get_node_input_connections(**node).is_empty())
+        .copied()
+        .collect();
+    let shared_state = &state;
+    rayon::scope(|scope| {
+        for node in ready {
+            scope.spawn(move |scope| evaluate_parallel_node(scope, document, shared_state, node));
+        }
+    });
+
+    let state = state.into_inner().expect("evaluation thread panicked");
+    // the error of the earliest node in the topological order is the one `evaluate` stops at,
+    // everything before it has been evaluated successfully in both modes
+    let first_error = state.errors.into_iter().min_by_key(|(node, _)| positions[node]);
+    if let Some((_, error)) = first_error {
+        return Err(error);
+    }
+
+    let mut evaluation = state.evaluation;
+    evaluation.invoked = order.clone();
+    evaluation.order = order;
+    Ok(evaluation)
+}
+
+struct ParallelEvaluationState {
+    evaluation: NodesEvaluation,
+    dependencies_count: HashMap<NodeHandler, usize>,
+    errors: Vec<(NodeHandler, NodesEvaluationError)>,
+}
+
+fn evaluate_parallel_node<'a>(
+    scope: &rayon::Scope<'a>,
+    document: &'a (dyn NodesDocument + Sync),
+    state: &'a Mutex<ParallelEvaluationState>,
+    handler: NodeHandler,
+) {
+    // inputs are copied under the lock, the node itself is invoked without it
+    let inputs = collect_inputs(document, &state.lock().expect("evaluation thread panicked").evaluation, handler);
+    let result = inputs.and_then(|(input_types, inputs)| invoke_node(document, handler, input_types, inputs));
+
+    let mut ready = Vec::new();
+    {
+        let mut locked = state.lock().expect("evaluation thread panicked");
+        let (output_types, outputs) = match result {
+            Ok(result) => result,
+            // downstream nodes are never scheduled, they can't be evaluated anyway
+            Err(error) => return locked.errors.push((handler, error)),
+        };
+        locked.evaluation.insert(handler, output_types, outputs);
+
+        for connection_handler in document.get_node_output_connections(handler) {
+            if let Some(connection) = document.get_connection(connection_handler) {
+                if let Some(count) = locked.dependencies_count.get_mut(&connection.to.node) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.push(connection.to.node);
+                    }
+                }
+            }
+        }
+    }
+
+    for node in ready {
+        scope.spawn(move |scope| evaluate_parallel_node(scope, document, state, node));
+    }
+}
+
+fn invoke_node(
+    document: &dyn NodesDocument,
+    handler: NodeHandler,
+    input_types: Vec<VariantType>,
+    mut inputs: Vec<Variant>,
+) -> Result<(Vec<VariantType>, Vec<Variant>), NodesEvaluationError> {
+    let node = document.get_node(handler).ok_or(NodesEvaluationError::UnknownNode(handler))?;
+
+    let (input_types, outputs_info) = determine_with_conversions(node, &input_types)
+        .map_err(|e| NodesEvaluationError::DetermineFailed(handler, e))?;
+    for (i, (input, input_type)) in inputs.iter_mut().zip(input_types.iter()).enumerate() {
+        if input.get_type() != *input_type {
+            let mut error = NodeError::new("implicit conversion failed");
+            error.failed_inputs.push((i, format!("{:?} can't be converted to {:?}", input.get_type(), input_type)));
+            *input = input.convert_to(input_type).ok_or(NodesEvaluationError::DetermineFailed(handler, error))?;
+        }
+    }
+    let output_types: Vec<VariantType> = outputs_info.into_iter().map(|info| info.variant_type).collect();
+
+    let mut outputs: Vec<Variant> = output_types.iter().map(|t| Variant::new(t.clone())).collect();
+    node.invoke(&mut inputs, &mut outputs)
+        .map_err(|e| NodesEvaluationError::InvokeFailed(handler, e))?;
+    Ok((output_types, outputs))
+}
+
+// output types of every node which can be determined without invoking the graph,
+// nodes whose determination fails are skipped together with everything downstream of them
+pub fn determine_types(document: &dyn NodesDocument) -> Result<HashMap<NodeHandler, Vec<VariantType>>, NodesEvaluationError> {
+    determine_types_with_overrides(document, &HashMap::new())
+}
+
+// type counterpart of `evaluate_with_overrides`
+pub fn determine_types_with_overrides(
+    document: &dyn NodesDocument,
+    overrides: &HashMap<NodeHandler, Vec<VariantType>>,
+) -> Result<HashMap<NodeHandler, Vec<VariantType>>, NodesEvaluationError> {
+    let order = get_topological_order(document)?;
+    let mut types: HashMap<NodeHandler, Vec<VariantType>> = HashMap::new();
+
+    'nodes: for handler in order {
+        if let Some(output_types) = overrides.get(&handler) {
+            types.insert(handler, output_types.clone());
+            continue;
+        }
+
+        let node = match document.get_node(handler) {
+            Some(node) => node,
+            None => continue,
+        };
+        let inputs_info = match node.get_inputs() {
+            Ok(inputs_info) => inputs_info,
+            Err(_) => continue,
+        };
+
+        let mut input_types = vec![VariantType::Null; inputs_info.len()];
+        for connection_handler in document.get_node_input_connections(handler) {
+            if let Some(connection) = document.get_connection(connection_handler) {
+                let output_type = types.get(&connection.from.node)
+                    .and_then(|output_types| output_types.get(connection.from.slot_index as usize));
+                match (input_types.get_mut(connection.to.slot_index as usize), output_type) {
+                    (Some(input_type), Some(output_type)) => *input_type = output_type.clone(),
+                    _ => continue 'nodes,
+                }
+            }
+        }
+
+        if let Ok((_, outputs_info)) = determine_with_conversions(node, &input_types) {
+            types.insert(handler, outputs_info.into_iter().map(|info| info.variant_type).collect());
+        }
+    }
+
+    Ok(types)
+}
+
+// inputs rejected by `determine` are implicitly converted to the closest type the node accepts,
+// returns the converted input types together with the outputs
+pub fn determine_with_conversions(
+    node: &dyn Node,
+    input: &[VariantType],
+) -> Result<(Vec<VariantType>, Vec<NodeOutputSlotInfo>), NodeError> {
+    let failed_inputs: Vec<usize> = match node.determine(input) {
+        Ok(outputs_info) => return Ok((input.to_vec(), outputs_info)),
+        Err(error) => error.failed_inputs.iter().map(|(i, _)| *i).collect(),
+    };
+
+    let mut input_types = input.to_vec();
+    for i in failed_inputs {
+        let original_type = match input.get(i) {
+            Some(original_type) => original_type.clone(),
+            None => continue,
+        };
+        let accepted = original_type.get_conversion_targets().into_iter().find(|target| {
+            input_types[i] = target.clone();
+            match node.determine(&input_types) {
+                Ok(_) => true,
+                Err(error) => error.failed_inputs.iter().all(|(failed, _)| *failed != i),
+            }
+        });
+        input_types[i] = accepted.unwrap_or(original_type);
+    }
+
+    let outputs_info = node.determine(&input_types)?;
+    Ok((input_types, outputs_info))
+}
+
+// unconnected inputs are passed as Null, nodes are free to reject them in determine
+fn collect_inputs(
+    document: &dyn NodesDocument,
+    evaluation: &NodesEvaluation,
+    handler: NodeHandler,
+) -> Result<(Vec<VariantType>, Vec<Variant>), NodesEvaluationError> {
+    let node = document.get_node(handler).ok_or(NodesEvaluationError::UnknownNode(handler))?;
+    let inputs_info = node.get_inputs()
+        .map_err(|e| NodesEvaluationError::DetermineFailed(handler, e))?;
+
+    let mut input_types = vec![VariantType::Null; inputs_info.len()];
+    let mut inputs = vec![Variant::Null; inputs_info.len()];
+    for connection_handler in document.get_node_input_connections(handler) {
+        let connection = match document.get_connection(connection_handler) {
+            Some(connection) => connection,
+            None => continue,
+        };
+        let input_index = connection.to.slot_index as usize;
+        let output_index = connection.from.slot_index as usize;
+        let value = evaluation.get_output(connection.from.node, output_index);
+        if input_index >= inputs.len() || value.is_none() {
+            let mut error = NodeError::new("connection refers to unexisting slot");
+            error.failed_inputs.push((input_index, connection.to.slot_name.clone()));
+            return Err(NodesEvaluationError::DetermineFailed(handler, error));
+        }
+        let value = value.unwrap();
+        input_types[input_index] = value.get_type();
+        inputs[input_index] = value.clone();
+    }
+
+    Ok((input_types, inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::nodes_evaluator::*;
+    use crate::test_nodes::*;
+
+    #[test]
+    fn evaluate_sum_test() {
+        let mut document = NodesDocumentImpl::new();
+        let sum = document.add_node(Box::new(TestSum {}));
+        let a = document.add_node(Box::new(TestConstant { value: 2. }));
+        let b = document.add_node(Box::new(TestConstant { value: 3. }));
+        connect(&mut document, a, sum, 0);
+        connect(&mut document, b, sum, 1);
+
+        let evaluation = evaluate(&document).unwrap();
+        assert_eq!(evaluation.order, vec![a, b, sum]);
+        assert_eq!(evaluation.get_output_type(sum, 0), Some(VariantType::RealNumber));
+        assert_eq!(evaluation.get_output(sum, 0), Some(&Variant::RealNumber(5.)));
+    }
+
+    #[test]
+    fn evaluate_unconnected_input_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 2. }));
+        let sum = document.add_node(Box::new(TestSum {}));
+        connect(&mut document, a, sum, 0);
+
+        match evaluate(&document) {
+            Err(NodesEvaluationError::DetermineFailed(node, error)) => {
+                assert_eq!(node, sum);
+                assert_eq!(error.failed_inputs.len(), 1);
+                assert_eq!(error.failed_inputs[0].0, 1);
+            },
+            _ => panic!("expected determine error"),
+        }
+    }
+
+    #[test]
+    fn evaluate_conversion_test() {
+        let mut document = NodesDocumentImpl::new();
+        let flag = document.add_node(Box::new(TestFlag {}));
+        let a = document.add_node(Box::new(TestConstant { value: 2. }));
+        let sum = document.add_node(Box::new(TestSum {}));
+        connect(&mut document, flag, sum, 0);
+        connect(&mut document, a, sum, 1);
+
+        let evaluation = evaluate(&document).unwrap();
+        assert_eq!(evaluation.get_output(sum, 0), Some(&Variant::RealNumber(3.)));
+
+        let (input_types, _) = determine_with_conversions(&TestSum {}, &[VariantType::Boolean, VariantType::IntegerNumber]).unwrap();
+        assert_eq!(input_types, vec![VariantType::RealNumber, VariantType::RealNumber]);
+        let error = determine_with_conversions(&TestSum {}, &[VariantType::Vector2, VariantType::IntegerNumber]).err().unwrap();
+        assert_eq!(error.failed_inputs.len(), 1);
+    }
+
+    #[test]
+    fn evaluate_parallel_test() {
+        let mut document = NodesDocumentImpl::new();
+        let mut last = document.add_node(Box::new(TestConstant { value: 1. }));
+        for i in 0..32 {
+            let constant = document.add_node(Box::new(TestConstant { value: i as f64 }));
+            let sum = document.add_node(Box::new(TestSum {}));
+            connect(&mut document, last, sum, 0);
+            connect(&mut document, constant, sum, 1);
+            let branch = document.add_node(Box::new(TestSum {}));
+            connect(&mut document, constant, branch, 0);
+            connect(&mut document, constant, branch, 1);
+            last = sum;
+        }
+
+        let sequential = evaluate(&document).unwrap();
+        let parallel = evaluate_parallel(&document).unwrap();
+        assert_eq!(parallel.order, sequential.order);
+        assert_eq!(parallel.outputs, sequential.outputs);
+        assert_eq!(parallel.output_types, sequential.output_types);
+        assert_eq!(parallel.get_output(last, 0), Some(&Variant::RealNumber(497.)));
+
+        let broken = document.add_node(Box::new(TestSum {}));
+        connect(&mut document, last, broken, 0);
+        match (evaluate(&document), evaluate_parallel(&document)) {
+            (Err(NodesEvaluationError::DetermineFailed(a, _)), Err(NodesEvaluationError::DetermineFailed(b, _))) => {
+                assert_eq!(a, broken);
+                assert_eq!(b, broken);
+            },
+            _ => panic!("expected determine errors"),
+        }
+    }
+
+    #[test]
+    fn evaluate_cycle_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let first = document.add_node(Box::new(TestSum {}));
+        let second = document.add_node(Box::new(TestSum {}));
+        connect(&mut document, a, first, 0);
+        connect(&mut document, second, first, 1);
+        connect(&mut document, first, second, 0);
+
+        match evaluate(&document) {
+            Err(NodesEvaluationError::Cycle(nodes)) => assert_eq!(nodes, vec![first, second]),
+            _ => panic!("expected cycle error"),
+        }
+    }
+}
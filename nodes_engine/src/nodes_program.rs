@@ -1,7 +1,29 @@
 use crate::{ Variant, VariantType, Node, NodeError, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::{ NodesDocument, NodesDocumentImpl, NodeHandler, NodeDocumentSlot, NodeDocumentConnection, NodesEvaluationError };
+use crate::NodeRegistry;
+use crate::nodes_document_file::{ NodesDocumentFile, invalid_data };
+use crate::nodes_evaluator;
+use serde::{ Serialize, Deserialize };
+use std::collections::{ HashMap, HashSet };
+use std::sync::Arc;
 
+pub const NODES_PROGRAM_TYPE_ID: &str = "std.nodes_program";
+
+// the inner document with the bindings of program slots, saved as the `program` parameter
+#[derive(Serialize, Deserialize)]
+struct NodesProgramFile {
+    document: NodesDocumentFile,
+    inputs: Vec<(String, NodeHandler)>,
+    outputs: Vec<(String, NodeHandler)>,
+}
+
+// node with the graph inside, program inputs and outputs are bound to
+// `ProgramInput` and `ProgramOutput` nodes of the inner document
 pub struct NodesProgram {
     name: String,
+    document: NodesDocumentImpl,
+    inputs: Vec<(String, NodeHandler)>,
+    outputs: Vec<(String, NodeHandler)>,
 }
 
 impl Node for NodesProgram {
@@ -9,23 +31,443 @@ impl Node for NodesProgram {
         self.name.clone()
     }
 
-    fn invoke(&self, _inputs: &mut [Variant], _outputs: &mut [Variant]) -> Result<(), NodeError> {
-        unimplemented!()
+    fn get_type_id(&self) -> String {
+        String::from(NODES_PROGRAM_TYPE_ID)
+    }
+
+    fn get_parameters(&self) -> Vec<(String, Variant)> {
+        let file = NodesProgramFile {
+            document: self.document.to_file(),
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+        };
+        let program = ron::ser::to_string(&file).expect("nodes program is serializable");
+        vec![
+            (String::from("name"), Variant::String(self.name.clone())),
+            (String::from("program"), Variant::String(program)),
+        ]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: Variant) -> Result<(), NodeError> {
+        match (name, value) {
+            ("name", Variant::String(value)) => {
+                self.name = value;
+                Ok(())
+            },
+            ("program", Variant::String(value)) => self.load(&value).map_err(|e| NodeError::new(&format!("invalid program: {}", e))),
+            _ => Err(NodeError::new(&format!("unknown parameter {}", name))),
+        }
+    }
+
+    fn invoke(&self, inputs: &mut [Variant], outputs: &mut [Variant]) -> Result<(), NodeError> {
+        if inputs.len() != self.inputs.len() || outputs.len() != self.outputs.len() {
+            return Err(NodeError::new("program slots count mismatch"));
+        }
+
+        let mut overrides = HashMap::new();
+        for ((_, handler), value) in self.inputs.iter().zip(inputs.iter()) {
+            overrides.insert(*handler, vec![value.clone()]);
+        }
+        let evaluation = nodes_evaluator::evaluate_with_overrides(&self.document, &overrides)
+            .map_err(|e| self.map_evaluation_error(e))?;
+
+        for ((name, handler), output) in self.outputs.iter().zip(outputs.iter_mut()) {
+            *output = evaluation.get_output(*handler, 0)
+                .cloned()
+                .ok_or_else(|| NodeError::new(&format!("program output {} is not evaluated", name)))?;
+        }
+        Ok(())
     }
 
     fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
-        unimplemented!()
+        Ok(self.inputs.iter().map(|(name, _)| NodeInputSlotInfo { name: name.clone() }).collect())
     }
 
-    fn determine(&self, _inputs: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
-        unimplemented!()
+    fn determine(&self, inputs: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        if inputs.len() != self.inputs.len() {
+            return Err(NodeError::new(&format!("expected {} inputs but got {}", self.inputs.len(), inputs.len())));
+        }
+
+        let mut overrides = HashMap::new();
+        for ((_, handler), input_type) in self.inputs.iter().zip(inputs.iter()) {
+            overrides.insert(*handler, vec![input_type.clone()]);
+        }
+        let types = nodes_evaluator::determine_types_with_overrides(&self.document, &overrides)
+            .map_err(|e| self.map_evaluation_error(e))?;
+
+        let mut result = Vec::with_capacity(self.outputs.len());
+        for (name, handler) in &self.outputs {
+            let variant_type = types.get(handler)
+                .and_then(|output_types| output_types.first())
+                .ok_or_else(|| NodeError::new(&format!("type of program output {} can't be determined", name)))?;
+            result.push(NodeOutputSlotInfo { name: name.clone(), variant_type: variant_type.clone() });
+        }
+        Ok(result)
     }
 }
 
 impl NodesProgram {
     pub fn new() -> NodesProgram {
+        NodesProgram::with_document(NodesDocumentImpl::new())
+    }
+
+    pub fn with_document(document: NodesDocumentImpl) -> NodesProgram {
         NodesProgram {
             name: String::new(),
+            document,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    // program for `NodeRegistry`, it can't create its inner nodes,
+    // `NodesDocumentImpl::create_node` creates programs bound to the registry of the document instead
+    pub fn new_unbound() -> Box<dyn Node> {
+        let registry = Arc::new(NodeRegistry::new());
+        Box::new(NodesProgram::with_document(NodesDocumentImpl::with_registry(registry)))
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = String::from(name);
+    }
+
+    pub fn get_document(&self) -> &NodesDocumentImpl {
+        &self.document
+    }
+
+    // program inputs and outputs refer to inner nodes by handlers, removing them breaks the program
+    pub fn get_document_mut(&mut self) -> &mut NodesDocumentImpl {
+        &mut self.document
+    }
+
+    // adds a `ProgramInput` node to the inner document, returns its handler
+    pub fn add_input(&mut self, name: &str) -> NodeHandler {
+        let handler = self.document.add_node(Box::new(ProgramInput {}));
+        self.inputs.push((String::from(name), handler));
+        handler
+    }
+
+    // adds a `ProgramOutput` node to the inner document, returns its handler
+    pub fn add_output(&mut self, name: &str) -> NodeHandler {
+        let handler = self.document.add_node(Box::new(ProgramOutput {}));
+        self.outputs.push((String::from(name), handler));
+        handler
+    }
+
+    pub fn get_input_nodes(&self) -> &[(String, NodeHandler)] {
+        &self.inputs
+    }
+
+    pub fn get_output_nodes(&self) -> &[(String, NodeHandler)] {
+        &self.outputs
+    }
+
+    // moves `selection` into a new program node; connections crossing the selection border
+    // are routed through program inputs and outputs, returns the handler of the program node
+    pub fn collapse(document: &mut NodesDocumentImpl, selection: &[NodeHandler], name: &str) -> Result<NodeHandler, NodeError> {
+        let selected: HashSet<NodeHandler> = selection.iter().copied().collect();
+        if selected.is_empty() {
+            return Err(NodeError::new("nothing to collapse"));
+        }
+        if let Some(unknown) = selection.iter().find(|handler| document.get_node(**handler).is_none()) {
+            return Err(NodeError::new(&format!("node {:?} doesn't exist", unknown)));
+        }
+
+        // a node outside of the selection both fed by it and feeding it would make a cycle through the program
+        for handler in document.get_nodes_list() {
+            if !selected.contains(&handler)
+                && selected.iter().any(|s| document.is_reachable(*s, handler))
+                && selected.iter().any(|s| document.is_reachable(handler, *s))
+            {
+                return Err(NodeError::new(&format!("node {:?} is between selected nodes, the selection can't be collapsed", handler)));
+            }
+        }
+
+        let mut sorted_selection: Vec<NodeHandler> = selected.iter().copied().collect();
+        sorted_selection.sort();
+        let mut removed_nodes = Vec::new();
+        let mut connections = HashMap::new();
+        for handler in sorted_selection {
+            let removed = document.remove_node(handler).expect("selected node exists");
+            for (connection_handler, connection) in &removed.connections {
+                connections.insert(*connection_handler, connection.clone());
+            }
+            removed_nodes.push(removed);
+        }
+        let mut connections: Vec<_> = connections.into_iter().collect();
+        connections.sort_by_key(|(connection_handler, _)| *connection_handler);
+
+        let mut program = NodesProgram::with_document(NodesDocumentImpl::with_registry(document.get_registry().clone()));
+        program.set_name(name);
+        let mut handlers = HashMap::new();
+        let mut center = nalgebra::Vector2::new(0., 0.);
+        for removed in removed_nodes {
+            let handler = program.document.add_node(removed.node);
+            program.document.set_node_position(handler, removed.position);
+            center += removed.position;
+            handlers.insert(removed.handler, handler);
+        }
+        center /= handlers.len() as f32;
+
+        // outer slots feeding the program and inner slots read outside, one program slot per distinct source
+        let mut input_sources: Vec<NodeDocumentSlot> = Vec::new();
+        let mut output_sources: Vec<NodeDocumentSlot> = Vec::new();
+        let mut outer_outputs = Vec::new();
+        for (_, connection) in connections {
+            let inner_slot = |slot: &NodeDocumentSlot| NodeDocumentSlot { node: handlers[&slot.node], ..slot.clone() };
+            match (selected.contains(&connection.from.node), selected.contains(&connection.to.node)) {
+                (true, true) => {
+                    program.document.add_connection(NodeDocumentConnection {
+                        from: inner_slot(&connection.from),
+                        to: inner_slot(&connection.to),
+                    });
+                },
+                (false, true) => {
+                    let index = match input_sources.iter().position(|source| *source == connection.from) {
+                        Some(index) => index,
+                        None => {
+                            input_sources.push(connection.from.clone());
+                            let input = program.add_input(&format!("input{}", input_sources.len() - 1));
+                            program.document.set_node_position(input, center);
+                            input_sources.len() - 1
+                        },
+                    };
+                    let input = program.inputs[index].1;
+                    program.document.add_connection(NodeDocumentConnection { from: program_slot(input, 0), to: inner_slot(&connection.to) });
+                },
+                (true, false) => {
+                    let source = inner_slot(&connection.from);
+                    let index = match output_sources.iter().position(|s| *s == source) {
+                        Some(index) => index,
+                        None => {
+                            output_sources.push(source.clone());
+                            let output = program.add_output(&format!("output{}", output_sources.len() - 1));
+                            program.document.add_connection(NodeDocumentConnection { from: source, to: program_slot(output, 0) });
+                            program.document.set_node_position(output, center);
+                            output_sources.len() - 1
+                        },
+                    };
+                    outer_outputs.push((index, connection.to.clone()));
+                },
+                (false, false) => {},
+            }
+        }
+
+        let program_handler = document.add_node(Box::new(program));
+        document.set_node_position(program_handler, center);
+        for (index, source) in input_sources.into_iter().enumerate() {
+            document.add_connection(NodeDocumentConnection { from: source, to: program_slot(program_handler, index) });
+        }
+        for (index, destination) in outer_outputs {
+            document.add_connection(NodeDocumentConnection { from: program_slot(program_handler, index), to: destination });
+        }
+        Ok(program_handler)
+    }
+
+    // replaces the inner document, slot bindings have to refer to program input and output nodes
+    fn load(&mut self, text: &str) -> std::io::Result<()> {
+        let file: NodesProgramFile = ron::de::from_str(text).map_err(invalid_data)?;
+        let document = NodesDocumentImpl::from_file(self.document.get_registry().clone(), file.document)?;
+        let is_bound = |bindings: &[(String, NodeHandler)], type_id: &str| bindings.iter().all(|(_, handler)| {
+            document.get_node(*handler).map(|node| node.get_type_id() == type_id).unwrap_or(false)
+        });
+        if !is_bound(&file.inputs, "std.program_input") || !is_bound(&file.outputs, "std.program_output") {
+            return Err(invalid_data("program slots are bound to unknown nodes"));
+        }
+        self.document = document;
+        self.inputs = file.inputs;
+        self.outputs = file.outputs;
+        Ok(())
+    }
+
+    fn map_evaluation_error(&self, error: NodesEvaluationError) -> NodeError {
+        NodeError::new(&format!("program {} failed: {:?}", self.name, error))
+    }
+}
+
+fn program_slot(node: NodeHandler, slot_index: usize) -> NodeDocumentSlot {
+    NodeDocumentSlot { node, slot_name: String::new(), slot_index: slot_index as u64 }
+}
+
+// argument of the program, its output is replaced by the program input value during evaluation
+pub struct ProgramInput {}
+
+impl ProgramInput {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(ProgramInput {})
+    }
+}
+
+impl Node for ProgramInput {
+
+    fn get_name(&self) -> String {
+        String::from("Program Input")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.program_input")
+    }
+
+    fn invoke(&self, _input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        output[0] = Variant::Null;
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(Vec::new())
+    }
+
+    fn determine(&self, _input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        Ok(vec![NodeOutputSlotInfo { name: String::from("value"), variant_type: VariantType::Null }])
+    }
+}
+
+// result of the program, passes its input through
+pub struct ProgramOutput {}
+
+impl ProgramOutput {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(ProgramOutput {})
+    }
+}
+
+impl Node for ProgramOutput {
+
+    fn get_name(&self) -> String {
+        String::from("Program Output")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.program_output")
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        output[0] = input[0].clone();
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(vec![NodeInputSlotInfo { name: String::from("value") }])
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        match input {
+            [VariantType::Null] => {
+                let mut error = NodeError::new("program output is not connected");
+                error.failed_inputs.push((0, String::from("input is not connected")));
+                Err(error)
+            },
+            [variant_type] => Ok(vec![NodeOutputSlotInfo { name: String::from("value"), variant_type: variant_type.clone() }]),
+            _ => Err(NodeError::new("program output expects one input")),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::nodes_program::*;
+    use crate::test_nodes::*;
+
+    #[test]
+    fn program_test() {
+        let mut program = NodesProgram::new();
+        let a = program.add_input("a");
+        let b = program.add_input("b");
+        let result = program.add_output("result");
+        let sum = program.get_document_mut().add_node(Box::new(TestSum {}));
+        connect(program.get_document_mut(), a, sum, 0);
+        connect(program.get_document_mut(), b, sum, 1);
+        connect(program.get_document_mut(), sum, result, 0);
+
+        assert_eq!(program.get_inputs().unwrap().len(), 2);
+        let outputs = program.determine(&[VariantType::RealNumber, VariantType::IntegerNumber]).unwrap();
+        assert_eq!(outputs[0].name, "result");
+        assert_eq!(outputs[0].variant_type, VariantType::RealNumber);
+        assert!(program.determine(&[VariantType::RealNumber, VariantType::Matrix2]).is_err());
+
+        let mut output = vec![Variant::Null];
+        program.invoke(&mut [Variant::RealNumber(1.), Variant::RealNumber(2.)], &mut output).unwrap();
+        assert_eq!(output[0], Variant::RealNumber(3.));
+
+        let mut document = NodesDocumentImpl::new();
+        let x = document.add_node(Box::new(TestConstant { value: 4. }));
+        let program = document.add_node(Box::new(program));
+        connect(&mut document, x, program, 0);
+        connect(&mut document, x, program, 1);
+        assert_eq!(evaluate(&document).unwrap().get_output(program, 0), Some(&Variant::RealNumber(8.)));
+    }
+
+    #[test]
+    fn collapse_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let b = document.add_node(Box::new(TestConstant { value: 2. }));
+        let first = document.add_node(Box::new(TestSum {}));
+        let second = document.add_node(Box::new(TestSum {}));
+        let last = document.add_node(Box::new(TestSum {}));
+        connect(&mut document, a, first, 0);
+        connect(&mut document, b, first, 1);
+        connect(&mut document, first, second, 0);
+        connect(&mut document, b, second, 1);
+        connect(&mut document, second, last, 0);
+        connect(&mut document, second, last, 1);
+        let expected = evaluate(&document).unwrap().get_output(last, 0).cloned();
+        assert_eq!(expected, Some(Variant::RealNumber(10.)));
+
+        let program = NodesProgram::collapse(&mut document, &[first, second], "sums").unwrap();
+        assert_eq!(document.get_nodes_list().len(), 4);
+        assert_eq!(document.get_node(program).unwrap().get_inputs().unwrap().len(), 2);
+        assert_eq!(document.get_node_input_connections(program).len(), 2);
+        assert_eq!(document.get_node_output_connections(program).len(), 2);
+        assert_eq!(evaluate(&document).unwrap().get_output(last, 0).cloned(), expected);
+
+        assert!(NodesProgram::collapse(&mut document, &[first], "unknown").is_err());
+    }
+
+    #[test]
+    fn collapse_between_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let b = document.add_node(Box::new(TestSum {}));
+        let c = document.add_node(Box::new(TestSum {}));
+        connect(&mut document, a, b, 0);
+        connect(&mut document, a, b, 1);
+        connect(&mut document, b, c, 0);
+        connect(&mut document, a, c, 1);
+
+        // b is fed by a and feeds c, so the program would be both before and after it
+        assert!(NodesProgram::collapse(&mut document, &[a, c], "cycle").is_err());
+        assert_eq!(document.get_nodes_list(), vec![a, b, c]);
+        assert_eq!(evaluate(&document).unwrap().get_output(c, 0), Some(&Variant::RealNumber(3.)));
+    }
+
+    #[test]
+    fn collapse_save_load_test() {
+        let mut document = NodesDocumentImpl::with_registry(test_registry());
+        let a = document.add_node(Box::new(TestConstant { value: 1. }));
+        let b = document.add_node(Box::new(TestConstant { value: 2. }));
+        let sum = document.add_node(Box::new(TestSum {}));
+        let last = document.add_node(Box::new(TestSum {}));
+        connect(&mut document, a, sum, 0);
+        connect(&mut document, b, sum, 1);
+        connect(&mut document, sum, last, 0);
+        connect(&mut document, b, last, 1);
+        let program = NodesProgram::collapse(&mut document, &[sum], "sum").unwrap();
+
+        let mut text = Vec::new();
+        document.save(&mut text).unwrap();
+        let mut loaded = NodesDocumentImpl::with_registry(test_registry());
+        loaded.load(&mut text.as_slice()).unwrap();
+        assert_eq!(loaded.get_node(program).unwrap().get_name(), "sum");
+        assert_eq!(loaded.get_node(program).unwrap().get_inputs().unwrap().len(), 2);
+        assert_eq!(evaluate(&loaded).unwrap().get_output(last, 0), Some(&Variant::RealNumber(5.)));
+
+        let mut saved_again = Vec::new();
+        loaded.save(&mut saved_again).unwrap();
+        assert_eq!(saved_again, text);
+
+        let mut broken = NodesProgram::new();
+        assert!(broken.set_parameter("program", Variant::String(String::from("()"))).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::std_nodes::utils::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ArithmeticOperation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    Min,
+    Max,
+}
+
+pub const ARITHMETIC_OPERATIONS: [ArithmeticOperation; 7] = [
+    ArithmeticOperation::Add,
+    ArithmeticOperation::Subtract,
+    ArithmeticOperation::Multiply,
+    ArithmeticOperation::Divide,
+    ArithmeticOperation::Power,
+    ArithmeticOperation::Min,
+    ArithmeticOperation::Max,
+];
+
+impl ArithmeticOperation {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ArithmeticOperation::Add => "Add",
+            ArithmeticOperation::Subtract => "Subtract",
+            ArithmeticOperation::Multiply => "Multiply",
+            ArithmeticOperation::Divide => "Divide",
+            ArithmeticOperation::Power => "Power",
+            ArithmeticOperation::Min => "Min",
+            ArithmeticOperation::Max => "Max",
+        }
+    }
+
+    fn apply_real(&self, a: f64, b: f64) -> f64 {
+        match self {
+            ArithmeticOperation::Add => a + b,
+            ArithmeticOperation::Subtract => a - b,
+            ArithmeticOperation::Multiply => a * b,
+            ArithmeticOperation::Divide => a / b,
+            ArithmeticOperation::Power => a.powf(b),
+            ArithmeticOperation::Min => a.min(b),
+            ArithmeticOperation::Max => a.max(b),
+        }
+    }
+
+    fn apply_integer(&self, a: i64, b: i64) -> Option<i64> {
+        match self {
+            ArithmeticOperation::Add => a.checked_add(b),
+            ArithmeticOperation::Subtract => a.checked_sub(b),
+            ArithmeticOperation::Multiply => a.checked_mul(b),
+            ArithmeticOperation::Divide => a.checked_div(b),
+            ArithmeticOperation::Power => {
+                if b < 0 || b > u32::MAX as i64 {
+                    None
+                } else {
+                    a.checked_pow(b as u32)
+                }
+            },
+            ArithmeticOperation::Min => Some(a.min(b)),
+            ArithmeticOperation::Max => Some(a.max(b)),
+        }
+    }
+}
+
+// element-wise binary operation over scalars and vectors
+pub struct Arithmetic {
+    operation: ArithmeticOperation,
+}
+
+impl Arithmetic {
+    pub fn new(operation: ArithmeticOperation) -> Box<dyn Node> {
+        Box::new(
+            Arithmetic {
+                operation
+            }
+        )
+    }
+}
+
+impl Node for Arithmetic {
+
+    fn get_name(&self) -> String {
+        String::from(self.operation.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        format!("std.{}", self.operation.get_name().to_lowercase())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        let output_type = self.determine(&get_input_types(input))?.remove(0).variant_type;
+        output[0] = if output_type == VariantType::IntegerNumber {
+            apply_integer(input, |v| self.operation.apply_integer(v[0], v[1]))?
+        } else {
+            apply_real(&output_type, input, |v| self.operation.apply_real(v[0], v[1]))?
+        };
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["a", "b"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 2)?;
+        Ok(output_slot("result", broadcast_types(input)?))
+    }
+}
+
+pub struct Clamp {}
+
+impl Clamp {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(Clamp {})
+    }
+}
+
+impl Node for Clamp {
+
+    fn get_name(&self) -> String {
+        String::from("Clamp")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.clamp")
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        let output_type = self.determine(&get_input_types(input))?.remove(0).variant_type;
+        output[0] = if output_type == VariantType::IntegerNumber {
+            apply_integer(input, |v| Some(v[0].max(v[1]).min(v[2])))?
+        } else {
+            apply_real(&output_type, input, |v| v[0].max(v[1]).min(v[2]))?
+        };
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["value", "min", "max"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 3)?;
+        Ok(output_slot("result", broadcast_types(input)?))
+    }
+}
+
+pub struct Lerp {}
+
+impl Lerp {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(Lerp {})
+    }
+}
+
+impl Node for Lerp {
+
+    fn get_name(&self) -> String {
+        String::from("Lerp")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.lerp")
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        let output_type = self.determine(&get_input_types(input))?.remove(0).variant_type;
+        output[0] = apply_real(&output_type, input, |v| v[0] + (v[1] - v[0]) * v[2])?;
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["a", "b", "t"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 3)?;
+        Ok(output_slot("result", broadcast_real_types(input)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::std_nodes::arithmetic::*;
+    use crate::std_nodes::utils::invoke;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn arithmetic_broadcast_test() {
+        let add = Arithmetic::new(ArithmeticOperation::Add);
+        let types = |a, b| add.determine(&[a, b]).map(|mut outputs| outputs.remove(0).variant_type);
+        assert_eq!(types(VariantType::IntegerNumber, VariantType::IntegerNumber).unwrap(), VariantType::IntegerNumber);
+        assert_eq!(types(VariantType::IntegerNumber, VariantType::RealNumber).unwrap(), VariantType::RealNumber);
+        assert_eq!(types(VariantType::RealNumber, VariantType::Vector3).unwrap(), VariantType::Vector3);
+
+        let error = types(VariantType::Vector3, VariantType::RowVector3).unwrap_err();
+        assert_eq!(error.failed_inputs[0].0, 1);
+        let error = types(VariantType::Boolean, VariantType::Null).unwrap_err();
+        assert_eq!(error.failed_inputs.len(), 2);
+
+        let result = invoke(add.as_ref(), vec![Variant::IntegerNumber(2), Variant::Vector3(Vector3::new(1., 2., 3.))]);
+        assert_eq!(result.unwrap(), Variant::Vector3(Vector3::new(3., 4., 5.)));
+    }
+
+    #[test]
+    fn arithmetic_integer_test() {
+        let divide = Arithmetic::new(ArithmeticOperation::Divide);
+        assert_eq!(invoke(divide.as_ref(), vec![Variant::IntegerNumber(7), Variant::IntegerNumber(2)]).unwrap(), Variant::IntegerNumber(3));
+        assert!(invoke(divide.as_ref(), vec![Variant::IntegerNumber(7), Variant::IntegerNumber(0)]).is_err());
+        assert_eq!(invoke(divide.as_ref(), vec![Variant::RealNumber(7.), Variant::IntegerNumber(2)]).unwrap(), Variant::RealNumber(3.5));
+
+        let power = Arithmetic::new(ArithmeticOperation::Power);
+        assert_eq!(invoke(power.as_ref(), vec![Variant::IntegerNumber(3), Variant::IntegerNumber(3)]).unwrap(), Variant::IntegerNumber(27));
+        assert!(invoke(power.as_ref(), vec![Variant::IntegerNumber(3), Variant::IntegerNumber(-1)]).is_err());
+        assert_eq!(power.get_type_id(), "std.power");
+    }
+
+    #[test]
+    fn clamp_lerp_test() {
+        let clamp = Clamp::new();
+        let result = invoke(clamp.as_ref(), vec![
+            Variant::Vector3(Vector3::new(-1., 0.5, 2.)),
+            Variant::RealNumber(0.),
+            Variant::RealNumber(1.),
+        ]);
+        assert_eq!(result.unwrap(), Variant::Vector3(Vector3::new(0., 0.5, 1.)));
+
+        let lerp = Lerp::new();
+        let result = invoke(lerp.as_ref(), vec![Variant::IntegerNumber(0), Variant::IntegerNumber(10), Variant::RealNumber(0.25)]);
+        assert_eq!(result.unwrap(), Variant::RealNumber(2.5));
+    }
+}
@@ -0,0 +1,537 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::std_nodes::utils::*;
+
+fn array_type(element_type: VariantType) -> VariantType {
+    VariantType::Array(Box::new(element_type))
+}
+
+fn expect_array(input: &[VariantType], index: usize, error: &mut NodeError) -> Option<VariantType> {
+    match input[index].get_element_type() {
+        Some(element_type) => Some(element_type.clone()),
+        None => {
+            error.failed_inputs.push((index, format!("array expected but got {:?}", input[index])));
+            None
+        },
+    }
+}
+
+fn get_elements(value: &Variant) -> Result<&[Variant], NodeError> {
+    match value {
+        Variant::Array(_, elements) => Ok(elements),
+        value => Err(NodeError::new(&format!("array expected but got {:?}", value.get_type()))),
+    }
+}
+
+// applies `function` to every element, inputs which are not arrays are passed to every invocation as is
+pub struct ArrayMap {
+    function: Box<dyn Node>,
+}
+
+impl ArrayMap {
+    pub fn new(function: Box<dyn Node>) -> Box<dyn Node> {
+        Box::new(
+            ArrayMap {
+                function
+            }
+        )
+    }
+}
+
+impl Node for ArrayMap {
+
+    fn get_name(&self) -> String {
+        format!("Map {}", self.function.get_name())
+    }
+
+    // the wrapped node is a part of the type, so every mapped operation is registered on its own
+    fn get_type_id(&self) -> String {
+        format!("std.map.{}", self.function.get_type_id())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        let output_types: Vec<VariantType> = self.determine(&get_input_types(input))?
+            .into_iter()
+            .map(|info| info.variant_type.get_element_type().cloned().unwrap_or(VariantType::Null))
+            .collect();
+        check_outputs_count(output, output_types.len())?;
+
+        let mut length = None;
+        let mut error = NodeError::new("mapped arrays have different lengths");
+        for (i, value) in input.iter().enumerate() {
+            if let Variant::Array(_, elements) = value {
+                match length {
+                    None => length = Some(elements.len()),
+                    Some(length) if length == elements.len() => {},
+                    Some(length) => error.failed_inputs.push((i, format!("expected {} elements but got {}", length, elements.len()))),
+                }
+            }
+        }
+        if !error.failed_inputs.is_empty() {
+            return Err(error);
+        }
+
+        let mut results: Vec<Vec<Variant>> = vec![Vec::new(); output_types.len()];
+        for element in 0..length.unwrap_or(0) {
+            let mut arguments: Vec<Variant> = input.iter()
+                .map(|value| match value {
+                    Variant::Array(_, elements) => elements[element].clone(),
+                    value => value.clone(),
+                })
+                .collect();
+            let mut function_output: Vec<Variant> = output_types.iter().map(|t| Variant::new(t.clone())).collect();
+            self.function.invoke(&mut arguments, &mut function_output)?;
+            for (result, value) in results.iter_mut().zip(function_output) {
+                result.push(value);
+            }
+        }
+
+        for ((output, output_type), result) in output.iter_mut().zip(output_types).zip(results) {
+            *output = Variant::Array(output_type, result);
+        }
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        self.function.get_inputs()
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        if input.iter().all(|t| t.get_element_type().is_none()) {
+            return Err(NodeError::new("at least one array input expected"));
+        }
+        let element_types: Vec<VariantType> = input.iter()
+            .map(|t| t.get_element_type().unwrap_or(t).clone())
+            .collect();
+        Ok(self.function.determine(&element_types)?
+            .into_iter()
+            .map(|info| NodeOutputSlotInfo { name: info.name, variant_type: array_type(info.variant_type) })
+            .collect())
+    }
+}
+
+// folds the array with a two-input `function` called as (accumulator, element)
+pub struct ArrayReduce {
+    function: Box<dyn Node>,
+}
+
+impl ArrayReduce {
+    pub fn new(function: Box<dyn Node>) -> Box<dyn Node> {
+        Box::new(
+            ArrayReduce {
+                function
+            }
+        )
+    }
+}
+
+impl Node for ArrayReduce {
+
+    fn get_name(&self) -> String {
+        format!("Reduce {}", self.function.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        format!("std.reduce.{}", self.function.get_type_id())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let mut accumulator = input[1].clone();
+        for element in get_elements(&input[0])? {
+            let mut arguments = vec![accumulator, element.clone()];
+            let mut result = vec![Variant::new(input[1].get_type())];
+            self.function.invoke(&mut arguments, &mut result)?;
+            accumulator = result.remove(0);
+        }
+        output[0] = accumulator;
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["array", "initial"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 2)?;
+        let mut error = NodeError::new("array and initial value expected");
+        let element_type = expect_array(input, 0, &mut error).ok_or(error)?;
+        let function_output = self.function.determine(&[input[1].clone(), element_type])
+            .map_err(|e| NodeError::new(&format!("reduce function can't be determined: {}", e.message)))?;
+        match function_output.first() {
+            Some(info) if function_output.len() == 1 && info.variant_type == input[1] => Ok(output_slot("result", input[1].clone())),
+            _ => {
+                let mut error = NodeError::new("reduce function has to return one value of the initial value type");
+                error.failed_inputs.push((1, format!("{:?} doesn't match the reduce function result", input[1])));
+                Err(error)
+            },
+        }
+    }
+}
+
+// keeps the elements whose mask value is true
+pub struct ArrayFilter {}
+
+impl ArrayFilter {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(ArrayFilter {})
+    }
+}
+
+impl Node for ArrayFilter {
+
+    fn get_name(&self) -> String {
+        String::from("Filter")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.filter")
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let elements = get_elements(&input[0])?;
+        let mask = get_elements(&input[1])?;
+        if elements.len() != mask.len() {
+            let mut error = NodeError::new("array and mask have different lengths");
+            error.failed_inputs.push((1, format!("expected {} elements but got {}", elements.len(), mask.len())));
+            return Err(error);
+        }
+        let filtered = elements.iter()
+            .zip(mask.iter())
+            .filter(|(_, keep)| **keep == Variant::Boolean(true))
+            .map(|(element, _)| element.clone())
+            .collect();
+        output[0] = Variant::Array(input[0].get_type().get_element_type().cloned().unwrap_or(VariantType::Null), filtered);
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["array", "mask"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 2)?;
+        let mut error = NodeError::new("array and boolean mask expected");
+        expect_array(input, 0, &mut error);
+        if input[1] != array_type(VariantType::Boolean) {
+            error.failed_inputs.push((1, format!("expected array of Boolean but got {:?}", input[1])));
+        }
+        if error.failed_inputs.is_empty() {
+            Ok(output_slot("result", input[0].clone()))
+        } else {
+            Err(error)
+        }
+    }
+}
+
+pub struct ArrayIndex {}
+
+impl ArrayIndex {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(ArrayIndex {})
+    }
+}
+
+impl Node for ArrayIndex {
+
+    fn get_name(&self) -> String {
+        String::from("Index")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.index")
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let elements = get_elements(&input[0])?;
+        let index = match input[1] {
+            Variant::IntegerNumber(index) => index,
+            _ => -1,
+        };
+        if index < 0 || index as usize >= elements.len() {
+            let mut error = NodeError::new("index out of range");
+            error.failed_inputs.push((1, format!("index {} is out of range 0..{}", index, elements.len())));
+            return Err(error);
+        }
+        output[0] = elements[index as usize].clone();
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["array", "index"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 2)?;
+        let mut error = NodeError::new("array and integer index expected");
+        let element_type = expect_array(input, 0, &mut error);
+        if input[1] != VariantType::IntegerNumber {
+            error.failed_inputs.push((1, describe_unsupported_input(&input[1])));
+        }
+        match element_type {
+            Some(element_type) if error.failed_inputs.is_empty() => Ok(output_slot("element", element_type)),
+            _ => Err(error),
+        }
+    }
+}
+
+// number of elements of an array or characters of a string
+pub struct ArrayLength {}
+
+impl ArrayLength {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(ArrayLength {})
+    }
+}
+
+impl Node for ArrayLength {
+
+    fn get_name(&self) -> String {
+        String::from("Array Length")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.array_length")
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let length = match &input[0] {
+            Variant::String(value) => value.chars().count(),
+            value => get_elements(value)?.len(),
+        };
+        output[0] = Variant::IntegerNumber(length as i64);
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["array"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 1)?;
+        if input[0] == VariantType::String || input[0].get_element_type().is_some() {
+            return Ok(output_slot("length", VariantType::IntegerNumber));
+        }
+        let mut error = NodeError::new("array or string expected");
+        error.failed_inputs.push((0, describe_unsupported_input(&input[0])));
+        Err(error)
+    }
+}
+
+// joins two arrays converting their elements to the common type, or two strings
+pub struct ArrayConcatenate {}
+
+impl ArrayConcatenate {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(ArrayConcatenate {})
+    }
+}
+
+impl Node for ArrayConcatenate {
+
+    fn get_name(&self) -> String {
+        String::from("Concatenate")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.concatenate")
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        let output_type = self.determine(&get_input_types(input))?.remove(0).variant_type;
+        output[0] = match (&input[0], &input[1]) {
+            (Variant::String(a), Variant::String(b)) => Variant::String(format!("{}{}", a, b)),
+            (a, b) => {
+                let a = a.convert_to(&output_type).ok_or_else(|| NodeError::new("array conversion failed"))?;
+                let b = b.convert_to(&output_type).ok_or_else(|| NodeError::new("array conversion failed"))?;
+                let mut elements = get_elements(&a)?.to_vec();
+                elements.extend_from_slice(get_elements(&b)?);
+                Variant::Array(output_type.get_element_type().cloned().unwrap_or(VariantType::Null), elements)
+            },
+        };
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["a", "b"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 2)?;
+        if input[0] == VariantType::String && input[1] == VariantType::String {
+            return Ok(output_slot("result", VariantType::String));
+        }
+        let mut error = NodeError::new("arrays with compatible elements or strings expected");
+        let a = expect_array(input, 0, &mut error);
+        let b = expect_array(input, 1, &mut error);
+        if let (Some(a), Some(b)) = (a, b) {
+            match VariantType::get_common_type(&[a, b]) {
+                Some(element_type) => return Ok(output_slot("result", array_type(element_type))),
+                None => error.failed_inputs.push((1, format!("{:?} and {:?} have no common element type", input[0], input[1]))),
+            }
+        }
+        Err(error)
+    }
+}
+
+// longest array a range can produce, longer ranges are rejected instead of exhausting memory
+pub const MAX_RANGE_LENGTH: i64 = 1 << 24;
+
+// integers from start up to but not including end
+pub struct Range {}
+
+impl Range {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(Range {})
+    }
+}
+
+impl Node for Range {
+
+    fn get_name(&self) -> String {
+        String::from("Range")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.range")
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let (start, end) = match (&input[0], &input[1]) {
+            (Variant::IntegerNumber(start), Variant::IntegerNumber(end)) => (*start, *end),
+            _ => (0, 0),
+        };
+        // the difference of extreme bounds overflows, such ranges are too long anyway
+        if end.checked_sub(start).map(|length| length > MAX_RANGE_LENGTH).unwrap_or(true) {
+            let mut error = NodeError::new("range is too long");
+            error.failed_inputs.push((1, format!("range {}..{} is longer than {} elements", start, end, MAX_RANGE_LENGTH)));
+            return Err(error);
+        }
+        let elements = (start..end).map(Variant::IntegerNumber).collect();
+        output[0] = Variant::Array(VariantType::IntegerNumber, elements);
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["start", "end"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 2)?;
+        let mut error = NodeError::new("integer bounds expected");
+        for (i, input_type) in input.iter().enumerate() {
+            if *input_type != VariantType::IntegerNumber {
+                error.failed_inputs.push((i, describe_unsupported_input(input_type)));
+            }
+        }
+        if error.failed_inputs.is_empty() {
+            Ok(output_slot("range", array_type(VariantType::IntegerNumber)))
+        } else {
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::std_nodes::array::*;
+    use crate::std_nodes::arithmetic::*;
+    use crate::std_nodes::comparison::*;
+    use crate::std_nodes::utils::invoke;
+
+    fn integers(values: &[i64]) -> Variant {
+        Variant::Array(VariantType::IntegerNumber, values.iter().map(|v| Variant::IntegerNumber(*v)).collect())
+    }
+
+    #[test]
+    fn map_reduce_test() {
+        let map = ArrayMap::new(Arithmetic::new(ArithmeticOperation::Multiply));
+        let array_of_integers = VariantType::Array(Box::new(VariantType::IntegerNumber));
+        let array_of_reals = VariantType::Array(Box::new(VariantType::RealNumber));
+        assert_eq!(map.determine(&[array_of_integers.clone(), VariantType::IntegerNumber]).unwrap()[0].variant_type, array_of_integers);
+        assert_eq!(map.determine(&[array_of_integers.clone(), VariantType::RealNumber]).unwrap()[0].variant_type, array_of_reals);
+        assert!(map.determine(&[VariantType::IntegerNumber, VariantType::IntegerNumber]).is_err());
+
+        let doubled = invoke(map.as_ref(), vec![integers(&[1, 2, 3]), Variant::IntegerNumber(2)]).unwrap();
+        assert_eq!(doubled, integers(&[2, 4, 6]));
+        let squares = invoke(map.as_ref(), vec![integers(&[1, 2, 3]), integers(&[1, 2, 3])]).unwrap();
+        assert_eq!(squares, integers(&[1, 4, 9]));
+        assert!(invoke(map.as_ref(), vec![integers(&[1, 2, 3]), integers(&[1])]).is_err());
+
+        let sum = ArrayReduce::new(Arithmetic::new(ArithmeticOperation::Add));
+        assert_eq!(invoke(sum.as_ref(), vec![squares, Variant::IntegerNumber(0)]).unwrap(), Variant::IntegerNumber(14));
+        assert!(sum.determine(&[array_of_reals, VariantType::IntegerNumber]).is_err());
+    }
+
+    #[test]
+    fn map_save_load_test() {
+        let mut document = NodesDocumentImpl::new();
+        let add_node = |document: &mut NodesDocumentImpl, type_id: &str| {
+            let node = document.create_node(type_id).unwrap();
+            document.add_node(node)
+        };
+        let start = add_node(&mut document, "std.constant_integer");
+        let end = add_node(&mut document, "std.constant_integer");
+        let range = add_node(&mut document, "std.range");
+        let map = add_node(&mut document, "std.map.std.multiply");
+        let reduce = add_node(&mut document, "std.reduce.std.add");
+        document.set_node_parameter(end, "value", Variant::IntegerNumber(4)).unwrap();
+
+        let slot = |node, slot_index| NodeDocumentSlot { node, slot_name: String::new(), slot_index };
+        let mut connect = |from, to, to_index| {
+            document.try_add_connection(NodeDocumentConnection { from: slot(from, 0), to: slot(to, to_index) }).unwrap();
+        };
+        connect(start, range, 0);
+        connect(end, range, 1);
+        connect(range, map, 0);
+        connect(range, map, 1);
+        connect(map, reduce, 0);
+        connect(start, reduce, 1);
+
+        let mut buffer = Vec::new();
+        document.save(&mut buffer).unwrap();
+        let mut loaded = NodesDocumentImpl::new();
+        loaded.load(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(loaded.get_node(map).unwrap().get_type_id(), "std.map.std.multiply");
+        assert_eq!(loaded.get_node(reduce).unwrap().get_name(), "Reduce Add");
+        let evaluation = evaluate(&loaded).unwrap();
+        assert_eq!(evaluation.get_output(map, 0), Some(&integers(&[0, 1, 4, 9])));
+        assert_eq!(evaluation.get_output(reduce, 0), Some(&Variant::IntegerNumber(14)));
+    }
+
+    #[test]
+    fn array_nodes_test() {
+        let range = invoke(Range::new().as_ref(), vec![Variant::IntegerNumber(0), Variant::IntegerNumber(5)]).unwrap();
+        assert_eq!(range, integers(&[0, 1, 2, 3, 4]));
+
+        let less = ArrayMap::new(Comparison::new(ComparisonOperation::Less));
+        let mask = invoke(less.as_ref(), vec![range.clone(), Variant::IntegerNumber(2)]).unwrap();
+        assert_eq!(invoke(ArrayFilter::new().as_ref(), vec![range.clone(), mask]).unwrap(), integers(&[0, 1]));
+
+        assert_eq!(invoke(ArrayIndex::new().as_ref(), vec![range.clone(), Variant::IntegerNumber(3)]).unwrap(), Variant::IntegerNumber(3));
+        assert!(invoke(ArrayIndex::new().as_ref(), vec![range.clone(), Variant::IntegerNumber(5)]).is_err());
+        assert!(invoke(Range::new().as_ref(), vec![Variant::IntegerNumber(0), Variant::IntegerNumber(i64::MAX)]).is_err());
+        assert!(invoke(Range::new().as_ref(), vec![Variant::IntegerNumber(i64::MIN), Variant::IntegerNumber(0)]).is_err());
+        assert_eq!(invoke(Range::new().as_ref(), vec![Variant::IntegerNumber(5), Variant::IntegerNumber(0)]).unwrap(), integers(&[]));
+        assert_eq!(invoke(ArrayLength::new().as_ref(), vec![range.clone()]).unwrap(), Variant::IntegerNumber(5));
+        assert_eq!(invoke(ArrayLength::new().as_ref(), vec![Variant::String(String::from("abc"))]).unwrap(), Variant::IntegerNumber(3));
+
+        let reals = Variant::Array(VariantType::RealNumber, vec![Variant::RealNumber(0.5)]);
+        let joined = invoke(ArrayConcatenate::new().as_ref(), vec![integers(&[1]), reals]).unwrap();
+        assert_eq!(joined, Variant::Array(VariantType::RealNumber, vec![Variant::RealNumber(1.), Variant::RealNumber(0.5)]));
+        let text = invoke(ArrayConcatenate::new().as_ref(), vec![Variant::String(String::from("a")), Variant::String(String::from("b"))]);
+        assert_eq!(text.unwrap(), Variant::String(String::from("ab")));
+        assert!(ArrayConcatenate::new().determine(&[range.get_type(), VariantType::String]).is_err());
+    }
+}
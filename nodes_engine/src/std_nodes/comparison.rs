@@ -0,0 +1,161 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::std_nodes::utils::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ComparisonOperation {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+pub const COMPARISON_OPERATIONS: [ComparisonOperation; 6] = [
+    ComparisonOperation::Less,
+    ComparisonOperation::LessEqual,
+    ComparisonOperation::Greater,
+    ComparisonOperation::GreaterEqual,
+    ComparisonOperation::Equal,
+    ComparisonOperation::NotEqual,
+];
+
+impl ComparisonOperation {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ComparisonOperation::Less => "Less",
+            ComparisonOperation::LessEqual => "Less Equal",
+            ComparisonOperation::Greater => "Greater",
+            ComparisonOperation::GreaterEqual => "Greater Equal",
+            ComparisonOperation::Equal => "Equal",
+            ComparisonOperation::NotEqual => "Not Equal",
+        }
+    }
+
+    pub fn get_type_id(&self) -> &'static str {
+        match self {
+            ComparisonOperation::Less => "std.less",
+            ComparisonOperation::LessEqual => "std.less_equal",
+            ComparisonOperation::Greater => "std.greater",
+            ComparisonOperation::GreaterEqual => "std.greater_equal",
+            ComparisonOperation::Equal => "std.equal",
+            ComparisonOperation::NotEqual => "std.not_equal",
+        }
+    }
+
+    // only equality is defined for non-scalar values
+    fn is_ordering(&self) -> bool {
+        !matches!(self, ComparisonOperation::Equal | ComparisonOperation::NotEqual)
+    }
+
+    fn apply(&self, ordering: Option<std::cmp::Ordering>) -> bool {
+        use std::cmp::Ordering;
+        match self {
+            ComparisonOperation::Less => ordering == Some(Ordering::Less),
+            ComparisonOperation::LessEqual => ordering == Some(Ordering::Less) || ordering == Some(Ordering::Equal),
+            ComparisonOperation::Greater => ordering == Some(Ordering::Greater),
+            ComparisonOperation::GreaterEqual => ordering == Some(Ordering::Greater) || ordering == Some(Ordering::Equal),
+            ComparisonOperation::Equal => ordering == Some(Ordering::Equal),
+            ComparisonOperation::NotEqual => ordering != Some(Ordering::Equal),
+        }
+    }
+}
+
+pub struct Comparison {
+    operation: ComparisonOperation,
+}
+
+impl Comparison {
+    pub fn new(operation: ComparisonOperation) -> Box<dyn Node> {
+        Box::new(
+            Comparison {
+                operation
+            }
+        )
+    }
+}
+
+impl Node for Comparison {
+
+    fn get_name(&self) -> String {
+        String::from(self.operation.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from(self.operation.get_type_id())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let ordering = match (&input[0], &input[1]) {
+            (Variant::IntegerNumber(a), Variant::IntegerNumber(b)) => Some(a.cmp(b)),
+            (a, b) if a.get_type() == b.get_type() && !self.operation.is_ordering() => {
+                if a == b { Some(std::cmp::Ordering::Equal) } else { None }
+            },
+            (a, b) => {
+                let a = get_components(a).ok_or_else(|| NodeError::new("scalar expected"))?;
+                let b = get_components(b).ok_or_else(|| NodeError::new("scalar expected"))?;
+                a[0].partial_cmp(&b[0])
+            },
+        };
+        output[0] = Variant::Boolean(self.operation.apply(ordering));
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["a", "b"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 2)?;
+        let is_scalar = |t: &VariantType| get_components_count(t) == Some(1);
+        let mut error = NodeError::new("inputs can't be compared");
+        for (i, input_type) in input.iter().enumerate() {
+            if *input_type == VariantType::Null {
+                error.failed_inputs.push((i, describe_unsupported_input(input_type)));
+            } else if self.operation.is_ordering() && !is_scalar(input_type) {
+                error.failed_inputs.push((i, String::from("only scalars can be ordered")));
+            }
+        }
+        if error.failed_inputs.is_empty() && input[0] != input[1] && !(is_scalar(&input[0]) && is_scalar(&input[1])) {
+            error.failed_inputs.push((1, format!("expected {:?} but got {:?}", input[0], input[1])));
+        }
+
+        if error.failed_inputs.is_empty() {
+            Ok(output_slot("result", VariantType::Boolean))
+        } else {
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::std_nodes::comparison::*;
+    use nalgebra::Vector2;
+
+    fn compare(operation: ComparisonOperation, a: Variant, b: Variant) -> Result<bool, NodeError> {
+        let mut output = vec![Variant::Null];
+        Comparison::new(operation).invoke(&mut [a, b], &mut output)?;
+        match output[0] {
+            Variant::Boolean(result) => Ok(result),
+            _ => panic!("boolean expected"),
+        }
+    }
+
+    #[test]
+    fn comparison_test() {
+        assert!(compare(ComparisonOperation::Less, Variant::IntegerNumber(1), Variant::RealNumber(1.5)).unwrap());
+        assert!(compare(ComparisonOperation::GreaterEqual, Variant::RealNumber(2.), Variant::IntegerNumber(2)).unwrap());
+        assert!(!compare(ComparisonOperation::Greater, Variant::RealNumber(f64::NAN), Variant::RealNumber(0.)).unwrap());
+        assert!(compare(ComparisonOperation::Less, Variant::Boolean(false), Variant::Boolean(true)).is_err());
+
+        let a = Variant::Vector2(Vector2::new(1., 2.));
+        let b = Variant::Vector2(Vector2::new(1., 3.));
+        assert!(compare(ComparisonOperation::NotEqual, a.clone(), b).unwrap());
+        assert!(compare(ComparisonOperation::Equal, a.clone(), a.clone()).unwrap());
+        assert!(compare(ComparisonOperation::Equal, a, Variant::RealNumber(1.)).is_err());
+    }
+}
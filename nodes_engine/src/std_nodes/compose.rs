@@ -0,0 +1,197 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::std_nodes::utils::*;
+
+pub const COMPOSABLE_TYPES: [VariantType; 10] = [
+    VariantType::Vector2,
+    VariantType::Vector3,
+    VariantType::Vector4,
+    VariantType::RowVector2,
+    VariantType::RowVector3,
+    VariantType::RowVector4,
+    VariantType::Matrix2,
+    VariantType::Matrix3,
+    VariantType::Matrix4,
+    VariantType::Quaternion,
+];
+
+// names and types of the parts: scalar components for vectors and quaternions, columns for matrices
+fn get_parts(variant_type: &VariantType) -> Vec<(&'static str, VariantType)> {
+    let components = ["x", "y", "z", "w"];
+    let columns = ["column0", "column1", "column2", "column3"];
+    match variant_type {
+        VariantType::Matrix2 => columns[..2].iter().map(|name| (*name, VariantType::Vector2)).collect(),
+        VariantType::Matrix3 => columns[..3].iter().map(|name| (*name, VariantType::Vector3)).collect(),
+        VariantType::Matrix4 => columns.iter().map(|name| (*name, VariantType::Vector4)).collect(),
+        VariantType::Quaternion => components.iter().map(|name| (*name, VariantType::RealNumber)).collect(),
+        _ => {
+            let count = get_components_count(variant_type).unwrap_or(0);
+            components[..count].iter().map(|name| (*name, VariantType::RealNumber)).collect()
+        },
+    }
+}
+
+fn get_type_name(variant_type: &VariantType) -> String {
+    let name = format!("{:?}", variant_type);
+    let mut result = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            result.push('_');
+        }
+        result.push(c.to_ascii_lowercase());
+    }
+    result
+}
+
+// builds a vector, matrix or quaternion from its parts
+pub struct Compose {
+    variant_type: VariantType,
+}
+
+impl Compose {
+    pub fn new(variant_type: VariantType) -> Box<dyn Node> {
+        Box::new(
+            Compose {
+                variant_type
+            }
+        )
+    }
+}
+
+impl Node for Compose {
+
+    fn get_name(&self) -> String {
+        format!("Compose {:?}", self.variant_type)
+    }
+
+    fn get_type_id(&self) -> String {
+        format!("std.compose_{}", get_type_name(&self.variant_type))
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let mut components = Vec::new();
+        for value in input.iter() {
+            components.extend(get_components(value).unwrap_or_default());
+        }
+        output[0] = match &self.variant_type {
+            VariantType::Matrix2 => Variant::Matrix2(nalgebra::Matrix2::from_column_slice(&components)),
+            VariantType::Matrix3 => Variant::Matrix3(nalgebra::Matrix3::from_column_slice(&components)),
+            VariantType::Matrix4 => Variant::Matrix4(nalgebra::Matrix4::from_column_slice(&components)),
+            VariantType::Quaternion => Variant::Quaternion(
+                nalgebra::Quaternion::new(components[3], components[0], components[1], components[2])
+            ),
+            variant_type => from_components(variant_type, &components)
+                .ok_or_else(|| NodeError::new(&format!("{:?} can't be composed", variant_type)))?,
+        };
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        let names: Vec<&str> = get_parts(&self.variant_type).iter().map(|(name, _)| *name).collect();
+        Ok(input_slots(&names))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        let parts = get_parts(&self.variant_type);
+        check_inputs_count(input, parts.len())?;
+        let mut error = NodeError::new(&format!("unexpected parts of {:?}", self.variant_type));
+        for (i, (input_type, (_, part_type))) in input.iter().zip(parts.iter()).enumerate() {
+            let is_scalar_part = *part_type == VariantType::RealNumber && get_components_count(input_type) == Some(1);
+            if *input_type != *part_type && !is_scalar_part {
+                error.failed_inputs.push((i, format!("expected {:?} but got {:?}", part_type, input_type)));
+            }
+        }
+        if error.failed_inputs.is_empty() {
+            Ok(output_slot("result", self.variant_type.clone()))
+        } else {
+            Err(error)
+        }
+    }
+}
+
+// splits a vector, matrix or quaternion into its parts
+pub struct Decompose {
+    variant_type: VariantType,
+}
+
+impl Decompose {
+    pub fn new(variant_type: VariantType) -> Box<dyn Node> {
+        Box::new(
+            Decompose {
+                variant_type
+            }
+        )
+    }
+}
+
+impl Node for Decompose {
+
+    fn get_name(&self) -> String {
+        format!("Decompose {:?}", self.variant_type)
+    }
+
+    fn get_type_id(&self) -> String {
+        format!("std.decompose_{}", get_type_name(&self.variant_type))
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        self.determine(&get_input_types(input))?;
+        let parts: Vec<Variant> = match &input[0] {
+            Variant::Matrix2(m) => m.column_iter().map(|c| Variant::Vector2(c.into_owned())).collect(),
+            Variant::Matrix3(m) => m.column_iter().map(|c| Variant::Vector3(c.into_owned())).collect(),
+            Variant::Matrix4(m) => m.column_iter().map(|c| Variant::Vector4(c.into_owned())).collect(),
+            Variant::Quaternion(q) => q.coords.iter().map(|c| Variant::RealNumber(*c)).collect(),
+            value => get_components(value).unwrap_or_default().into_iter().map(Variant::RealNumber).collect(),
+        };
+        check_outputs_count(output, parts.len())?;
+        for (output, part) in output.iter_mut().zip(parts) {
+            *output = part;
+        }
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["value"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, 1)?;
+        if input[0] != self.variant_type {
+            let mut error = NodeError::new(&format!("{:?} expected", self.variant_type));
+            error.failed_inputs.push((0, format!("expected {:?} but got {:?}", self.variant_type, input[0])));
+            return Err(error);
+        }
+        Ok(get_parts(&self.variant_type)
+            .into_iter()
+            .map(|(name, variant_type)| NodeOutputSlotInfo { name: String::from(name), variant_type })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::std_nodes::compose::*;
+    use nalgebra::{ Matrix2, Quaternion, Vector2, Vector3 };
+
+    #[test]
+    fn compose_decompose_test() {
+        let compose = Compose::new(VariantType::Vector3);
+        assert_eq!(compose.get_type_id(), "std.compose_vector3");
+        let mut output = vec![Variant::Null];
+        compose.invoke(&mut [Variant::IntegerNumber(1), Variant::RealNumber(2.), Variant::RealNumber(3.)], &mut output).unwrap();
+        assert_eq!(output[0], Variant::Vector3(Vector3::new(1., 2., 3.)));
+        assert!(compose.determine(&[VariantType::RealNumber, VariantType::Boolean, VariantType::RealNumber]).is_err());
+
+        let compose = Compose::new(VariantType::Matrix2);
+        assert_eq!(Decompose::new(VariantType::RowVector2).get_type_id(), "std.decompose_row_vector2");
+        compose.invoke(&mut [Variant::Vector2(Vector2::new(1., 2.)), Variant::Vector2(Vector2::new(3., 4.))], &mut output).unwrap();
+        assert_eq!(output[0], Variant::Matrix2(Matrix2::new(1., 3., 2., 4.)));
+
+        let decompose = Decompose::new(VariantType::Quaternion);
+        let mut parts = vec![Variant::Null; 4];
+        decompose.invoke(&mut [Variant::Quaternion(Quaternion::new(4., 1., 2., 3.))], &mut parts).unwrap();
+        assert_eq!(parts, vec![Variant::RealNumber(1.), Variant::RealNumber(2.), Variant::RealNumber(3.), Variant::RealNumber(4.)]);
+    }
+}
@@ -1,14 +1,14 @@
 use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
 
 pub struct ConstantReal {
-    _value: f64,
+    value: f64,
 }
 
 impl ConstantReal {
     pub fn new() -> Box<dyn Node> {
         Box::new(
             ConstantReal { 
-                _value: 0.0
+                value: 0.0
             }
         )
     }
@@ -20,15 +20,41 @@ impl Node for ConstantReal {
         String::from("Constant Real")
     }
 
-    fn invoke(&self, _input: &mut[Variant], _output: &mut[Variant]) -> Result<(), NodeError> {
-        unimplemented!();
+    fn get_type_id(&self) -> String {
+        String::from("std.constant_real")
+    }
+
+    fn get_parameters(&self) -> Vec<(String, Variant)> {
+        vec![(String::from("value"), Variant::RealNumber(self.value))]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: Variant) -> Result<(), NodeError> {
+        match (name, value) {
+            ("value", Variant::RealNumber(value)) => {
+                self.value = value;
+                Ok(())
+            },
+            ("value", value) => Err(NodeError::new(&format!("parameter value can't be set to {:?}", value.get_type()))),
+            _ => Err(NodeError::new(&format!("unknown parameter {}", name))),
+        }
+    }
+
+    fn invoke(&self, _input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        if output.len() != 1 {
+            return Err(NodeError::new("constant has exactly one output"));
+        }
+        output[0] = Variant::RealNumber(self.value);
+        Ok(())
     }
 
     fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
-        unimplemented!();
+        Ok(Vec::new())
     }
 
     fn determine(&self, _input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
-        unimplemented!();
+        Ok(vec![NodeOutputSlotInfo {
+            name: String::from("value"),
+            variant_type: VariantType::RealNumber,
+        }])
     }
 }
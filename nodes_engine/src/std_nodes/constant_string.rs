@@ -0,0 +1,60 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+
+pub struct ConstantString {
+    value: String,
+}
+
+impl ConstantString {
+    pub fn new() -> Box<dyn Node> {
+        Box::new(
+            ConstantString {
+                value: String::new()
+            }
+        )
+    }
+}
+
+impl Node for ConstantString {
+
+    fn get_name(&self) -> String {
+        String::from("Constant String")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("std.constant_string")
+    }
+
+    fn get_parameters(&self) -> Vec<(String, Variant)> {
+        vec![(String::from("value"), Variant::String(self.value.clone()))]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: Variant) -> Result<(), NodeError> {
+        match (name, value) {
+            ("value", Variant::String(value)) => {
+                self.value = value;
+                Ok(())
+            },
+            ("value", value) => Err(NodeError::new(&format!("parameter value can't be set to {:?}", value.get_type()))),
+            _ => Err(NodeError::new(&format!("unknown parameter {}", name))),
+        }
+    }
+
+    fn invoke(&self, _input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        if output.len() != 1 {
+            return Err(NodeError::new("constant has exactly one output"));
+        }
+        output[0] = Variant::String(self.value.clone());
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(Vec::new())
+    }
+
+    fn determine(&self, _input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        Ok(vec![NodeOutputSlotInfo {
+            name: String::from("value"),
+            variant_type: VariantType::String,
+        }])
+    }
+}
@@ -0,0 +1,468 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::std_nodes::utils::*;
+use common::mesh::Mesh;
+use nalgebra::Vector3;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GeometryOperation {
+    Plane,
+    Box,
+    Sphere,
+    Cylinder,
+    Transform,
+    Merge,
+    ComputeNormals,
+    ComputeTangents,
+}
+
+pub const GEOMETRY_OPERATIONS: [GeometryOperation; 8] = [
+    GeometryOperation::Plane,
+    GeometryOperation::Box,
+    GeometryOperation::Sphere,
+    GeometryOperation::Cylinder,
+    GeometryOperation::Transform,
+    GeometryOperation::Merge,
+    GeometryOperation::ComputeNormals,
+    GeometryOperation::ComputeTangents,
+];
+
+impl GeometryOperation {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            GeometryOperation::Plane => "Plane",
+            GeometryOperation::Box => "Box",
+            GeometryOperation::Sphere => "Sphere",
+            GeometryOperation::Cylinder => "Cylinder",
+            GeometryOperation::Transform => "Transform Mesh",
+            GeometryOperation::Merge => "Merge Meshes",
+            GeometryOperation::ComputeNormals => "Compute Normals",
+            GeometryOperation::ComputeTangents => "Compute Tangents",
+        }
+    }
+
+    pub fn get_type_id(&self) -> &'static str {
+        match self {
+            GeometryOperation::Plane => "std.mesh_plane",
+            GeometryOperation::Box => "std.mesh_box",
+            GeometryOperation::Sphere => "std.mesh_sphere",
+            GeometryOperation::Cylinder => "std.mesh_cylinder",
+            GeometryOperation::Transform => "std.mesh_transform",
+            GeometryOperation::Merge => "std.mesh_merge",
+            GeometryOperation::ComputeNormals => "std.mesh_compute_normals",
+            GeometryOperation::ComputeTangents => "std.mesh_compute_tangents",
+        }
+    }
+
+    fn get_inputs(&self) -> &'static [(&'static str, Option<VariantType>)] {
+        // `None` stands for any scalar
+        match self {
+            GeometryOperation::Plane => &[("width", None), ("depth", None)],
+            GeometryOperation::Box => &[("size", Some(VariantType::Vector3))],
+            GeometryOperation::Sphere => &[("radius", None), ("segments", Some(VariantType::IntegerNumber)), ("rings", Some(VariantType::IntegerNumber))],
+            GeometryOperation::Cylinder => &[("radius", None), ("height", None), ("segments", Some(VariantType::IntegerNumber))],
+            GeometryOperation::Transform => &[("mesh", Some(VariantType::Mesh)), ("matrix", Some(VariantType::Matrix4))],
+            GeometryOperation::Merge => &[("a", Some(VariantType::Mesh)), ("b", Some(VariantType::Mesh))],
+            GeometryOperation::ComputeNormals | GeometryOperation::ComputeTangents => &[("mesh", Some(VariantType::Mesh))],
+        }
+    }
+}
+
+fn check_input_types(name: &str, input: &[VariantType], expected: &[(&str, Option<VariantType>)]) -> Result<(), NodeError> {
+    check_inputs_count(input, expected.len())?;
+    let mut error = NodeError::new(&format!("unexpected inputs of {}", name));
+    for (i, (input_type, (_, expected_type))) in input.iter().zip(expected.iter()).enumerate() {
+        match expected_type {
+            Some(expected_type) if input_type != expected_type => {
+                error.failed_inputs.push((i, format!("expected {:?} but got {:?}", expected_type, input_type)));
+            },
+            None if get_components_count(input_type) != Some(1) => {
+                error.failed_inputs.push((i, describe_unsupported_input(input_type)));
+            },
+            _ => {},
+        }
+    }
+    if error.failed_inputs.is_empty() {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+fn get_mesh(value: &Variant) -> Result<&Mesh, NodeError> {
+    match value {
+        Variant::Mesh(mesh) => Ok(mesh),
+        value => Err(NodeError::new(&format!("mesh expected but got {:?}", value.get_type()))),
+    }
+}
+
+fn get_vectors3(values: &[Variant]) -> Vec<Vector3<f32>> {
+    values.iter()
+        .filter_map(|value| match value { Variant::Vector3(v) => Some(v.map(|x| x as f32)), _ => None })
+        .collect()
+}
+
+fn get_count(value: &Variant, name: &str, min: i64) -> Result<u32, NodeError> {
+    match value {
+        Variant::IntegerNumber(v) if *v >= min && *v <= i64::from(u16::MAX) => Ok(*v as u32),
+        _ => Err(NodeError::new(&format!("{} has to be an integer from {} to {}", name, min, u16::MAX))),
+    }
+}
+
+// keeps sphere vertex indices far from the u32 limit
+pub const MAX_SPHERE_VERTICES: u64 = 1 << 24;
+
+// mesh primitives and whole-mesh processing, meshes are in f32 precision
+pub struct Geometry {
+    operation: GeometryOperation,
+}
+
+impl Geometry {
+    pub fn new(operation: GeometryOperation) -> Box<dyn Node> {
+        Box::new(
+            Geometry {
+                operation
+            }
+        )
+    }
+}
+
+impl Node for Geometry {
+
+    fn get_name(&self) -> String {
+        String::from(self.operation.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from(self.operation.get_type_id())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let real = |i: usize| get_real(&input[i]).unwrap_or_default() as f32;
+        let mesh = match self.operation {
+            GeometryOperation::Plane => Mesh::plane(real(0), real(1)),
+            GeometryOperation::Box => match &input[0] {
+                Variant::Vector3(size) => Mesh::cuboid(size.map(|x| x as f32)),
+                _ => return Err(NodeError::new("size has to be a vector")),
+            },
+            GeometryOperation::Sphere => {
+                let segments = get_count(&input[1], "segments", 3)?;
+                let rings = get_count(&input[2], "rings", 2)?;
+                if u64::from(segments + 1) * u64::from(rings + 1) > MAX_SPHERE_VERTICES {
+                    let mut error = NodeError::new("sphere has too many vertices");
+                    error.failed_inputs.push((2, format!("{} segments and {} rings give more than {} vertices", segments, rings, MAX_SPHERE_VERTICES)));
+                    return Err(error);
+                }
+                Mesh::sphere(real(0), segments, rings)
+            },
+            GeometryOperation::Cylinder => Mesh::cylinder(real(0), real(1), get_count(&input[2], "segments", 3)?),
+            GeometryOperation::Transform => {
+                let mut mesh = get_mesh(&input[0])?.clone();
+                match &input[1] {
+                    Variant::Matrix4(matrix) => mesh.transform(&matrix.map(|x| x as f32)),
+                    _ => return Err(NodeError::new("matrix has to be a 4x4 matrix")),
+                }
+                mesh
+            },
+            GeometryOperation::Merge => {
+                let mut mesh = get_mesh(&input[0])?.clone();
+                mesh.merge(get_mesh(&input[1])?);
+                mesh
+            },
+            GeometryOperation::ComputeNormals => {
+                let mut mesh = get_mesh(&input[0])?.clone();
+                mesh.compute_normals();
+                mesh
+            },
+            GeometryOperation::ComputeTangents => {
+                let mut mesh = get_mesh(&input[0])?.clone();
+                // MikkTSpace, so normal maps baked by the usual tools look right
+                if let Err(e) = mesh.generate_tangents() {
+                    let mut error = NodeError::new("tangents can't be generated");
+                    error.failed_inputs.push((0, format!("{:?}", e)));
+                    return Err(error);
+                }
+                mesh
+            },
+        };
+        output[0] = Variant::Mesh(Box::new(mesh));
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        let names: Vec<&str> = self.operation.get_inputs().iter().map(|(name, _)| *name).collect();
+        Ok(input_slots(&names))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_input_types(self.operation.get_name(), input, self.operation.get_inputs())?;
+        Ok(output_slot("mesh", VariantType::Mesh))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MeshAttribute {
+    Indices,
+    Positions,
+    Normals,
+    Tangents,
+    Bitangents,
+    Colors(usize),
+    Uvs(usize),
+}
+
+impl MeshAttribute {
+    pub fn get_all() -> Vec<MeshAttribute> {
+        let mut attributes = vec![
+            MeshAttribute::Indices,
+            MeshAttribute::Positions,
+            MeshAttribute::Normals,
+            MeshAttribute::Tangents,
+            MeshAttribute::Bitangents,
+        ];
+        attributes.extend((0..4).map(MeshAttribute::Colors));
+        attributes.extend((0..8).map(MeshAttribute::Uvs));
+        attributes
+    }
+
+    pub fn get_name(&self) -> String {
+        match self {
+            MeshAttribute::Indices => String::from("indices"),
+            MeshAttribute::Positions => String::from("positions"),
+            MeshAttribute::Normals => String::from("normals"),
+            MeshAttribute::Tangents => String::from("tangents"),
+            MeshAttribute::Bitangents => String::from("bitangents"),
+            MeshAttribute::Colors(set) => format!("colors{}", set),
+            MeshAttribute::Uvs(set) => format!("uvs{}", set),
+        }
+    }
+
+    pub fn get_element_type(&self) -> VariantType {
+        match self {
+            MeshAttribute::Indices => VariantType::IntegerNumber,
+            MeshAttribute::Colors(_) => VariantType::Vector4,
+            MeshAttribute::Uvs(_) => VariantType::Vector2,
+            _ => VariantType::Vector3,
+        }
+    }
+
+    fn get_array_type(&self) -> VariantType {
+        VariantType::Array(Box::new(self.get_element_type()))
+    }
+
+    fn read(&self, mesh: &Mesh) -> Vec<Variant> {
+        let vectors3 = |vectors: &[Vector3<f32>]| vectors.iter().map(|v| Variant::Vector3(v.map(f64::from))).collect();
+        match self {
+            MeshAttribute::Indices => mesh.indices.iter().map(|i| Variant::IntegerNumber(i64::from(*i))).collect(),
+            MeshAttribute::Positions => vectors3(&mesh.positions),
+            MeshAttribute::Normals => vectors3(&mesh.normals),
+            MeshAttribute::Tangents => vectors3(&mesh.tangents),
+            MeshAttribute::Bitangents => vectors3(&mesh.bitangents),
+            MeshAttribute::Colors(set) => mesh.colors[*set].iter().map(|v| Variant::Vector4(v.map(f64::from))).collect(),
+            MeshAttribute::Uvs(set) => mesh.uvs[*set].iter().map(|v| Variant::Vector2(v.map(f64::from))).collect(),
+        }
+    }
+
+    // vertex attributes have to be empty or match the vertices count, indices have to be in range
+    fn write(&self, mesh: &mut Mesh, values: &[Variant]) -> Result<(), NodeError> {
+        let count = mesh.get_vertices_count();
+        let expected_count = match self {
+            MeshAttribute::Positions if count == 0 => values.len(),
+            MeshAttribute::Positions => count,
+            _ => if values.is_empty() { 0 } else { count },
+        };
+        let mut error = NodeError::new(&format!("{} can't be set", self.get_name()));
+        if *self == MeshAttribute::Indices {
+            if values.len() % 3 != 0 {
+                error.failed_inputs.push((1, format!("{} indices don't form triangles", values.len())));
+            }
+        } else if values.len() != expected_count {
+            error.failed_inputs.push((1, format!("expected {} values but got {}", expected_count, values.len())));
+        }
+        if !error.failed_inputs.is_empty() {
+            return Err(error);
+        }
+
+        match self {
+            MeshAttribute::Indices => {
+                let mut indices = Vec::with_capacity(values.len());
+                for value in values {
+                    match value {
+                        Variant::IntegerNumber(i) if *i >= 0 && (*i as usize) < count => indices.push(*i as u32),
+                        value => return Err(NodeError::new(&format!("index {:?} is out of {} vertices", value, count))),
+                    }
+                }
+                mesh.indices = indices;
+            },
+            MeshAttribute::Positions => mesh.positions = get_vectors3(values),
+            MeshAttribute::Normals => mesh.normals = get_vectors3(values),
+            MeshAttribute::Tangents => mesh.tangents = get_vectors3(values),
+            MeshAttribute::Bitangents => mesh.bitangents = get_vectors3(values),
+            MeshAttribute::Colors(set) => {
+                mesh.colors[*set] = values.iter()
+                    .filter_map(|value| match value { Variant::Vector4(v) => Some(v.map(|x| x as f32)), _ => None })
+                    .collect();
+            },
+            MeshAttribute::Uvs(set) => {
+                mesh.uvs[*set] = values.iter()
+                    .filter_map(|value| match value { Variant::Vector2(v) => Some(v.map(|x| x as f32)), _ => None })
+                    .collect();
+            },
+        }
+        Ok(())
+    }
+}
+
+// reads one attribute of a mesh as an array
+pub struct GetMeshAttribute {
+    attribute: MeshAttribute,
+}
+
+impl GetMeshAttribute {
+    pub fn new(attribute: MeshAttribute) -> Box<dyn Node> {
+        Box::new(
+            GetMeshAttribute {
+                attribute
+            }
+        )
+    }
+}
+
+impl Node for GetMeshAttribute {
+
+    fn get_name(&self) -> String {
+        format!("Get Mesh {}", self.attribute.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        format!("std.mesh_get_{}", self.attribute.get_name())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let values = self.attribute.read(get_mesh(&input[0])?);
+        output[0] = Variant::Array(self.attribute.get_element_type(), values);
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["mesh"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_input_types(&self.get_name(), input, &[("mesh", Some(VariantType::Mesh))])?;
+        Ok(output_slot(&self.attribute.get_name(), self.attribute.get_array_type()))
+    }
+}
+
+// replaces one attribute of a mesh, an empty array removes the attribute
+pub struct SetMeshAttribute {
+    attribute: MeshAttribute,
+}
+
+impl SetMeshAttribute {
+    pub fn new(attribute: MeshAttribute) -> Box<dyn Node> {
+        Box::new(
+            SetMeshAttribute {
+                attribute
+            }
+        )
+    }
+}
+
+impl Node for SetMeshAttribute {
+
+    fn get_name(&self) -> String {
+        format!("Set Mesh {}", self.attribute.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        format!("std.mesh_set_{}", self.attribute.get_name())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let mut mesh = get_mesh(&input[0])?.clone();
+        if let Variant::Array(_, values) = &input[1] {
+            self.attribute.write(&mut mesh, values)?;
+        }
+        output[0] = Variant::Mesh(Box::new(mesh));
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(&["mesh", "values"]))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        let expected = [("mesh", Some(VariantType::Mesh)), ("values", Some(self.attribute.get_array_type()))];
+        check_input_types(&self.get_name(), input, &expected)?;
+        Ok(output_slot("mesh", VariantType::Mesh))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::std_nodes::geometry::*;
+    use crate::std_nodes::utils::invoke;
+    use nalgebra::{ Matrix4, Vector4 };
+
+    fn unwrap_mesh(value: Variant) -> Mesh {
+        match value {
+            Variant::Mesh(mesh) => *mesh,
+            _ => panic!("mesh expected"),
+        }
+    }
+
+    #[test]
+    fn primitives_test() {
+        let sphere = Geometry::new(GeometryOperation::Sphere);
+        let arguments = vec![Variant::RealNumber(2.), Variant::IntegerNumber(8), Variant::IntegerNumber(4)];
+        assert_eq!(unwrap_mesh(invoke(sphere.as_ref(), arguments).unwrap()), Mesh::sphere(2., 8, 4));
+        let arguments = vec![Variant::RealNumber(2.), Variant::IntegerNumber(8192), Variant::IntegerNumber(4096)];
+        assert_eq!(invoke(sphere.as_ref(), arguments).unwrap_err().failed_inputs[0].0, 2);
+
+        let cylinder = Geometry::new(GeometryOperation::Cylinder);
+        assert!(invoke(cylinder.as_ref(), vec![Variant::IntegerNumber(1), Variant::IntegerNumber(1), Variant::IntegerNumber(2)]).is_err());
+        let cylinder = Geometry::new(GeometryOperation::Cylinder);
+        assert!(cylinder.determine(&[VariantType::RealNumber, VariantType::RealNumber, VariantType::RealNumber]).is_err());
+
+        let plane = invoke(Geometry::new(GeometryOperation::Plane).as_ref(), vec![Variant::RealNumber(2.), Variant::RealNumber(2.)]).unwrap();
+        let translation = Variant::Matrix4(Matrix4::new_translation(&Vector3::new(0., 1., 0.)));
+        let moved = invoke(Geometry::new(GeometryOperation::Transform).as_ref(), vec![plane.clone(), translation]).unwrap();
+        let merged = unwrap_mesh(invoke(Geometry::new(GeometryOperation::Merge).as_ref(), vec![plane, moved]).unwrap());
+        assert_eq!(merged.get_vertices_count(), 8);
+        assert_eq!(merged.positions[4], Vector3::new(-1., 1., 1.));
+
+        let tangents = invoke(Geometry::new(GeometryOperation::ComputeTangents).as_ref(), vec![Variant::Mesh(Box::new(merged))]).unwrap();
+        assert_eq!(unwrap_mesh(tangents).tangents.len(), 8);
+        let mut no_uvs = Mesh::plane(1., 1.);
+        no_uvs.uvs[0].clear();
+        assert!(invoke(Geometry::new(GeometryOperation::ComputeTangents).as_ref(), vec![Variant::Mesh(Box::new(no_uvs))]).is_err());
+    }
+
+    #[test]
+    fn attributes_test() {
+        let mesh = Variant::Mesh(Box::new(Mesh::plane(2., 2.)));
+        let positions = invoke(GetMeshAttribute::new(MeshAttribute::Positions).as_ref(), vec![mesh.clone()]).unwrap();
+        match &positions {
+            Variant::Array(VariantType::Vector3, values) => assert_eq!(values[0], Variant::Vector3(nalgebra::Vector3::new(-1., 0., 1.))),
+            _ => panic!("array of vectors expected"),
+        }
+
+        let colors = Variant::Array(VariantType::Vector4, vec![Variant::Vector4(Vector4::new(1., 0., 0., 1.)); 4]);
+        let set_colors = SetMeshAttribute::new(MeshAttribute::Colors(1));
+        assert_eq!(set_colors.get_type_id(), "std.mesh_set_colors1");
+        let colored = unwrap_mesh(invoke(set_colors.as_ref(), vec![mesh.clone(), colors]).unwrap());
+        assert_eq!(colored.colors[1], vec![Vector4::new(1., 0., 0., 1.); 4]);
+
+        let too_short = Variant::Array(VariantType::Vector2, vec![Variant::Vector2(nalgebra::Vector2::zeros())]);
+        assert!(invoke(SetMeshAttribute::new(MeshAttribute::Uvs(0)).as_ref(), vec![mesh.clone(), too_short]).is_err());
+        let out_of_range = Variant::Array(VariantType::IntegerNumber, vec![Variant::IntegerNumber(0), Variant::IntegerNumber(1), Variant::IntegerNumber(4)]);
+        assert!(invoke(SetMeshAttribute::new(MeshAttribute::Indices).as_ref(), vec![mesh, out_of_range]).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::std_nodes::utils::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LinearAlgebraOperation {
+    Dot,
+    Cross,
+    Normalize,
+    Length,
+    Multiply,
+    Transpose,
+    Inverse,
+    Determinant,
+}
+
+pub const LINEAR_ALGEBRA_OPERATIONS: [LinearAlgebraOperation; 8] = [
+    LinearAlgebraOperation::Dot,
+    LinearAlgebraOperation::Cross,
+    LinearAlgebraOperation::Normalize,
+    LinearAlgebraOperation::Length,
+    LinearAlgebraOperation::Multiply,
+    LinearAlgebraOperation::Transpose,
+    LinearAlgebraOperation::Inverse,
+    LinearAlgebraOperation::Determinant,
+];
+
+impl LinearAlgebraOperation {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            LinearAlgebraOperation::Dot => "Dot",
+            LinearAlgebraOperation::Cross => "Cross",
+            LinearAlgebraOperation::Normalize => "Normalize",
+            LinearAlgebraOperation::Length => "Length",
+            LinearAlgebraOperation::Multiply => "Matrix Multiply",
+            LinearAlgebraOperation::Transpose => "Transpose",
+            LinearAlgebraOperation::Inverse => "Inverse",
+            LinearAlgebraOperation::Determinant => "Determinant",
+        }
+    }
+
+    pub fn get_type_id(&self) -> &'static str {
+        match self {
+            LinearAlgebraOperation::Dot => "std.dot",
+            LinearAlgebraOperation::Cross => "std.cross",
+            LinearAlgebraOperation::Normalize => "std.normalize",
+            LinearAlgebraOperation::Length => "std.length",
+            LinearAlgebraOperation::Multiply => "std.matrix_multiply",
+            LinearAlgebraOperation::Transpose => "std.transpose",
+            LinearAlgebraOperation::Inverse => "std.inverse",
+            LinearAlgebraOperation::Determinant => "std.determinant",
+        }
+    }
+
+    fn get_input_names(&self) -> &'static [&'static str] {
+        match self {
+            LinearAlgebraOperation::Dot | LinearAlgebraOperation::Cross | LinearAlgebraOperation::Multiply => &["a", "b"],
+            _ => &["value"],
+        }
+    }
+
+    fn get_output_type(&self, input: &[VariantType]) -> Option<VariantType> {
+        match (self, input) {
+            (LinearAlgebraOperation::Dot, [a, b]) if is_vector(a) && get_components_count(a) == get_components_count(b) && is_vector(b) => {
+                Some(VariantType::RealNumber)
+            },
+            (LinearAlgebraOperation::Cross, [a, b]) if a == b && (*a == VariantType::Vector3 || *a == VariantType::RowVector3) => Some(a.clone()),
+            (LinearAlgebraOperation::Normalize, [a]) if is_vector(a) || *a == VariantType::Quaternion => Some(a.clone()),
+            (LinearAlgebraOperation::Length, [a]) if is_vector(a) || *a == VariantType::Quaternion => Some(VariantType::RealNumber),
+            (LinearAlgebraOperation::Multiply, [a, b]) => get_multiply_type(a, b),
+            (LinearAlgebraOperation::Transpose, [a]) => get_transposed_type(a),
+            (LinearAlgebraOperation::Inverse, [a]) if is_matrix(a) || *a == VariantType::Quaternion => Some(a.clone()),
+            (LinearAlgebraOperation::Determinant, [a]) if is_matrix(a) => Some(VariantType::RealNumber),
+            _ => None,
+        }
+    }
+}
+
+fn is_vector(variant_type: &VariantType) -> bool {
+    matches!(get_components_count(variant_type), Some(count) if count > 1)
+}
+
+fn is_matrix(variant_type: &VariantType) -> bool {
+    *variant_type == VariantType::Matrix2 || *variant_type == VariantType::Matrix3 || *variant_type == VariantType::Matrix4
+}
+
+fn get_transposed_type(variant_type: &VariantType) -> Option<VariantType> {
+    match variant_type {
+        VariantType::Vector2 => Some(VariantType::RowVector2),
+        VariantType::Vector3 => Some(VariantType::RowVector3),
+        VariantType::Vector4 => Some(VariantType::RowVector4),
+        VariantType::RowVector2 => Some(VariantType::Vector2),
+        VariantType::RowVector3 => Some(VariantType::Vector3),
+        VariantType::RowVector4 => Some(VariantType::Vector4),
+        variant_type if is_matrix(variant_type) => Some(variant_type.clone()),
+        _ => None,
+    }
+}
+
+// matrix product rules, vectors are columns and row vectors are rows
+fn get_multiply_type(a: &VariantType, b: &VariantType) -> Option<VariantType> {
+    use VariantType::*;
+    let is_scalar = |t: &VariantType| get_components_count(t) == Some(1);
+    match (a, b) {
+        (a, b) if is_scalar(a) && (is_matrix(b) || *b == Quaternion) => Some(b.clone()),
+        (a, b) if is_scalar(b) && (is_matrix(a) || *a == Quaternion) => Some(a.clone()),
+        (Matrix2, Matrix2) | (Matrix3, Matrix3) | (Matrix4, Matrix4) => Some(a.clone()),
+        (Matrix2, Vector2) | (Matrix3, Vector3) | (Matrix4, Vector4) => Some(b.clone()),
+        (RowVector2, Matrix2) | (RowVector3, Matrix3) | (RowVector4, Matrix4) => Some(a.clone()),
+        (RowVector2, Vector2) | (RowVector3, Vector3) | (RowVector4, Vector4) => Some(RealNumber),
+        (Vector2, RowVector2) => Some(Matrix2),
+        (Vector3, RowVector3) => Some(Matrix3),
+        (Vector4, RowVector4) => Some(Matrix4),
+        (Quaternion, Quaternion) => Some(Quaternion),
+        (Quaternion, Vector3) => Some(Vector3),
+        _ => None,
+    }
+}
+
+fn multiply(a: &Variant, b: &Variant) -> Option<Variant> {
+    use Variant::*;
+    match (a, b) {
+        (Matrix2(a), b) | (b, Matrix2(a)) if get_real(b).is_some() => Some(Matrix2(a * get_real(b)?)),
+        (Matrix3(a), b) | (b, Matrix3(a)) if get_real(b).is_some() => Some(Matrix3(a * get_real(b)?)),
+        (Matrix4(a), b) | (b, Matrix4(a)) if get_real(b).is_some() => Some(Matrix4(a * get_real(b)?)),
+        (Quaternion(a), b) | (b, Quaternion(a)) if get_real(b).is_some() => Some(Quaternion(a * get_real(b)?)),
+        (Matrix2(a), Matrix2(b)) => Some(Matrix2(a * b)),
+        (Matrix3(a), Matrix3(b)) => Some(Matrix3(a * b)),
+        (Matrix4(a), Matrix4(b)) => Some(Matrix4(a * b)),
+        (Matrix2(a), Vector2(b)) => Some(Vector2(a * b)),
+        (Matrix3(a), Vector3(b)) => Some(Vector3(a * b)),
+        (Matrix4(a), Vector4(b)) => Some(Vector4(a * b)),
+        (RowVector2(a), Matrix2(b)) => Some(RowVector2((a.transpose() * b).transpose())),
+        (RowVector3(a), Matrix3(b)) => Some(RowVector3((a.transpose() * b).transpose())),
+        (RowVector4(a), Matrix4(b)) => Some(RowVector4((a.transpose() * b).transpose())),
+        (RowVector2(a), Vector2(b)) => Some(RealNumber(a.dot(b))),
+        (RowVector3(a), Vector3(b)) => Some(RealNumber(a.dot(b))),
+        (RowVector4(a), Vector4(b)) => Some(RealNumber(a.dot(b))),
+        (Vector2(a), RowVector2(b)) => Some(Matrix2(a * b.transpose())),
+        (Vector3(a), RowVector3(b)) => Some(Matrix3(a * b.transpose())),
+        (Vector4(a), RowVector4(b)) => Some(Matrix4(a * b.transpose())),
+        (Quaternion(a), Quaternion(b)) => Some(Quaternion(a * b)),
+        (Quaternion(a), Vector3(b)) => Some(Vector3(nalgebra::UnitQuaternion::from_quaternion(*a) * b)),
+        _ => None,
+    }
+}
+
+fn transpose(value: &Variant) -> Option<Variant> {
+    match value {
+        Variant::Vector2(v) => Some(Variant::RowVector2(*v)),
+        Variant::Vector3(v) => Some(Variant::RowVector3(*v)),
+        Variant::Vector4(v) => Some(Variant::RowVector4(*v)),
+        Variant::RowVector2(v) => Some(Variant::Vector2(*v)),
+        Variant::RowVector3(v) => Some(Variant::Vector3(*v)),
+        Variant::RowVector4(v) => Some(Variant::Vector4(*v)),
+        Variant::Matrix2(m) => Some(Variant::Matrix2(m.transpose())),
+        Variant::Matrix3(m) => Some(Variant::Matrix3(m.transpose())),
+        Variant::Matrix4(m) => Some(Variant::Matrix4(m.transpose())),
+        _ => None,
+    }
+}
+
+fn inverse(value: &Variant) -> Option<Variant> {
+    match value {
+        Variant::Matrix2(m) => m.try_inverse().map(Variant::Matrix2),
+        Variant::Matrix3(m) => m.try_inverse().map(Variant::Matrix3),
+        Variant::Matrix4(m) => m.try_inverse().map(Variant::Matrix4),
+        Variant::Quaternion(q) => q.try_inverse().map(Variant::Quaternion),
+        _ => None,
+    }
+}
+
+fn determinant(value: &Variant) -> Option<Variant> {
+    match value {
+        Variant::Matrix2(m) => Some(Variant::RealNumber(m.determinant())),
+        Variant::Matrix3(m) => Some(Variant::RealNumber(m.determinant())),
+        Variant::Matrix4(m) => Some(Variant::RealNumber(m.determinant())),
+        _ => None,
+    }
+}
+
+fn normalize(value: &Variant) -> Option<Variant> {
+    if let Variant::Quaternion(q) = value {
+        return q.coords.try_normalize(f64::EPSILON).map(|coords| Variant::Quaternion(nalgebra::Quaternion::from(coords)));
+    }
+    let components = get_components(value)?;
+    let length = components.iter().map(|c| c * c).sum::<f64>().sqrt();
+    if length <= f64::EPSILON {
+        return None;
+    }
+    let normalized: Vec<f64> = components.iter().map(|c| c / length).collect();
+    from_components(&value.get_type(), &normalized)
+}
+
+fn length(value: &Variant) -> Option<Variant> {
+    if let Variant::Quaternion(q) = value {
+        return Some(Variant::RealNumber(q.norm()));
+    }
+    let components = get_components(value)?;
+    Some(Variant::RealNumber(components.iter().map(|c| c * c).sum::<f64>().sqrt()))
+}
+
+fn dot(a: &Variant, b: &Variant) -> Option<Variant> {
+    let a = get_components(a)?;
+    let b = get_components(b)?;
+    Some(Variant::RealNumber(a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()))
+}
+
+fn cross(a: &Variant, b: &Variant) -> Option<Variant> {
+    match (a, b) {
+        (Variant::Vector3(a), Variant::Vector3(b)) => Some(Variant::Vector3(a.cross(b))),
+        (Variant::RowVector3(a), Variant::RowVector3(b)) => Some(Variant::RowVector3(a.cross(b))),
+        _ => None,
+    }
+}
+
+// vector and matrix operations over the nalgebra types stored in `Variant`
+pub struct LinearAlgebra {
+    operation: LinearAlgebraOperation,
+}
+
+impl LinearAlgebra {
+    pub fn new(operation: LinearAlgebraOperation) -> Box<dyn Node> {
+        Box::new(
+            LinearAlgebra {
+                operation
+            }
+        )
+    }
+}
+
+impl Node for LinearAlgebra {
+
+    fn get_name(&self) -> String {
+        String::from(self.operation.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from(self.operation.get_type_id())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let result = match self.operation {
+            LinearAlgebraOperation::Dot => dot(&input[0], &input[1]),
+            LinearAlgebraOperation::Cross => cross(&input[0], &input[1]),
+            LinearAlgebraOperation::Normalize => normalize(&input[0]),
+            LinearAlgebraOperation::Length => length(&input[0]),
+            LinearAlgebraOperation::Multiply => multiply(&input[0], &input[1]),
+            LinearAlgebraOperation::Transpose => transpose(&input[0]),
+            LinearAlgebraOperation::Inverse => inverse(&input[0]),
+            LinearAlgebraOperation::Determinant => determinant(&input[0]),
+        };
+        output[0] = result.ok_or_else(|| NodeError::new(&format!("{} is not defined for the input", self.operation.get_name())))?;
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(self.operation.get_input_names()))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, self.operation.get_input_names().len())?;
+        match self.operation.get_output_type(input) {
+            Some(output_type) => Ok(output_slot("result", output_type)),
+            None => {
+                let mut error = NodeError::new(&format!("{} is not defined for {:?}", self.operation.get_name(), input));
+                for (i, input_type) in input.iter().enumerate() {
+                    error.failed_inputs.push((i, describe_unsupported_input(input_type)));
+                }
+                Err(error)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::std_nodes::linear_algebra::*;
+    use crate::std_nodes::utils::invoke;
+    use nalgebra::{ Matrix2, Vector2, Vector3 };
+
+    #[test]
+    fn vector_operations_test() {
+        let x = Variant::Vector3(Vector3::new(1., 0., 0.));
+        let y = Variant::Vector3(Vector3::new(0., 2., 0.));
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Dot).as_ref(), vec![x.clone(), y.clone()]).unwrap(), Variant::RealNumber(0.));
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Cross).as_ref(), vec![x.clone(), y.clone()]).unwrap(), Variant::Vector3(Vector3::new(0., 0., 2.)));
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Length).as_ref(), vec![y.clone()]).unwrap(), Variant::RealNumber(2.));
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Normalize).as_ref(), vec![y]).unwrap(), Variant::Vector3(Vector3::new(0., 1., 0.)));
+        assert!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Normalize).as_ref(), vec![Variant::Vector3(Vector3::zeros())]).is_err());
+        assert!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Cross).as_ref(), vec![x, Variant::Vector2(Vector2::zeros())]).is_err());
+    }
+
+    #[test]
+    fn matrix_operations_test() {
+        let m = Matrix2::new(1., 2., 3., 4.);
+        let v = Vector2::new(1., 1.);
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Multiply).as_ref(), vec![Variant::Matrix2(m), Variant::Vector2(v)]).unwrap(), Variant::Vector2(m * v));
+        assert_eq!(
+            invoke(LinearAlgebra::new(LinearAlgebraOperation::Multiply).as_ref(), vec![Variant::RowVector2(v), Variant::Matrix2(m)]).unwrap(),
+            Variant::RowVector2(Vector2::new(4., 6.))
+        );
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Multiply).as_ref(), vec![Variant::RowVector2(v), Variant::Vector2(v)]).unwrap(), Variant::RealNumber(2.));
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Multiply).as_ref(), vec![Variant::IntegerNumber(2), Variant::Matrix2(m)]).unwrap(), Variant::Matrix2(m * 2.));
+        assert!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Multiply).as_ref(), vec![Variant::Vector2(v), Variant::Vector2(v)]).is_err());
+
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Transpose).as_ref(), vec![Variant::Vector2(v)]).unwrap(), Variant::RowVector2(v));
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Determinant).as_ref(), vec![Variant::Matrix2(m)]).unwrap(), Variant::RealNumber(-2.));
+        assert_eq!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Inverse).as_ref(), vec![Variant::Matrix2(m)]).unwrap(), Variant::Matrix2(m.try_inverse().unwrap()));
+        assert!(invoke(LinearAlgebra::new(LinearAlgebraOperation::Inverse).as_ref(), vec![Variant::Matrix2(Matrix2::zeros())]).is_err());
+    }
+}
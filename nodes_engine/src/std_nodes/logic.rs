@@ -0,0 +1,120 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::std_nodes::utils::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LogicOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+pub const LOGIC_OPERATIONS: [LogicOperation; 4] = [
+    LogicOperation::And,
+    LogicOperation::Or,
+    LogicOperation::Xor,
+    LogicOperation::Not,
+];
+
+impl LogicOperation {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            LogicOperation::And => "And",
+            LogicOperation::Or => "Or",
+            LogicOperation::Xor => "Xor",
+            LogicOperation::Not => "Not",
+        }
+    }
+
+    fn get_input_names(&self) -> &'static [&'static str] {
+        match self {
+            LogicOperation::Not => &["a"],
+            _ => &["a", "b"],
+        }
+    }
+
+    fn apply(&self, v: &[bool]) -> bool {
+        match self {
+            LogicOperation::And => v[0] && v[1],
+            LogicOperation::Or => v[0] || v[1],
+            LogicOperation::Xor => v[0] != v[1],
+            LogicOperation::Not => !v[0],
+        }
+    }
+}
+
+pub struct Logic {
+    operation: LogicOperation,
+}
+
+impl Logic {
+    pub fn new(operation: LogicOperation) -> Box<dyn Node> {
+        Box::new(
+            Logic {
+                operation
+            }
+        )
+    }
+}
+
+impl Node for Logic {
+
+    fn get_name(&self) -> String {
+        String::from(self.operation.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        format!("std.{}", self.operation.get_name().to_lowercase())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        self.determine(&get_input_types(input))?;
+        let arguments: Vec<bool> = input.iter()
+            .map(|value| match value {
+                Variant::Boolean(value) => *value,
+                _ => false,
+            })
+            .collect();
+        output[0] = Variant::Boolean(self.operation.apply(&arguments));
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(self.operation.get_input_names()))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, self.operation.get_input_names().len())?;
+        let mut error = NodeError::new("boolean inputs expected");
+        for (i, input_type) in input.iter().enumerate() {
+            if *input_type != VariantType::Boolean {
+                error.failed_inputs.push((i, describe_unsupported_input(input_type)));
+            }
+        }
+        if error.failed_inputs.is_empty() {
+            Ok(output_slot("result", VariantType::Boolean))
+        } else {
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::std_nodes::logic::*;
+
+    #[test]
+    fn logic_test() {
+        let mut output = vec![Variant::Null];
+        Logic::new(LogicOperation::Xor).invoke(&mut [Variant::Boolean(true), Variant::Boolean(false)], &mut output).unwrap();
+        assert_eq!(output[0], Variant::Boolean(true));
+        Logic::new(LogicOperation::Not).invoke(&mut [Variant::Boolean(true)], &mut output).unwrap();
+        assert_eq!(output[0], Variant::Boolean(false));
+
+        let error = Logic::new(LogicOperation::And).determine(&[VariantType::Boolean, VariantType::IntegerNumber]).err().unwrap();
+        assert_eq!(error.failed_inputs.len(), 1);
+        assert_eq!(error.failed_inputs[0].0, 1);
+    }
+}
@@ -1,3 +1,186 @@
+pub mod arithmetic;
+pub mod array;
+pub mod comparison;
+pub mod compose;
 pub mod constant_boolean;
 pub mod constant_integer;
 pub mod constant_real;
+pub mod constant_string;
+pub mod geometry;
+pub mod linear_algebra;
+pub mod logic;
+pub mod quaternion;
+pub mod trigonometry;
+pub mod utils;
+
+use crate::NodeRegistry;
+use crate::nodes_program::{ NodesProgram, ProgramInput, ProgramOutput };
+use self::arithmetic::*;
+use self::array::*;
+use self::comparison::*;
+use self::compose::*;
+use self::geometry::*;
+use self::linear_algebra::*;
+use self::logic::*;
+use self::quaternion::*;
+use self::trigonometry::*;
+
+pub fn register_std_nodes(registry: &mut NodeRegistry) {
+    let nodes = [
+        ("Constants", "Boolean value set in the node parameters", constant_boolean::ConstantBoolean::new as fn() -> _),
+        ("Constants", "Integer value set in the node parameters", constant_integer::ConstantInteger::new),
+        ("Constants", "Real value set in the node parameters", constant_real::ConstantReal::new),
+        ("Constants", "String value set in the node parameters", constant_string::ConstantString::new),
+        ("Arrays", "Elements of the array selected by a boolean mask", ArrayFilter::new),
+        ("Arrays", "Element of the array at the given index", ArrayIndex::new),
+        ("Arrays", "Number of elements of an array or characters of a string", ArrayLength::new),
+        ("Arrays", "Joins two arrays or two strings", ArrayConcatenate::new),
+        ("Arrays", "Integers from start up to end", Range::new),
+    ];
+    for (category, description, factory) in nodes.iter() {
+        registry.register(category, description, *factory).expect("std node type ids are unique");
+    }
+
+    for operation in ARITHMETIC_OPERATIONS.iter().copied() {
+        registry.register("Math", "Element-wise operation over scalars and vectors", move || Arithmetic::new(operation))
+            .expect("std node type ids are unique");
+        registry.register("Arrays", "Applies the operation to every element of arrays", move || ArrayMap::new(Arithmetic::new(operation)))
+            .expect("std node type ids are unique");
+        registry.register("Arrays", "Folds an array with the operation starting from the initial value", move || ArrayReduce::new(Arithmetic::new(operation)))
+            .expect("std node type ids are unique");
+    }
+    registry.register("Math", "Limits value by min and max", Clamp::new).expect("std node type ids are unique");
+    registry.register("Math", "Linear interpolation between a and b", Lerp::new).expect("std node type ids are unique");
+
+    for function in TRIGONOMETRY_FUNCTIONS.iter().copied() {
+        registry.register("Trigonometry", "Element-wise function, angles are in radians", move || Trigonometry::new(function))
+            .expect("std node type ids are unique");
+        registry.register("Arrays", "Applies the function to every element of arrays", move || ArrayMap::new(Trigonometry::new(function)))
+            .expect("std node type ids are unique");
+    }
+
+    for operation in COMPARISON_OPERATIONS.iter().copied() {
+        registry.register("Comparison", "Compares two values", move || Comparison::new(operation))
+            .expect("std node type ids are unique");
+        registry.register("Arrays", "Applies the operation to every element of arrays", move || ArrayMap::new(Comparison::new(operation)))
+            .expect("std node type ids are unique");
+    }
+
+    for operation in LOGIC_OPERATIONS.iter().copied() {
+        registry.register("Logic", "Boolean operation", move || Logic::new(operation))
+            .expect("std node type ids are unique");
+        registry.register("Arrays", "Applies the operation to every element of arrays", move || ArrayMap::new(Logic::new(operation)))
+            .expect("std node type ids are unique");
+        if operation != LogicOperation::Not {
+            registry.register("Arrays", "Folds an array with the operation starting from the initial value", move || ArrayReduce::new(Logic::new(operation)))
+                .expect("std node type ids are unique");
+    }
+    }
+
+    for variant_type in COMPOSABLE_TYPES.iter() {
+        let compose_type = variant_type.clone();
+        let decompose_type = variant_type.clone();
+        registry.register("Linear Algebra", "Builds a value from its components or columns", move || Compose::new(compose_type.clone()))
+            .expect("std node type ids are unique");
+        registry.register("Linear Algebra", "Splits a value into its components or columns", move || Decompose::new(decompose_type.clone()))
+            .expect("std node type ids are unique");
+    }
+    for operation in LINEAR_ALGEBRA_OPERATIONS.iter().copied() {
+        registry.register("Linear Algebra", "Vector and matrix operation", move || LinearAlgebra::new(operation))
+            .expect("std node type ids are unique");
+    }
+    for operation in QUATERNION_OPERATIONS.iter().copied() {
+        registry.register("Linear Algebra", "Rotation operation, angles are in radians", move || QuaternionNode::new(operation))
+            .expect("std node type ids are unique");
+    }
+
+    for operation in GEOMETRY_OPERATIONS.iter().copied() {
+        registry.register("Geometry", "Mesh primitive or processing", move || Geometry::new(operation))
+            .expect("std node type ids are unique");
+    }
+    for attribute in MeshAttribute::get_all() {
+        registry.register("Geometry", "Reads a mesh attribute as an array", move || GetMeshAttribute::new(attribute))
+            .expect("std node type ids are unique");
+        registry.register("Geometry", "Replaces a mesh attribute, an empty array removes it", move || SetMeshAttribute::new(attribute))
+            .expect("std node type ids are unique");
+    }
+
+    registry.register("Program", "Argument of the program", ProgramInput::new).expect("std node type ids are unique");
+    registry.register("Program", "Result of the program", ProgramOutput::new).expect("std node type ids are unique");
+    registry.register("Program", "Nodes collapsed into one node", NodesProgram::new_unbound).expect("std node type ids are unique");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn math_graph_test() {
+        let mut document = NodesDocumentImpl::new();
+        let a = add_node(&mut document, "std.constant_integer");
+        let b = add_node(&mut document, "std.constant_real");
+        let multiply = add_node(&mut document, "std.multiply");
+        let less = add_node(&mut document, "std.less");
+        let not = add_node(&mut document, "std.not");
+        document.set_node_parameter(a, "value", Variant::IntegerNumber(3)).unwrap();
+        document.set_node_parameter(b, "value", Variant::RealNumber(0.5)).unwrap();
+
+        let slot = |node, slot_index| NodeDocumentSlot { node, slot_name: String::new(), slot_index };
+        let mut connect = |from, to, to_index| {
+            document.try_add_connection(NodeDocumentConnection { from: slot(from, 0), to: slot(to, to_index) }).unwrap();
+        };
+        connect(a, multiply, 0);
+        connect(b, multiply, 1);
+        connect(multiply, less, 0);
+        connect(a, less, 1);
+        connect(less, not, 0);
+
+        let evaluation = evaluate(&document).unwrap();
+        assert_eq!(evaluation.get_output(multiply, 0), Some(&Variant::RealNumber(1.5)));
+        assert_eq!(evaluation.get_output(less, 0), Some(&Variant::Boolean(true)));
+        assert_eq!(evaluation.get_output(not, 0), Some(&Variant::Boolean(false)));
+
+        let and = add_node(&mut document, "std.and");
+        let refused = document.try_add_connection(NodeDocumentConnection { from: slot(multiply, 0), to: slot(and, 0) });
+        assert!(matches!(refused, Err(ConnectionError::TypeMismatch(VariantType::RealNumber, _))));
+    }
+
+    fn add_node(document: &mut NodesDocumentImpl, type_id: &str) -> NodeHandler {
+        let node = document.create_node(type_id).unwrap();
+        document.add_node(node)
+    }
+
+    #[test]
+    fn constants_test() {
+        let mut document = NodesDocumentImpl::new();
+        let values = [
+            ("std.constant_boolean", Variant::Boolean(true)),
+            ("std.constant_integer", Variant::IntegerNumber(-3)),
+            ("std.constant_real", Variant::RealNumber(1.5)),
+            ("std.constant_string", Variant::String(String::from("text"))),
+        ];
+
+        let mut handlers = Vec::new();
+        for (type_id, value) in values.iter() {
+            let mut node = document.create_node(type_id).unwrap();
+            assert_eq!(node.get_parameters_info()[0].variant_type, value.get_type());
+            node.set_parameter("value", value.clone()).unwrap();
+            assert_eq!(node.get_parameter("value"), Some(value.clone()));
+            assert!(node.set_parameter("value", Variant::Null).is_err());
+            assert!(node.set_parameter("unknown", value.clone()).is_err());
+            assert!(node.get_inputs().unwrap().is_empty());
+            handlers.push(document.add_node(node));
+        }
+
+        let evaluation = evaluate(&document).unwrap();
+        for (handler, (_, value)) in handlers.iter().zip(values.iter()) {
+            assert_eq!(evaluation.get_output_type(*handler, 0), Some(value.get_type()));
+            assert_eq!(evaluation.get_output(*handler, 0), Some(value));
+        }
+
+        document.set_node_parameter(handlers[2], "value", Variant::RealNumber(-2.)).unwrap();
+        assert!(document.set_node_parameter(handlers[2], "value", Variant::Boolean(false)).is_err());
+        let evaluation = evaluate(&document).unwrap();
+        assert_eq!(evaluation.get_output(handlers[2], 0), Some(&Variant::RealNumber(-2.)));
+    }
+}
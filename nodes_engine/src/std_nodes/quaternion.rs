@@ -0,0 +1,191 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::std_nodes::utils::*;
+use nalgebra::{ Unit, UnitQuaternion };
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum QuaternionOperation {
+    FromAxisAngle,
+    FromEuler,
+    Slerp,
+    ToMatrix,
+}
+
+pub const QUATERNION_OPERATIONS: [QuaternionOperation; 4] = [
+    QuaternionOperation::FromAxisAngle,
+    QuaternionOperation::FromEuler,
+    QuaternionOperation::Slerp,
+    QuaternionOperation::ToMatrix,
+];
+
+impl QuaternionOperation {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            QuaternionOperation::FromAxisAngle => "Quaternion From Axis Angle",
+            QuaternionOperation::FromEuler => "Quaternion From Euler",
+            QuaternionOperation::Slerp => "Slerp",
+            QuaternionOperation::ToMatrix => "Quaternion To Matrix",
+        }
+    }
+
+    pub fn get_type_id(&self) -> &'static str {
+        match self {
+            QuaternionOperation::FromAxisAngle => "std.quaternion_from_axis_angle",
+            QuaternionOperation::FromEuler => "std.quaternion_from_euler",
+            QuaternionOperation::Slerp => "std.slerp",
+            QuaternionOperation::ToMatrix => "std.quaternion_to_matrix",
+        }
+    }
+
+    fn get_inputs(&self) -> &'static [(&'static str, Option<VariantType>)] {
+        // `None` stands for any scalar
+        match self {
+            QuaternionOperation::FromAxisAngle => &[("axis", Some(VariantType::Vector3)), ("angle", None)],
+            QuaternionOperation::FromEuler => &[("roll", None), ("pitch", None), ("yaw", None)],
+            QuaternionOperation::Slerp => &[("a", Some(VariantType::Quaternion)), ("b", Some(VariantType::Quaternion)), ("t", None)],
+            QuaternionOperation::ToMatrix => &[("rotation", Some(VariantType::Quaternion))],
+        }
+    }
+
+    fn get_outputs(&self) -> Vec<NodeOutputSlotInfo> {
+        match self {
+            QuaternionOperation::ToMatrix => vec![
+                NodeOutputSlotInfo { name: String::from("matrix3"), variant_type: VariantType::Matrix3 },
+                NodeOutputSlotInfo { name: String::from("matrix4"), variant_type: VariantType::Matrix4 },
+            ],
+            _ => output_slot("result", VariantType::Quaternion),
+        }
+    }
+}
+
+fn get_rotation(value: &Variant) -> Option<UnitQuaternion<f64>> {
+    match value {
+        Variant::Quaternion(q) => Unit::try_new(*q, f64::EPSILON),
+        _ => None,
+    }
+}
+
+// quaternion construction and conversion, angles are in radians
+pub struct QuaternionNode {
+    operation: QuaternionOperation,
+}
+
+impl QuaternionNode {
+    pub fn new(operation: QuaternionOperation) -> Box<dyn Node> {
+        Box::new(
+            QuaternionNode {
+                operation
+            }
+        )
+    }
+}
+
+impl Node for QuaternionNode {
+
+    fn get_name(&self) -> String {
+        String::from(self.operation.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from(self.operation.get_type_id())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        let outputs_count = self.determine(&get_input_types(input))?.len();
+        check_outputs_count(output, outputs_count)?;
+        match self.operation {
+            QuaternionOperation::FromAxisAngle => {
+                let axis = match &input[0] {
+                    Variant::Vector3(axis) => Unit::try_new(*axis, f64::EPSILON),
+                    _ => None,
+                };
+                let axis = axis.ok_or_else(|| NodeError::new("rotation axis can't be zero"))?;
+                let angle = get_real(&input[1]).unwrap_or_default();
+                output[0] = Variant::Quaternion(UnitQuaternion::from_axis_angle(&axis, angle).into_inner());
+            },
+            QuaternionOperation::FromEuler => {
+                let angles: Vec<f64> = input.iter().map(|value| get_real(value).unwrap_or_default()).collect();
+                output[0] = Variant::Quaternion(UnitQuaternion::from_euler_angles(angles[0], angles[1], angles[2]).into_inner());
+            },
+            QuaternionOperation::Slerp => {
+                let a = get_rotation(&input[0]).ok_or_else(|| NodeError::new("a is not a rotation"))?;
+                let b = get_rotation(&input[1]).ok_or_else(|| NodeError::new("b is not a rotation"))?;
+                let t = get_real(&input[2]).unwrap_or_default();
+                let result = a.try_slerp(&b, t, f64::EPSILON)
+                    .ok_or_else(|| NodeError::new("slerp is not defined for opposite rotations"))?;
+                output[0] = Variant::Quaternion(result.into_inner());
+            },
+            QuaternionOperation::ToMatrix => {
+                let rotation = get_rotation(&input[0]).ok_or_else(|| NodeError::new("rotation can't be zero"))?;
+                output[0] = Variant::Matrix3(rotation.to_rotation_matrix().into_inner());
+                output[1] = Variant::Matrix4(rotation.to_homogeneous());
+            },
+        }
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        let names: Vec<&str> = self.operation.get_inputs().iter().map(|(name, _)| *name).collect();
+        Ok(input_slots(&names))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        let expected = self.operation.get_inputs();
+        check_inputs_count(input, expected.len())?;
+        let mut error = NodeError::new(&format!("unexpected inputs of {}", self.operation.get_name()));
+        for (i, (input_type, (_, expected_type))) in input.iter().zip(expected.iter()).enumerate() {
+            match expected_type {
+                Some(expected_type) if input_type != expected_type => {
+                    error.failed_inputs.push((i, format!("expected {:?} but got {:?}", expected_type, input_type)));
+                },
+                None if get_components_count(input_type) != Some(1) => {
+                    error.failed_inputs.push((i, describe_unsupported_input(input_type)));
+                },
+                _ => {},
+            }
+        }
+        if error.failed_inputs.is_empty() {
+            Ok(self.operation.get_outputs())
+        } else {
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::std_nodes::quaternion::*;
+    use crate::std_nodes::utils::invoke_outputs;
+    use nalgebra::{ Quaternion, Vector3 };
+
+    fn assert_quaternion_eq(value: &Variant, expected: UnitQuaternion<f64>) {
+        match value {
+            Variant::Quaternion(q) => assert!((q - expected.into_inner()).norm() < 1e-9, "{:?} != {:?}", q, expected),
+            _ => panic!("quaternion expected"),
+        }
+    }
+
+    #[test]
+    fn quaternion_test() {
+        let angle = Variant::RealNumber(std::f64::consts::FRAC_PI_2);
+        let z = Variant::Vector3(Vector3::z());
+        let rotation = invoke_outputs(QuaternionNode::new(QuaternionOperation::FromAxisAngle).as_ref(), vec![z, angle], 1).unwrap().remove(0);
+        let expected = UnitQuaternion::from_euler_angles(0., 0., std::f64::consts::FRAC_PI_2);
+        assert_quaternion_eq(&rotation, expected);
+        let euler = vec![Variant::IntegerNumber(0), Variant::IntegerNumber(0), Variant::RealNumber(std::f64::consts::FRAC_PI_2)];
+        assert_quaternion_eq(&invoke_outputs(QuaternionNode::new(QuaternionOperation::FromEuler).as_ref(), euler, 1).unwrap()[0], expected);
+        assert!(invoke_outputs(QuaternionNode::new(QuaternionOperation::FromAxisAngle).as_ref(), vec![Variant::Vector3(Vector3::zeros()), Variant::RealNumber(1.)], 1).is_err());
+
+        let identity = Variant::Quaternion(Quaternion::identity());
+        let half = invoke_outputs(QuaternionNode::new(QuaternionOperation::Slerp).as_ref(), vec![identity, rotation.clone(), Variant::RealNumber(0.5)], 1).unwrap();
+        assert_quaternion_eq(&half[0], UnitQuaternion::from_euler_angles(0., 0., std::f64::consts::FRAC_PI_4));
+
+        let matrices = invoke_outputs(QuaternionNode::new(QuaternionOperation::ToMatrix).as_ref(), vec![rotation], 2).unwrap();
+        match &matrices[0] {
+            Variant::Matrix3(m) => assert!((m * Vector3::x() - Vector3::y()).norm() < 1e-9 && m.determinant() > 0.),
+            _ => panic!("matrix expected"),
+        }
+        assert_eq!(matrices[1].get_type(), VariantType::Matrix4);
+        assert!(QuaternionNode::new(QuaternionOperation::ToMatrix).determine(&[VariantType::Matrix3]).is_err());
+    }
+}
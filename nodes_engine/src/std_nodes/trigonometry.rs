@@ -0,0 +1,120 @@
+use crate::{ Node, NodeError, Variant, VariantType, NodeInputSlotInfo, NodeOutputSlotInfo };
+use crate::std_nodes::utils::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TrigonometryFunction {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+}
+
+pub const TRIGONOMETRY_FUNCTIONS: [TrigonometryFunction; 7] = [
+    TrigonometryFunction::Sin,
+    TrigonometryFunction::Cos,
+    TrigonometryFunction::Tan,
+    TrigonometryFunction::Asin,
+    TrigonometryFunction::Acos,
+    TrigonometryFunction::Atan,
+    TrigonometryFunction::Atan2,
+];
+
+impl TrigonometryFunction {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            TrigonometryFunction::Sin => "Sin",
+            TrigonometryFunction::Cos => "Cos",
+            TrigonometryFunction::Tan => "Tan",
+            TrigonometryFunction::Asin => "Asin",
+            TrigonometryFunction::Acos => "Acos",
+            TrigonometryFunction::Atan => "Atan",
+            TrigonometryFunction::Atan2 => "Atan2",
+        }
+    }
+
+    fn get_input_names(&self) -> &'static [&'static str] {
+        match self {
+            TrigonometryFunction::Atan2 => &["y", "x"],
+            _ => &["angle"],
+        }
+    }
+
+    fn apply(&self, v: &[f64]) -> f64 {
+        match self {
+            TrigonometryFunction::Sin => v[0].sin(),
+            TrigonometryFunction::Cos => v[0].cos(),
+            TrigonometryFunction::Tan => v[0].tan(),
+            TrigonometryFunction::Asin => v[0].asin(),
+            TrigonometryFunction::Acos => v[0].acos(),
+            TrigonometryFunction::Atan => v[0].atan(),
+            TrigonometryFunction::Atan2 => v[0].atan2(v[1]),
+        }
+    }
+}
+
+// element-wise trigonometric function, angles are in radians
+pub struct Trigonometry {
+    function: TrigonometryFunction,
+}
+
+impl Trigonometry {
+    pub fn new(function: TrigonometryFunction) -> Box<dyn Node> {
+        Box::new(
+            Trigonometry {
+                function
+            }
+        )
+    }
+}
+
+impl Node for Trigonometry {
+
+    fn get_name(&self) -> String {
+        String::from(self.function.get_name())
+    }
+
+    fn get_type_id(&self) -> String {
+        format!("std.{}", self.function.get_name().to_lowercase())
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        check_outputs_count(output, 1)?;
+        let output_type = self.determine(&get_input_types(input))?.remove(0).variant_type;
+        output[0] = apply_real(&output_type, input, |v| self.function.apply(v))?;
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(input_slots(self.function.get_input_names()))
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        check_inputs_count(input, self.function.get_input_names().len())?;
+        Ok(output_slot("result", broadcast_real_types(input)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::std_nodes::trigonometry::*;
+    use nalgebra::Vector2;
+
+    #[test]
+    fn trigonometry_test() {
+        let sin = Trigonometry::new(TrigonometryFunction::Sin);
+        let mut output = vec![Variant::Null];
+        sin.invoke(&mut [Variant::Vector2(Vector2::new(0., std::f64::consts::FRAC_PI_2))], &mut output).unwrap();
+        assert_eq!(output[0], Variant::Vector2(Vector2::new(0., 1.)));
+
+        let atan2 = Trigonometry::new(TrigonometryFunction::Atan2);
+        assert_eq!(atan2.get_inputs().unwrap().len(), 2);
+        atan2.invoke(&mut [Variant::IntegerNumber(1), Variant::IntegerNumber(1)], &mut output).unwrap();
+        assert_eq!(output[0], Variant::RealNumber(std::f64::consts::FRAC_PI_4));
+        assert!(atan2.determine(&[VariantType::RealNumber]).is_err());
+        assert_eq!(atan2.determine(&[VariantType::Boolean, VariantType::RealNumber]).err().unwrap().failed_inputs[0].0, 0);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{ NodeError, NodeInputSlotInfo, NodeOutputSlotInfo, Variant, VariantType };
+
+// number of real components taking part in element-wise math, scalars have one
+pub fn get_components_count(variant_type: &VariantType) -> Option<usize> {
+    match variant_type {
+        VariantType::IntegerNumber | VariantType::RealNumber => Some(1),
+        VariantType::Vector2 | VariantType::RowVector2 => Some(2),
+        VariantType::Vector3 | VariantType::RowVector3 => Some(3),
+        VariantType::Vector4 | VariantType::RowVector4 => Some(4),
+        _ => None,
+    }
+}
+
+pub fn describe_unsupported_input(variant_type: &VariantType) -> String {
+    match variant_type {
+        VariantType::Null => String::from("input is not connected"),
+        _ => format!("{:?} is not supported", variant_type),
+    }
+}
+
+// result type of an element-wise operation: integers stay integers,
+// scalars are broadcasted to the vector type and all vector inputs have to match
+pub fn broadcast_types(input: &[VariantType]) -> Result<VariantType, NodeError> {
+    let mut error = NodeError::new("inputs can't be broadcasted to a common type");
+    let mut vector_type: Option<VariantType> = None;
+    let mut all_integers = true;
+
+    for (i, input_type) in input.iter().enumerate() {
+        match get_components_count(input_type) {
+            None => error.failed_inputs.push((i, describe_unsupported_input(input_type))),
+            Some(1) => all_integers = all_integers && *input_type == VariantType::IntegerNumber,
+            Some(_) => {
+                all_integers = false;
+                match &vector_type {
+                    None => vector_type = Some(input_type.clone()),
+                    Some(vector_type) if vector_type == input_type => {},
+                    Some(vector_type) => {
+                        error.failed_inputs.push((i, format!("expected {:?} but got {:?}", vector_type, input_type)))
+                    },
+                }
+            },
+        }
+    }
+
+    if !error.failed_inputs.is_empty() {
+        Err(error)
+    } else if let Some(vector_type) = vector_type {
+        Ok(vector_type)
+    } else if all_integers {
+        Ok(VariantType::IntegerNumber)
+    } else {
+        Ok(VariantType::RealNumber)
+    }
+}
+
+// same as `broadcast_types`, but integers are promoted to reals
+pub fn broadcast_real_types(input: &[VariantType]) -> Result<VariantType, NodeError> {
+    match broadcast_types(input)? {
+        VariantType::IntegerNumber => Ok(VariantType::RealNumber),
+        result => Ok(result),
+    }
+}
+
+pub fn get_real(value: &Variant) -> Option<f64> {
+    match value {
+        Variant::IntegerNumber(v) => Some(*v as f64),
+        Variant::RealNumber(v) => Some(*v),
+        _ => None,
+    }
+}
+
+pub fn get_components(value: &Variant) -> Option<Vec<f64>> {
+    match value {
+        Variant::IntegerNumber(v) => Some(vec![*v as f64]),
+        Variant::RealNumber(v) => Some(vec![*v]),
+        Variant::Vector2(v) | Variant::RowVector2(v) => Some(v.iter().copied().collect()),
+        Variant::Vector3(v) | Variant::RowVector3(v) => Some(v.iter().copied().collect()),
+        Variant::Vector4(v) | Variant::RowVector4(v) => Some(v.iter().copied().collect()),
+        _ => None,
+    }
+}
+
+pub fn from_components(variant_type: &VariantType, components: &[f64]) -> Option<Variant> {
+    if get_components_count(variant_type) != Some(components.len()) {
+        return None;
+    }
+    match variant_type {
+        VariantType::RealNumber => Some(Variant::RealNumber(components[0])),
+        VariantType::Vector2 => Some(Variant::Vector2(nalgebra::Vector2::from_column_slice(components))),
+        VariantType::Vector3 => Some(Variant::Vector3(nalgebra::Vector3::from_column_slice(components))),
+        VariantType::Vector4 => Some(Variant::Vector4(nalgebra::Vector4::from_column_slice(components))),
+        VariantType::RowVector2 => Some(Variant::RowVector2(nalgebra::Vector2::from_column_slice(components))),
+        VariantType::RowVector3 => Some(Variant::RowVector3(nalgebra::Vector3::from_column_slice(components))),
+        VariantType::RowVector4 => Some(Variant::RowVector4(nalgebra::Vector4::from_column_slice(components))),
+        _ => None,
+    }
+}
+
+// applies `function` to every component of the result, scalar inputs are repeated for each component
+pub fn apply_real<F>(output_type: &VariantType, input: &[Variant], function: F) -> Result<Variant, NodeError>
+where
+    F: Fn(&[f64]) -> f64,
+{
+    let count = get_components_count(output_type)
+        .ok_or_else(|| NodeError::new(&format!("{:?} is not an element-wise type", output_type)))?;
+
+    let mut error = NodeError::new("inputs don't match the determined type");
+    let mut components = Vec::with_capacity(input.len());
+    for (i, value) in input.iter().enumerate() {
+        match get_components(value) {
+            Some(c) if c.len() == 1 || c.len() == count => components.push(c),
+            _ => error.failed_inputs.push((i, describe_unsupported_input(&value.get_type()))),
+        }
+    }
+    if !error.failed_inputs.is_empty() {
+        return Err(error);
+    }
+
+    let mut arguments = vec![0.; input.len()];
+    let mut result = Vec::with_capacity(count);
+    for component in 0..count {
+        for (argument, c) in arguments.iter_mut().zip(components.iter()) {
+            *argument = if c.len() == 1 { c[0] } else { c[component] };
+        }
+        result.push(function(&arguments));
+    }
+
+    from_components(output_type, &result)
+        .ok_or_else(|| NodeError::new(&format!("{:?} is not an element-wise type", output_type)))
+}
+
+// integer counterpart of `apply_real`, `function` returns None on overflow or invalid arguments
+pub fn apply_integer<F>(input: &[Variant], function: F) -> Result<Variant, NodeError>
+where
+    F: Fn(&[i64]) -> Option<i64>,
+{
+    let mut error = NodeError::new("integer inputs expected");
+    let mut arguments = Vec::with_capacity(input.len());
+    for (i, value) in input.iter().enumerate() {
+        match value {
+            Variant::IntegerNumber(v) => arguments.push(*v),
+            _ => error.failed_inputs.push((i, describe_unsupported_input(&value.get_type()))),
+        }
+    }
+    if !error.failed_inputs.is_empty() {
+        return Err(error);
+    }
+
+    function(&arguments)
+        .map(Variant::IntegerNumber)
+        .ok_or_else(|| NodeError::new("integer operation overflow or invalid arguments"))
+}
+
+pub fn get_input_types(input: &[Variant]) -> Vec<VariantType> {
+    input.iter().map(|value| value.get_type()).collect()
+}
+
+pub fn check_inputs_count(input: &[VariantType], count: usize) -> Result<(), NodeError> {
+    if input.len() == count {
+        Ok(())
+    } else {
+        Err(NodeError::new(&format!("expected {} inputs but got {}", count, input.len())))
+    }
+}
+
+pub fn check_outputs_count(output: &[Variant], count: usize) -> Result<(), NodeError> {
+    if output.len() == count {
+        Ok(())
+    } else {
+        Err(NodeError::new(&format!("expected {} outputs but got {}", count, output.len())))
+    }
+}
+
+pub fn input_slots(names: &[&str]) -> Vec<NodeInputSlotInfo> {
+    names.iter().map(|name| NodeInputSlotInfo { name: String::from(*name) }).collect()
+}
+
+pub fn output_slot(name: &str, variant_type: VariantType) -> Vec<NodeOutputSlotInfo> {
+    vec![NodeOutputSlotInfo { name: String::from(name), variant_type }]
+}
+
+// invokes a node with a single output, used by the tests of the std nodes
+#[cfg(test)]
+pub fn invoke(node: &dyn crate::Node, input: Vec<Variant>) -> Result<Variant, NodeError> {
+    invoke_outputs(node, input, 1).map(|mut output| output.remove(0))
+}
+
+#[cfg(test)]
+pub fn invoke_outputs(node: &dyn crate::Node, mut input: Vec<Variant>, outputs_count: usize) -> Result<Vec<Variant>, NodeError> {
+    let mut output = vec![Variant::Null; outputs_count];
+    node.invoke(&mut input, &mut output)?;
+    Ok(output)
+}
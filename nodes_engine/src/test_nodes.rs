@@ -0,0 +1,130 @@
+use crate::*;
+
+pub struct TestConstant {
+    pub value: f64,
+}
+
+impl Node for TestConstant {
+    fn get_name(&self) -> String {
+        String::from("Test Constant")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("test.constant")
+    }
+
+    fn get_parameters(&self) -> Vec<(String, Variant)> {
+        vec![(String::from("value"), Variant::RealNumber(self.value))]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: Variant) -> Result<(), NodeError> {
+        match (name, value) {
+            ("value", Variant::RealNumber(value)) => {
+                self.value = value;
+                Ok(())
+            },
+            _ => Err(NodeError::new("unknown parameter")),
+        }
+    }
+
+    fn invoke(&self, _input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        output[0] = Variant::RealNumber(self.value);
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(Vec::new())
+    }
+
+    fn determine(&self, _input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        Ok(vec![NodeOutputSlotInfo { name: String::from("value"), variant_type: VariantType::RealNumber }])
+    }
+}
+
+pub struct TestFlag {}
+
+impl Node for TestFlag {
+    fn get_name(&self) -> String {
+        String::from("Test Flag")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("test.flag")
+    }
+
+    fn invoke(&self, _input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        output[0] = Variant::Boolean(true);
+        Ok(())
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(Vec::new())
+    }
+
+    fn determine(&self, _input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        Ok(vec![NodeOutputSlotInfo { name: String::from("flag"), variant_type: VariantType::Boolean }])
+    }
+}
+
+pub struct TestSum {}
+
+impl Node for TestSum {
+    fn get_name(&self) -> String {
+        String::from("Test Sum")
+    }
+
+    fn get_type_id(&self) -> String {
+        String::from("test.sum")
+    }
+
+    fn invoke(&self, input: &mut[Variant], output: &mut[Variant]) -> Result<(), NodeError> {
+        match (&input[0], &input[1]) {
+            (Variant::RealNumber(a), Variant::RealNumber(b)) => {
+                output[0] = Variant::RealNumber(a + b);
+                Ok(())
+            },
+            _ => Err(NodeError::new("unexpected input")),
+        }
+    }
+
+    fn get_inputs(&self) -> Result<Vec<NodeInputSlotInfo>, NodeError> {
+        Ok(vec![
+            NodeInputSlotInfo { name: String::from("a") },
+            NodeInputSlotInfo { name: String::from("b") },
+        ])
+    }
+
+    fn determine(&self, input: &[VariantType]) -> Result<Vec<NodeOutputSlotInfo>, NodeError> {
+        let mut error = NodeError::new("sum expects real numbers");
+        for (i, t) in input.iter().enumerate() {
+            if *t != VariantType::RealNumber {
+                error.failed_inputs.push((i, String::from("expected real number")));
+            }
+        }
+        if error.failed_inputs.is_empty() {
+            Ok(vec![NodeOutputSlotInfo { name: String::from("sum"), variant_type: VariantType::RealNumber }])
+        } else {
+            Err(error)
+        }
+    }
+}
+
+pub fn test_registry() -> std::sync::Arc<NodeRegistry> {
+    let mut registry = NodeRegistry::with_std_nodes();
+    registry.register("Test", "", || Box::new(TestConstant { value: 0. })).unwrap();
+    registry.register("Test", "", || Box::new(TestFlag {})).unwrap();
+    registry.register("Test", "", || Box::new(TestSum {})).unwrap();
+    std::sync::Arc::new(registry)
+}
+
+pub fn slot(node: NodeHandler, slot_index: u64) -> NodeDocumentSlot {
+    NodeDocumentSlot { node, slot_name: String::new(), slot_index }
+}
+
+pub fn connection(from: NodeHandler, from_index: u64, to: NodeHandler, to_index: u64) -> NodeDocumentConnection {
+    NodeDocumentConnection { from: slot(from, from_index), to: slot(to, to_index) }
+}
+
+pub fn connect(document: &mut NodesDocumentImpl, from: NodeHandler, to: NodeHandler, to_index: u64) {
+    document.add_connection(connection(from, 0, to, to_index));
+}
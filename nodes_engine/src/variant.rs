@@ -1,5 +1,8 @@
 use nalgebra;
+use common::mesh::Mesh;
+use serde::{ Serialize, Deserialize };
 
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Variant {
     Null,
     Boolean(bool),
@@ -15,8 +18,13 @@ pub enum Variant {
     Matrix3(nalgebra::Matrix3<f64>),
     Matrix4(nalgebra::Matrix4<f64>),
     Quaternion(nalgebra::Quaternion<f64>),
+    String(String),
+    Mesh(Box<Mesh>),
+    // homogeneous, the element type is kept so empty arrays are typed too
+    Array(VariantType, Vec<Variant>),
 }
 
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum VariantType {
     Null,
     Boolean,
@@ -32,9 +40,115 @@ pub enum VariantType {
     Matrix3,
     Matrix4,
     Quaternion,
+    String,
+    Mesh,
+    Array(Box<VariantType>),
+}
+
+// every type except arrays, whose element types are arbitrary
+pub const VARIANT_TYPES: [VariantType; 16] = [
+    VariantType::Null,
+    VariantType::Boolean,
+    VariantType::IntegerNumber,
+    VariantType::RealNumber,
+    VariantType::Vector2,
+    VariantType::Vector3,
+    VariantType::Vector4,
+    VariantType::RowVector2,
+    VariantType::RowVector3,
+    VariantType::RowVector4,
+    VariantType::Matrix2,
+    VariantType::Matrix3,
+    VariantType::Matrix4,
+    VariantType::Quaternion,
+    VariantType::String,
+    VariantType::Mesh,
+];
+
+impl VariantType {
+    // one step implicit conversions, the type lattice is their transitive closure,
+    // arrays convert element-wise
+    fn get_direct_conversions(&self) -> Vec<VariantType> {
+        match self {
+            VariantType::Boolean => vec![VariantType::IntegerNumber],
+            VariantType::IntegerNumber => vec![VariantType::RealNumber],
+            VariantType::Vector2 => vec![VariantType::RowVector2],
+            VariantType::Vector3 => vec![VariantType::RowVector3],
+            VariantType::Vector4 => vec![VariantType::RowVector4],
+            VariantType::Array(element_type) => element_type.get_direct_conversions()
+                .into_iter()
+                .map(|element_type| VariantType::Array(Box::new(element_type)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // types `self` implicitly converts to, the closest ones first, `self` is not included
+    pub fn get_conversion_targets(&self) -> Vec<VariantType> {
+        let mut targets: Vec<VariantType> = Vec::new();
+        let mut i = 0;
+        let mut current = self.clone();
+        loop {
+            for target in current.get_direct_conversions() {
+                if target != *self && !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+            if i == targets.len() {
+                return targets;
+            }
+            current = targets[i].clone();
+            i += 1;
+        }
+    }
+
+    pub fn can_convert_to(&self, target: &VariantType) -> bool {
+        self == target || self.get_conversion_targets().contains(target)
+    }
+
+    // least type all of `types` implicitly convert to, used to pick an overload of a node
+    pub fn get_common_type(types: &[VariantType]) -> Option<VariantType> {
+        let first = types.first()?;
+        let mut candidates = vec![first.clone()];
+        candidates.extend(first.get_conversion_targets());
+        candidates.retain(|candidate| types.iter().all(|t| t.can_convert_to(candidate)));
+        candidates.iter()
+            .find(|candidate| candidates.iter().all(|other| candidate.can_convert_to(other)))
+            .cloned()
+    }
+
+    pub fn get_element_type(&self) -> Option<&VariantType> {
+        match self {
+            VariantType::Array(element_type) => Some(element_type),
+            _ => None,
+        }
+    }
 }
 
 impl Variant {
+    // default value of the given type, numbers are zero and quaternions are the identity; used to allocate node output buffers
+    pub fn new(variant_type: VariantType) -> Variant {
+        match variant_type {
+            VariantType::Null => Variant::Null,
+            VariantType::Boolean => Variant::Boolean(false),
+            VariantType::IntegerNumber => Variant::IntegerNumber(0),
+            VariantType::RealNumber => Variant::RealNumber(0.),
+            VariantType::Vector2 => Variant::Vector2(nalgebra::Vector2::zeros()),
+            VariantType::Vector3 => Variant::Vector3(nalgebra::Vector3::zeros()),
+            VariantType::Vector4 => Variant::Vector4(nalgebra::Vector4::zeros()),
+            VariantType::RowVector2 => Variant::RowVector2(nalgebra::Vector2::zeros()),
+            VariantType::RowVector3 => Variant::RowVector3(nalgebra::Vector3::zeros()),
+            VariantType::RowVector4 => Variant::RowVector4(nalgebra::Vector4::zeros()),
+            VariantType::Matrix2 => Variant::Matrix2(nalgebra::Matrix2::zeros()),
+            VariantType::Matrix3 => Variant::Matrix3(nalgebra::Matrix3::zeros()),
+            VariantType::Matrix4 => Variant::Matrix4(nalgebra::Matrix4::zeros()),
+            VariantType::Quaternion => Variant::Quaternion(nalgebra::Quaternion::identity()),
+            VariantType::String => Variant::String(String::new()),
+            VariantType::Mesh => Variant::Mesh(Box::new(Mesh::new())),
+            VariantType::Array(element_type) => Variant::Array(*element_type, Vec::new()),
+        }
+    }
+
     pub fn get_type(&self) -> VariantType {
         match self {
             Variant::Null => VariantType::Null,
@@ -51,6 +165,70 @@ impl Variant {
             Variant::Matrix3(_) => VariantType::Matrix3,
             Variant::Matrix4(_) => VariantType::Matrix4,
             Variant::Quaternion(_) => VariantType::Quaternion,
+            Variant::String(_) => VariantType::String,
+            Variant::Mesh(_) => VariantType::Mesh,
+            Variant::Array(element_type, _) => VariantType::Array(Box::new(element_type.clone())),
+        }
+    }
+
+    // implicit conversion, None if `self` doesn't convert to `target`
+    pub fn convert_to(&self, target: &VariantType) -> Option<Variant> {
+        match (self, target) {
+            (value, target) if value.get_type() == *target => Some(value.clone()),
+            (Variant::Array(_, elements), VariantType::Array(element_type)) => {
+                let elements: Option<Vec<Variant>> = elements.iter().map(|element| element.convert_to(element_type)).collect();
+                Some(Variant::Array((**element_type).clone(), elements?))
+            },
+            (Variant::Boolean(v), VariantType::IntegerNumber) => Some(Variant::IntegerNumber(*v as i64)),
+            (Variant::Boolean(v), VariantType::RealNumber) => Some(Variant::RealNumber(if *v { 1. } else { 0. })),
+            (Variant::IntegerNumber(v), VariantType::RealNumber) => Some(Variant::RealNumber(*v as f64)),
+            // row vectors share the storage with vectors, so the transpose keeps the components
+            (Variant::Vector2(v), VariantType::RowVector2) => Some(Variant::RowVector2(*v)),
+            (Variant::Vector3(v), VariantType::RowVector3) => Some(Variant::RowVector3(*v)),
+            (Variant::Vector4(v), VariantType::RowVector4) => Some(Variant::RowVector4(*v)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn conversions_test() {
+        for from in VARIANT_TYPES.iter() {
+            for to in VARIANT_TYPES.iter() {
+                let converted = Variant::new(from.clone()).convert_to(to);
+                assert_eq!(from.can_convert_to(to), converted.is_some(), "{:?} -> {:?}", from, to);
+                assert_eq!(converted.map(|value| value.get_type()).unwrap_or_else(|| to.clone()), *to);
+            }
         }
+
+        assert_eq!(VariantType::Boolean.get_conversion_targets(), vec![VariantType::IntegerNumber, VariantType::RealNumber]);
+        assert!(!VariantType::RealNumber.can_convert_to(&VariantType::IntegerNumber));
+        assert_eq!(Variant::Boolean(true).convert_to(&VariantType::RealNumber), Some(Variant::RealNumber(1.)));
+        let v = Vector3::new(1., 2., 3.);
+        assert_eq!(Variant::Vector3(v).convert_to(&VariantType::RowVector3), Some(Variant::RowVector3(v)));
+        assert_eq!(Variant::RowVector3(v).convert_to(&VariantType::Vector3), None);
+    }
+
+    #[test]
+    fn common_type_test() {
+        let common = |types: &[VariantType]| VariantType::get_common_type(types);
+        assert_eq!(common(&[VariantType::Boolean, VariantType::IntegerNumber]), Some(VariantType::IntegerNumber));
+        assert_eq!(common(&[VariantType::Boolean, VariantType::RealNumber]), Some(VariantType::RealNumber));
+        assert_eq!(common(&[VariantType::Vector2, VariantType::RowVector2]), Some(VariantType::RowVector2));
+        assert_eq!(common(&[VariantType::Matrix3, VariantType::Matrix3]), Some(VariantType::Matrix3));
+        assert_eq!(common(&[VariantType::RealNumber, VariantType::Vector2]), None);
+        assert_eq!(common(&[]), None);
+
+        let array = |element_type| VariantType::Array(Box::new(element_type));
+        assert_eq!(common(&[array(VariantType::IntegerNumber), array(VariantType::RealNumber)]), Some(array(VariantType::RealNumber)));
+        assert_eq!(common(&[array(VariantType::IntegerNumber), VariantType::RealNumber]), None);
+        let integers = Variant::Array(VariantType::IntegerNumber, vec![Variant::IntegerNumber(1)]);
+        let reals = Variant::Array(VariantType::RealNumber, vec![Variant::RealNumber(1.)]);
+        assert_eq!(integers.convert_to(&array(VariantType::RealNumber)), Some(reals));
     }
 }
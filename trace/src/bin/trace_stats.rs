@@ -0,0 +1,161 @@
+use std::path::Path;
+use trace::{
+    compare_statistics, compute_statistics, find_regressions, SpanStatistics, StatisticsMetric,
+    TraceCapture,
+};
+
+const USAGE: &str = "usage:
+    trace_stats <capture>
+        prints statistics of the spans of a capture
+    trace_stats <base capture> <current capture> [--metric <metric>] [--threshold <percent>]
+        compares two runs and fails if a span has regressed by more than the threshold,
+        metrics are count, total, self, mean, p50, p95 and p99, the default is mean with 10 percent";
+
+// exit codes, regressions are distinguished from broken runs
+const EXIT_REGRESSION: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+struct Options {
+    captures: Vec<String>,
+    metric: StatisticsMetric,
+    threshold: f64,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        captures: Vec::new(),
+        metric: StatisticsMetric::Mean,
+        threshold: 10.,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--metric" => {
+                let name = args.next().ok_or("--metric needs a value")?;
+                options.metric = StatisticsMetric::parse(name)
+                    .ok_or_else(|| format!("unknown metric {}", name))?;
+            }
+            "--threshold" => {
+                let value = args.next().ok_or("--threshold needs a value")?;
+                options.threshold = value
+                    .parse()
+                    .map_err(|_| format!("invalid threshold {}", value))?;
+            }
+            _ => options.captures.push(arg.clone()),
+        }
+    }
+    match options.captures.len() {
+        1 | 2 => Ok(options),
+        _ => Err(String::from("one or two captures are expected")),
+    }
+}
+
+fn load_statistics(path: &str) -> Result<Vec<SpanStatistics>, String> {
+    TraceCapture::load(Path::new(path))
+        .map(|capture| compute_statistics(&capture))
+        .map_err(|error| format!("failed to load {}: {}", path, error))
+}
+
+fn microseconds(nanoseconds: u64) -> f64 {
+    nanoseconds as f64 / 1000.
+}
+
+fn print_statistics(statistics: &[SpanStatistics]) {
+    println!(
+        "{:<40} {:>8} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+        "span", "count", "total us", "self us", "mean us", "p50 us", "p95 us", "p99 us"
+    );
+    for span in statistics {
+        println!(
+            "{:<40} {:>8} {:>12.3} {:>12.3} {:>12.3} {:>12.3} {:>12.3} {:>12.3}",
+            span.name,
+            span.count,
+            microseconds(span.total_time),
+            microseconds(span.self_time),
+            span.get_mean_time() / 1000.,
+            microseconds(span.p50_time),
+            microseconds(span.p95_time),
+            microseconds(span.p99_time),
+        );
+    }
+}
+
+// times are printed in microseconds like in the statistics table
+fn format_metric(metric: StatisticsMetric, value: f64) -> String {
+    match metric {
+        StatisticsMetric::Count => format!("{}", value),
+        _ => format!("{:.3} us", value / 1000.),
+    }
+}
+
+// returns true if a span has regressed
+fn print_comparison(
+    base: &[SpanStatistics],
+    current: &[SpanStatistics],
+    metric: StatisticsMetric,
+    threshold: f64,
+) -> bool {
+    let comparisons = compare_statistics(base, current);
+    println!(
+        "{:<40} {:>14} {:>14} {:>10}",
+        "span", "base", "current", "change"
+    );
+    for comparison in &comparisons {
+        let value = |statistics: &Option<SpanStatistics>| match statistics {
+            Some(statistics) => format_metric(metric, metric.get(statistics)),
+            None => String::from("-"),
+        };
+        let change = match comparison.get_change(metric) {
+            Some(change) => format!("{:+.1}%", change),
+            None => String::from("-"),
+        };
+        println!(
+            "{:<40} {:>14} {:>14} {:>10}",
+            comparison.name,
+            value(&comparison.base),
+            value(&comparison.current),
+            change
+        );
+    }
+
+    let regressions = find_regressions(&comparisons, metric, threshold);
+    for regression in &regressions {
+        eprintln!(
+            "{} regressed by {:.1}% of {:?}, the threshold is {}%",
+            regression.name,
+            regression.get_change(metric).unwrap_or_default(),
+            metric,
+            threshold
+        );
+    }
+    !regressions.is_empty()
+}
+
+fn run(args: &[String]) -> Result<bool, String> {
+    let options = parse_options(args)?;
+    match options.captures.as_slice() {
+        [capture] => {
+            print_statistics(&load_statistics(capture)?);
+            Ok(false)
+        }
+        [base, current] => Ok(print_comparison(
+            &load_statistics(base)?,
+            &load_statistics(current)?,
+            options.metric,
+            options.threshold,
+        )),
+        _ => unreachable!("the number of captures is checked by parse_options"),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(false) => {}
+        Ok(true) => std::process::exit(EXIT_REGRESSION),
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            std::process::exit(EXIT_ERROR);
+        }
+    }
+}
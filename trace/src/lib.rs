@@ -1,6 +1,24 @@
+// generated code refers to `::trace`, this makes it work inside the crate too
+extern crate self as trace;
+
 #[macro_use]
 pub mod trace_point;
+pub mod trace_binary;
+pub mod trace_capture;
+pub mod trace_chrome;
+pub mod trace_collector;
+pub mod trace_event;
 pub mod trace_marker;
+pub mod trace_spans;
+pub mod trace_statistics;
+pub mod trace_stream;
 
-pub use self::trace_point::*;
+pub use self::trace_capture::*;
+pub use self::trace_collector::*;
+pub use self::trace_event::*;
 pub use self::trace_marker::*;
+pub use self::trace_point::*;
+pub use self::trace_spans::*;
+pub use self::trace_statistics::*;
+pub use self::trace_stream::*;
+pub use trace_proc_macro::trace;
@@ -0,0 +1,313 @@
+use crate::trace_capture::{
+    invalid_data, CapturedEvent, CapturedPoint, CapturedThread, TraceCapture,
+};
+use crate::{TraceEventKind, TraceValue};
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"TRCB";
+
+// increase on every incompatible change of the format,
+// version 2 added markers, counters and arguments, version 1 files are still readable
+pub const TRACE_BINARY_VERSION: u32 = 2;
+
+// all integers except the version are LEB128 varints, strings are length prefixed utf-8,
+// timestamps are stored as deltas from the previous event of the thread;
+// events other than enter and leave are followed by a value tag, integer values are zigzag varints
+// and float values are little endian bits
+pub fn write_binary(capture: &TraceCapture, writer: &mut dyn Write) -> std::io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&TRACE_BINARY_VERSION.to_le_bytes())?;
+
+    write_varint(writer, capture.points.len() as u64)?;
+    for point in &capture.points {
+        write_string(writer, &point.name)?;
+        write_string(writer, &point.file)?;
+        write_varint(writer, u64::from(point.line))?;
+    }
+
+    write_varint(writer, capture.threads.len() as u64)?;
+    for thread in &capture.threads {
+        write_varint(writer, thread.id)?;
+        match &thread.name {
+            Some(name) => {
+                writer.write_all(&[1])?;
+                write_string(writer, name)?;
+            }
+            None => writer.write_all(&[0])?,
+        }
+
+        write_varint(writer, thread.events.len() as u64)?;
+        let mut previous_timestamp = 0;
+        for event in &thread.events {
+            writer.write_all(&[kind_to_byte(event.kind)])?;
+            write_varint(writer, u64::from(event.point))?;
+            let delta = event
+                .timestamp
+                .checked_sub(previous_timestamp)
+                .ok_or_else(|| invalid_data("timestamps of a thread have to be monotonic"))?;
+            write_varint(writer, delta)?;
+            write_varint(writer, u64::from(event.depth))?;
+            if has_value(event.kind) {
+                write_value(writer, event.value)?;
+            }
+            previous_timestamp = event.timestamp;
+        }
+    }
+    writer.flush()
+}
+
+pub fn read_binary(reader: &mut dyn Read) -> std::io::Result<TraceCapture> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a binary trace"));
+    }
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version > TRACE_BINARY_VERSION {
+        return Err(invalid_data(format!(
+            "unsupported binary trace version {}",
+            version
+        )));
+    }
+
+    let mut capture = TraceCapture::new();
+    let points_count = read_varint(reader)?;
+    for _ in 0..points_count {
+        capture.points.push(CapturedPoint {
+            name: read_string(reader)?,
+            file: read_string(reader)?,
+            line: read_u32(reader)?,
+        });
+    }
+
+    let threads_count = read_varint(reader)?;
+    for _ in 0..threads_count {
+        let id = read_varint(reader)?;
+        let name = match read_byte(reader)? {
+            0 => None,
+            1 => Some(read_string(reader)?),
+            flag => return Err(invalid_data(format!("invalid thread name flag {}", flag))),
+        };
+
+        let events_count = read_varint(reader)?;
+        let mut events = Vec::new();
+        let mut timestamp = 0u64;
+        for _ in 0..events_count {
+            let kind = byte_to_kind(read_byte(reader)?)?;
+            let point = read_u32(reader)?;
+            if point as usize >= capture.points.len() {
+                return Err(invalid_data(format!("invalid trace point {}", point)));
+            }
+            timestamp = timestamp
+                .checked_add(read_varint(reader)?)
+                .ok_or_else(|| invalid_data("timestamp overflow"))?;
+            let depth = read_u32(reader)?;
+            let value = if has_value(kind) {
+                read_value(reader)?
+            } else {
+                None
+            };
+            events.push(CapturedEvent {
+                kind,
+                point,
+                timestamp,
+                depth,
+                value,
+            });
+        }
+        capture.threads.push(CapturedThread { id, name, events });
+    }
+    Ok(capture)
+}
+
+fn kind_to_byte(kind: TraceEventKind) -> u8 {
+    match kind {
+        TraceEventKind::Enter => 0,
+        TraceEventKind::Leave => 1,
+        TraceEventKind::Marker => 2,
+        TraceEventKind::Counter => 3,
+        TraceEventKind::Argument => 4,
+    }
+}
+
+fn byte_to_kind(byte: u8) -> std::io::Result<TraceEventKind> {
+    match byte {
+        0 => Ok(TraceEventKind::Enter),
+        1 => Ok(TraceEventKind::Leave),
+        2 => Ok(TraceEventKind::Marker),
+        3 => Ok(TraceEventKind::Counter),
+        4 => Ok(TraceEventKind::Argument),
+        kind => Err(invalid_data(format!("invalid event kind {}", kind))),
+    }
+}
+
+// spans don't store the tag to keep the common events small
+fn has_value(kind: TraceEventKind) -> bool {
+    match kind {
+        TraceEventKind::Enter | TraceEventKind::Leave => false,
+        TraceEventKind::Marker | TraceEventKind::Counter | TraceEventKind::Argument => true,
+    }
+}
+
+fn write_value(writer: &mut dyn Write, value: Option<TraceValue>) -> std::io::Result<()> {
+    match value {
+        None => writer.write_all(&[0]),
+        Some(TraceValue::Integer(value)) => {
+            writer.write_all(&[1])?;
+            write_varint(writer, ((value << 1) ^ (value >> 63)) as u64)
+        }
+        Some(TraceValue::Float(value)) => {
+            writer.write_all(&[2])?;
+            writer.write_all(&value.to_bits().to_le_bytes())
+        }
+    }
+}
+
+fn read_value(reader: &mut dyn Read) -> std::io::Result<Option<TraceValue>> {
+    match read_byte(reader)? {
+        0 => Ok(None),
+        1 => {
+            let value = read_varint(reader)?;
+            Ok(Some(TraceValue::Integer(
+                (value >> 1) as i64 ^ -((value & 1) as i64),
+            )))
+        }
+        2 => {
+            let mut bits = [0; 8];
+            reader.read_exact(&mut bits)?;
+            Ok(Some(TraceValue::Float(f64::from_bits(u64::from_le_bytes(
+                bits,
+            )))))
+        }
+        tag => Err(invalid_data(format!("invalid value tag {}", tag))),
+    }
+}
+
+fn write_varint(writer: &mut dyn Write, mut value: u64) -> std::io::Result<()> {
+    let mut bytes = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes[len] = byte;
+            len += 1;
+            break;
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+    writer.write_all(&bytes[..len])
+}
+
+fn write_string(writer: &mut dyn Write, value: &str) -> std::io::Result<()> {
+    write_varint(writer, value.len() as u64)?;
+    writer.write_all(value.as_bytes())
+}
+
+fn read_byte(reader: &mut dyn Read) -> std::io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_varint(reader: &mut dyn Read) -> std::io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = read_byte(reader)?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("varint is too long"))
+}
+
+fn read_u32(reader: &mut dyn Read) -> std::io::Result<u32> {
+    let value = read_varint(reader)?;
+    if value > u64::from(u32::MAX) {
+        return Err(invalid_data(format!("{} doesn't fit u32", value)));
+    }
+    Ok(value as u32)
+}
+
+fn read_string(reader: &mut dyn Read) -> std::io::Result<String> {
+    let len = read_varint(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(invalid_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace_capture::tests::sample_capture;
+
+    #[test]
+    fn round_trip_test() {
+        let capture = sample_capture();
+        let mut buffer = Vec::new();
+        write_binary(&capture, &mut buffer).unwrap();
+        assert_eq!(read_binary(&mut buffer.as_slice()).unwrap(), capture);
+
+        // truncated and corrupted files are rejected
+        assert!(read_binary(&mut &buffer[..buffer.len() - 1]).is_err());
+        let mut corrupted = buffer.clone();
+        corrupted[0] = b'X';
+        assert!(read_binary(&mut corrupted.as_slice()).is_err());
+    }
+
+    #[test]
+    fn values_test() {
+        let mut capture = sample_capture();
+        let main = capture
+            .threads
+            .iter_mut()
+            .find(|thread| thread.name.as_deref() != Some("worker"))
+            .unwrap();
+        let mut argument = main.events[0];
+        argument.kind = TraceEventKind::Argument;
+        argument.depth = 1;
+        for value in &[i64::MIN, -1, 0, 1, i64::MAX] {
+            argument.value = Some(TraceValue::Integer(*value));
+            main.events.insert(1, argument);
+        }
+        argument.value = Some(TraceValue::Float(-0.25));
+        main.events.insert(1, argument);
+
+        let mut buffer = Vec::new();
+        write_binary(&capture, &mut buffer).unwrap();
+        assert_eq!(read_binary(&mut buffer.as_slice()).unwrap(), capture);
+    }
+
+    #[test]
+    fn version_1_test() {
+        // a thread with a single enter event
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(MAGIC);
+        buffer.extend_from_slice(&1u32.to_le_bytes());
+        buffer.extend_from_slice(&[1, 1, b'a', 1, b'b', 3]);
+        buffer.extend_from_slice(&[1, 5, 0, 1, 0, 0, 10, 0]);
+        let capture = read_binary(&mut buffer.as_slice()).unwrap();
+        assert_eq!(capture.points[0].name, "a");
+        assert_eq!(capture.threads[0].events[0].timestamp, 10);
+        assert_eq!(capture.threads[0].events[0].value, None);
+    }
+
+    #[test]
+    fn varint_test() {
+        for value in &[0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let mut buffer = Vec::new();
+            write_varint(&mut buffer, *value).unwrap();
+            assert_eq!(read_varint(&mut buffer.as_slice()).unwrap(), *value);
+        }
+        let mut buffer = Vec::new();
+        write_varint(&mut buffer, 300).unwrap();
+        assert_eq!(buffer, vec![0xac, 0x02]);
+    }
+}
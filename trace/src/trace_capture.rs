@@ -0,0 +1,362 @@
+use crate::trace_collector::{collect_events, ThreadEvents};
+use crate::{trace_binary, trace_chrome, TraceEventKind, TraceValue};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+// trace point of a capture, events refer to it by index
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CapturedPoint {
+    pub name: String,
+    pub file: String,
+    pub line: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CapturedEvent {
+    pub kind: TraceEventKind,
+    pub point: u32,
+    pub timestamp: u64,
+    pub depth: u32,
+    pub value: Option<TraceValue>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct CapturedThread {
+    pub id: u64,
+    pub name: Option<String>,
+    pub events: Vec<CapturedEvent>,
+}
+
+// owned copy of trace events, it can be saved, loaded and analyzed in another process
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TraceCapture {
+    pub points: Vec<CapturedPoint>,
+    pub threads: Vec<CapturedThread>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TraceFormat {
+    // Chrome Trace Event Format, loadable by chrome://tracing and Perfetto
+    ChromeJson,
+    // own compact format
+    Binary,
+}
+
+impl TraceFormat {
+    // `json` files are Chrome traces, everything else is the binary format
+    pub fn from_path(path: &Path) -> TraceFormat {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => TraceFormat::ChromeJson,
+            _ => TraceFormat::Binary,
+        }
+    }
+}
+
+impl TraceCapture {
+    pub fn new() -> TraceCapture {
+        TraceCapture::default()
+    }
+
+    // takes the events recorded since the previous collection
+    pub fn collect() -> TraceCapture {
+        let mut capture = TraceCapture::new();
+        capture.append(collect_events());
+        capture
+    }
+
+    pub fn append(&mut self, threads: Vec<ThreadEvents>) {
+        let mut indices = PointIndices::new(self);
+        for thread in threads {
+            let events: Vec<CapturedEvent> = thread
+                .events
+                .iter()
+                .map(|event| CapturedEvent {
+                    kind: event.kind,
+                    point: indices.get(self, event.name, event.file, event.line),
+                    timestamp: event.timestamp,
+                    depth: event.depth,
+                    value: event.value,
+                })
+                .collect();
+            match self
+                .threads
+                .iter_mut()
+                .find(|captured| captured.id == thread.thread_id)
+            {
+                Some(captured) => captured.events.extend(events),
+                None => self.threads.push(CapturedThread {
+                    id: thread.thread_id,
+                    name: thread.thread_name,
+                    events,
+                }),
+            }
+        }
+    }
+
+    // appends events of another capture, its points are remapped to the points of this one
+    pub fn merge(&mut self, other: TraceCapture) {
+        let mut indices = PointIndices::new(self);
+        let points: Vec<u32> = other
+            .points
+            .iter()
+            .map(|point| indices.get(self, &point.name, &point.file, point.line))
+            .collect();
+        for mut thread in other.threads {
+            for event in &mut thread.events {
+                event.point = points[event.point as usize];
+            }
+            match self
+                .threads
+                .iter_mut()
+                .find(|captured| captured.id == thread.id)
+            {
+                Some(captured) => {
+                    captured.events.extend(thread.events);
+                    if captured.name.is_none() {
+                        captured.name = thread.name;
+                    }
+                }
+                None => self.threads.push(thread),
+            }
+        }
+    }
+
+    pub fn get_point(&self, event: &CapturedEvent) -> &CapturedPoint {
+        &self.points[event.point as usize]
+    }
+
+    pub fn write(
+        &self,
+        writer: &mut dyn std::io::Write,
+        format: TraceFormat,
+    ) -> std::io::Result<()> {
+        match format {
+            TraceFormat::ChromeJson => trace_chrome::write_chrome_json(self, writer),
+            TraceFormat::Binary => trace_binary::write_binary(self, writer),
+        }
+    }
+
+    pub fn read(
+        reader: &mut dyn std::io::Read,
+        format: TraceFormat,
+    ) -> std::io::Result<TraceCapture> {
+        match format {
+            TraceFormat::ChromeJson => trace_chrome::read_chrome_json(reader),
+            TraceFormat::Binary => trace_binary::read_binary(reader),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer, TraceFormat::from_path(path))
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<TraceCapture> {
+        let mut reader = BufReader::new(File::open(path)?);
+        TraceCapture::read(&mut reader, TraceFormat::from_path(path))
+    }
+}
+
+// deduplicates points while a capture is being filled
+pub(crate) struct PointIndices {
+    indices: HashMap<CapturedPoint, u32>,
+}
+
+impl PointIndices {
+    pub fn new(capture: &TraceCapture) -> PointIndices {
+        let indices = capture
+            .points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| (point.clone(), i as u32))
+            .collect();
+        PointIndices { indices }
+    }
+
+    pub fn get(&mut self, capture: &mut TraceCapture, name: &str, file: &str, line: u32) -> u32 {
+        let point = CapturedPoint {
+            name: String::from(name),
+            file: String::from(file),
+            line,
+        };
+        let next_index = capture.points.len() as u32;
+        let index = *self.indices.entry(point.clone()).or_insert(next_index);
+        if index == next_index {
+            capture.points.push(point);
+        }
+        index
+    }
+}
+
+// keeps collected events in a file, the file is rewritten on every flush and when the guard is dropped,
+// so keeping it in `main` saves the trace on process exit
+pub struct TraceFile {
+    path: PathBuf,
+    capture: TraceCapture,
+}
+
+impl TraceFile {
+    pub fn new(path: &Path) -> TraceFile {
+        TraceFile {
+            path: path.to_path_buf(),
+            capture: TraceCapture::new(),
+        }
+    }
+
+    pub fn get_capture(&self) -> &TraceCapture {
+        &self.capture
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.capture.append(collect_events());
+        self.capture.save(&self.path)
+    }
+}
+
+impl Drop for TraceFile {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            log::error!("failed to save trace to {:?}: {}", self.path, error);
+        }
+    }
+}
+
+pub(crate) fn invalid_data<E: ToString>(error: E) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error.to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::*;
+
+    // two threads with nested spans, a marker and a counter, the second span of the main thread is left open
+    pub fn sample_capture() -> TraceCapture {
+        let _lock = trace_collector::lock_collector_for_test();
+        let worker = std::thread::Builder::new()
+            .name(String::from("worker"))
+            .spawn(|| {
+                trace_point!("worker \"job\"");
+            })
+            .unwrap();
+        worker.join().unwrap();
+        {
+            trace_point!("outer");
+            {
+                trace_point!("inner");
+            }
+            trace_marker!("tick");
+            trace_counter!("items", 2.5);
+        }
+        let _open = TracePoint::new({
+            static INFO: TracePointInfo = TracePointInfo {
+                name: "open",
+                file: "main.rs",
+                line: 7,
+            };
+            &INFO
+        });
+
+        let mut capture = TraceCapture::collect();
+        let thread_id = current_thread_id();
+        let worker_name = Some(String::from("worker"));
+        capture
+            .threads
+            .retain(|thread| thread.id == thread_id || thread.name == worker_name);
+        capture
+    }
+
+    #[test]
+    fn collect_test() {
+        let capture = sample_capture();
+        assert_eq!(capture.threads.len(), 2);
+        let main = capture
+            .threads
+            .iter()
+            .find(|thread| thread.name.as_deref() != Some("worker"))
+            .unwrap();
+        let names: Vec<&str> = main
+            .events
+            .iter()
+            .map(|event| capture.get_point(event).name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["outer", "inner", "inner", "tick", "items", "outer", "open"]
+        );
+        assert_eq!(main.events[1].depth, 1);
+        assert_eq!(main.events[4].value, Some(TraceValue::Float(2.5)));
+        assert_eq!(capture.get_point(&main.events[6]).line, 7);
+
+        let mut appended = capture.clone();
+        appended.append(vec![ThreadEvents {
+            thread_id: main.id,
+            thread_name: None,
+            events: Vec::new(),
+        }]);
+        assert_eq!(appended, capture);
+    }
+
+    #[test]
+    fn merge_test() {
+        let capture = sample_capture();
+        let (first, second) = capture.threads.split_at(1);
+        let mut merged = TraceCapture {
+            points: capture.points.clone(),
+            threads: first.to_vec(),
+        };
+        // the second part has its own point table in the reversed order
+        let mut other = TraceCapture {
+            points: capture.points.iter().rev().cloned().collect(),
+            threads: second.to_vec(),
+        };
+        let last = capture.points.len() as u32 - 1;
+        for event in &mut other.threads[0].events {
+            event.point = last - event.point;
+        }
+
+        merged.merge(other);
+        assert_eq!(merged, capture);
+    }
+
+    #[test]
+    fn trace_file_test() {
+        let path =
+            std::env::temp_dir().join(format!("trace_file_test_{}.json", std::process::id()));
+        {
+            let _lock = trace_collector::lock_collector_for_test();
+            let mut file = TraceFile::new(&path);
+            {
+                trace_point!("first");
+            }
+            file.flush().unwrap();
+            trace_point!("second");
+        }
+
+        // the file is rewritten with all collected events when the guard is dropped,
+        // the second span is closed before that
+        let capture = TraceCapture::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let names: Vec<&str> = capture
+            .threads
+            .iter()
+            .flat_map(|thread| thread.events.iter())
+            .map(|event| capture.get_point(event).name.as_str())
+            .collect();
+        assert_eq!(names, vec!["first", "first", "second", "second"]);
+    }
+
+    #[test]
+    fn format_from_path_test() {
+        assert_eq!(
+            TraceFormat::from_path(Path::new("capture.JSON")),
+            TraceFormat::ChromeJson
+        );
+        assert_eq!(
+            TraceFormat::from_path(Path::new("capture.trace")),
+            TraceFormat::Binary
+        );
+    }
+}
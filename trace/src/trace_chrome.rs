@@ -0,0 +1,454 @@
+use crate::trace_capture::{
+    invalid_data, CapturedEvent, CapturedThread, PointIndices, TraceCapture,
+};
+use crate::{TraceEventKind, TraceValue};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+// one event of the Chrome Trace Event Format, timestamps are in microseconds
+#[derive(Serialize, Deserialize)]
+struct ChromeEvent {
+    #[serde(default)]
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    cat: String,
+    ph: String,
+    #[serde(default)]
+    ts: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    #[serde(default)]
+    pid: u64,
+    #[serde(default)]
+    tid: u64,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    args: Value,
+    // scope of instant events
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    s: String,
+    // call site of counters, their args are plotted, so it can't be kept there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    line: Option<u32>,
+}
+
+// both the object and the bare array forms are valid traces
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChromeTrace {
+    Object {
+        #[serde(rename = "traceEvents")]
+        trace_events: Vec<ChromeEvent>,
+    },
+    Array(Vec<ChromeEvent>),
+}
+
+fn to_microseconds(timestamp: u64) -> f64 {
+    timestamp as f64 / 1000.
+}
+
+fn to_nanoseconds(microseconds: f64) -> u64 {
+    (microseconds * 1000.).round().max(0.) as u64
+}
+
+fn to_json(value: TraceValue) -> Value {
+    match value {
+        TraceValue::Integer(value) => json!(value),
+        TraceValue::Float(value) => json!(value),
+    }
+}
+
+fn from_json(value: &Value) -> Option<TraceValue> {
+    value
+        .as_i64()
+        .map(TraceValue::Integer)
+        .or_else(|| value.as_f64().map(TraceValue::Float))
+}
+
+// spans are written as begin and end events, markers as instant events, counters as counter events
+// and thread names as metadata events; span arguments are written with end events,
+// Chrome merges them into the arguments of spans, arguments outside of spans are skipped
+pub fn write_chrome_json(
+    capture: &TraceCapture,
+    writer: &mut dyn std::io::Write,
+) -> std::io::Result<()> {
+    let pid = u64::from(std::process::id());
+    let mut events = Vec::new();
+    for thread in &capture.threads {
+        if let Some(name) = &thread.name {
+            events.push(ChromeEvent {
+                name: String::from("thread_name"),
+                cat: String::new(),
+                ph: String::from("M"),
+                ts: 0.,
+                dur: None,
+                pid,
+                tid: thread.id,
+                args: json!({ "name": name }),
+                s: String::new(),
+                file: None,
+                line: None,
+            });
+        }
+
+        let mut open_arguments: Vec<Map<String, Value>> = Vec::new();
+        for event in &thread.events {
+            let point = capture.get_point(event);
+            let mut chrome_event = ChromeEvent {
+                name: point.name.clone(),
+                cat: String::from("trace"),
+                ph: String::new(),
+                ts: to_microseconds(event.timestamp),
+                dur: None,
+                pid,
+                tid: thread.id,
+                args: json!({ "file": point.file, "line": point.line }),
+                s: String::new(),
+                file: None,
+                line: None,
+            };
+            match event.kind {
+                TraceEventKind::Enter => {
+                    chrome_event.ph = String::from("B");
+                    open_arguments.push(Map::new());
+                }
+                TraceEventKind::Leave => {
+                    chrome_event.ph = String::from("E");
+                    for (key, value) in open_arguments.pop().unwrap_or_default() {
+                        chrome_event.args[key] = value;
+                    }
+                }
+                TraceEventKind::Marker => {
+                    chrome_event.ph = String::from("i");
+                    chrome_event.s = String::from("t");
+                }
+                TraceEventKind::Counter => {
+                    chrome_event.ph = String::from("C");
+                    chrome_event.args = json!({ "value": event.value.map(to_json) });
+                    chrome_event.file = Some(point.file.clone());
+                    chrome_event.line = Some(point.line);
+                }
+                TraceEventKind::Argument => {
+                    if let (Some(arguments), Some(value)) = (open_arguments.last_mut(), event.value)
+                    {
+                        arguments.insert(point.name.clone(), to_json(value));
+                    }
+                    continue;
+                }
+            }
+            events.push(chrome_event);
+        }
+    }
+
+    let trace = json!({ "traceEvents": events, "displayTimeUnit": "ns" });
+    serde_json::to_writer(&mut *writer, &trace).map_err(invalid_data)?;
+    writer.flush()
+}
+
+// span opened by a begin event or a complete event, complete events know their end in advance
+#[derive(Clone, Copy)]
+struct OpenSpan {
+    point: u32,
+    end: Option<u64>,
+}
+
+// leave events of complete events are emitted once the following events of the thread pass their ends,
+// so events stay in the order of timestamps and nested complete events get their depths
+fn close_ended_spans(stack: &mut Vec<OpenSpan>, events: &mut Vec<CapturedEvent>, timestamp: u64) {
+    while let Some(OpenSpan {
+        point,
+        end: Some(end),
+    }) = stack.last().copied()
+    {
+        if end > timestamp {
+            break;
+        }
+        stack.pop();
+        events.push(CapturedEvent {
+            kind: TraceEventKind::Leave,
+            point,
+            timestamp: end,
+            depth: stack.len() as u32,
+            value: None,
+        });
+    }
+}
+
+// reads begin, end, complete, instant and counter events and thread names, other events are skipped;
+// an end event closes the innermost open span of its thread, its arguments are attached to the span
+// and don't have call sites
+pub fn read_chrome_json(reader: &mut dyn std::io::Read) -> std::io::Result<TraceCapture> {
+    let events = match serde_json::from_reader(reader).map_err(invalid_data)? {
+        ChromeTrace::Object { trace_events } => trace_events,
+        ChromeTrace::Array(events) => events,
+    };
+
+    let mut capture = TraceCapture::new();
+    let mut indices = PointIndices::new(&capture);
+    let mut open_spans: HashMap<u64, Vec<OpenSpan>> = HashMap::new();
+    for event in events {
+        let thread = match capture
+            .threads
+            .iter()
+            .position(|thread| thread.id == event.tid)
+        {
+            Some(position) => position,
+            None => {
+                capture.threads.push(CapturedThread {
+                    id: event.tid,
+                    name: None,
+                    events: Vec::new(),
+                });
+                capture.threads.len() - 1
+            }
+        };
+        let stack = open_spans.entry(event.tid).or_default();
+        let timestamp = to_nanoseconds(event.ts);
+        if matches!(event.ph.as_str(), "B" | "X" | "i" | "I" | "C" | "E") {
+            close_ended_spans(stack, &mut capture.threads[thread].events, timestamp);
+        }
+
+        match event.ph.as_str() {
+            "M" if event.name == "thread_name" => {
+                capture.threads[thread].name = event.args["name"].as_str().map(String::from);
+            }
+            "B" | "X" => {
+                let file = event.args["file"].as_str().unwrap_or_default();
+                let line = event.args["line"].as_u64().unwrap_or(0) as u32;
+                let point = indices.get(&mut capture, &event.name, file, line);
+                capture.threads[thread].events.push(CapturedEvent {
+                    kind: TraceEventKind::Enter,
+                    point,
+                    timestamp,
+                    depth: stack.len() as u32,
+                    value: None,
+                });
+                // a complete event without a duration is an empty span
+                let end = match event.ph.as_str() {
+                    "X" => Some(to_nanoseconds(event.ts + event.dur.unwrap_or(0.))),
+                    _ => None,
+                };
+                stack.push(OpenSpan { point, end });
+            }
+            "i" | "I" => {
+                let file = event.args["file"].as_str().unwrap_or_default();
+                let line = event.args["line"].as_u64().unwrap_or(0) as u32;
+                let point = indices.get(&mut capture, &event.name, file, line);
+                capture.threads[thread].events.push(CapturedEvent {
+                    kind: TraceEventKind::Marker,
+                    point,
+                    timestamp,
+                    depth: stack.len() as u32,
+                    value: None,
+                });
+            }
+            "C" => {
+                let file = event.file.as_deref().unwrap_or_default();
+                let line = event.line.unwrap_or(0);
+                for (key, value) in event.args.as_object().into_iter().flatten() {
+                    let value = match from_json(value) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+                    // every series of a counter event is a counter
+                    let name = if key == "value" {
+                        event.name.clone()
+                    } else {
+                        format!("{} {}", event.name, key)
+                    };
+                    let point = indices.get(&mut capture, &name, file, line);
+                    capture.threads[thread].events.push(CapturedEvent {
+                        kind: TraceEventKind::Counter,
+                        point,
+                        timestamp,
+                        depth: stack.len() as u32,
+                        value: Some(value),
+                    });
+                }
+            }
+            "E" => {
+                if stack.is_empty() {
+                    return Err(invalid_data(format!("unmatched end event at {}", event.ts)));
+                }
+                for (key, value) in event.args.as_object().into_iter().flatten() {
+                    if key == "file" || key == "line" {
+                        continue;
+                    }
+                    if let Some(value) = from_json(value) {
+                        let point = indices.get(&mut capture, key, "", 0);
+                        capture.threads[thread].events.push(CapturedEvent {
+                            kind: TraceEventKind::Argument,
+                            point,
+                            timestamp,
+                            depth: stack.len() as u32,
+                            value: Some(value),
+                        });
+                    }
+                }
+
+                let point = stack.pop().expect("the stack is checked above").point;
+                let depth = stack.len() as u32;
+                capture.threads[thread].events.push(CapturedEvent {
+                    kind: TraceEventKind::Leave,
+                    point,
+                    timestamp,
+                    depth,
+                    value: None,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    for thread in &mut capture.threads {
+        if let Some(stack) = open_spans.get_mut(&thread.id) {
+            close_ended_spans(stack, &mut thread.events, u64::MAX);
+        }
+    }
+    capture
+        .threads
+        .retain(|thread| !thread.events.is_empty() || thread.name.is_some());
+    Ok(capture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace_capture::tests::sample_capture;
+    use crate::CapturedPoint;
+
+    #[test]
+    fn round_trip_test() {
+        let capture = sample_capture();
+        let mut buffer = Vec::new();
+        write_chrome_json(&capture, &mut buffer).unwrap();
+
+        let json: Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(json["traceEvents"][0]["ph"], "M");
+        assert_eq!(json["traceEvents"][0]["args"]["name"], "worker");
+        assert_eq!(json["traceEvents"][1]["name"], "worker \"job\"");
+
+        let loaded = read_chrome_json(&mut buffer.as_slice()).unwrap();
+        assert_eq!(loaded, capture);
+    }
+
+    #[test]
+    fn arguments_test() {
+        let point = |name: &str| CapturedPoint {
+            name: String::from(name),
+            file: String::from("main.rs"),
+            line: 1,
+        };
+        let event = |kind, point, timestamp, depth, value| CapturedEvent {
+            kind,
+            point,
+            timestamp,
+            depth,
+            value,
+        };
+        use TraceEventKind::*;
+        let capture = TraceCapture {
+            points: vec![point("frame"), point("index")],
+            threads: vec![CapturedThread {
+                id: 1,
+                name: None,
+                events: vec![
+                    event(Enter, 0, 1000, 0, None),
+                    event(Argument, 1, 1000, 1, Some(TraceValue::Integer(3))),
+                    event(Leave, 0, 2000, 0, None),
+                    // arguments outside of spans are lost
+                    event(Argument, 1, 3000, 0, Some(TraceValue::Float(0.5))),
+                ],
+            }],
+        };
+        let mut buffer = Vec::new();
+        write_chrome_json(&capture, &mut buffer).unwrap();
+        let json: Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(json["traceEvents"][1]["args"]["index"], 3);
+
+        // the argument is moved to the end of the span and loses its call site
+        let loaded = read_chrome_json(&mut buffer.as_slice()).unwrap();
+        let events = &loaded.threads[0].events;
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1].kind, Argument);
+        assert_eq!(events[1].value, Some(TraceValue::Integer(3)));
+        assert_eq!(events[1].timestamp, 2000);
+        assert_eq!(loaded.get_point(&events[1]).name, "index");
+        assert_eq!(loaded.get_point(&events[1]).line, 0);
+    }
+
+    #[test]
+    fn foreign_trace_test() {
+        let text = r#"[
+            { "name": "frame", "ph": "X", "ts": 1.5, "dur": 2, "pid": 1, "tid": 3 },
+            { "name": "counter", "ph": "C", "ts": 2, "pid": 1, "tid": 3, "args": { "cats": 1, "dogs": 2.5 } },
+            { "name": "sample", "ph": "P", "ts": 3, "pid": 1, "tid": 3 },
+            { "name": "update", "ph": "B", "ts": 4, "pid": 1, "tid": 3 },
+            { "ph": "E", "ts": 5, "pid": 1, "tid": 3 }
+        ]"#;
+        let capture = read_chrome_json(&mut text.as_bytes()).unwrap();
+        assert_eq!(capture.threads.len(), 1);
+        let events = &capture.threads[0].events;
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| event.timestamp)
+                .collect::<Vec<_>>(),
+            vec![1500, 2000, 2000, 3500, 4000, 5000]
+        );
+        assert_eq!(capture.get_point(&events[1]).name, "counter cats");
+        assert_eq!(events[1].value, Some(TraceValue::Integer(1)));
+        assert_eq!(events[1].depth, 1);
+        assert_eq!(capture.get_point(&events[2]).name, "counter dogs");
+        assert_eq!(events[2].value, Some(TraceValue::Float(2.5)));
+        assert_eq!(events[3].kind, TraceEventKind::Leave);
+        assert_eq!(capture.get_point(&events[5]).name, "update");
+
+        assert!(read_chrome_json(&mut r#"[{ "ph": "E", "ts": 1 }]"#.as_bytes()).is_err());
+        assert!(read_chrome_json(&mut "{".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn nested_complete_events_test() {
+        let text = r#"[
+            { "name": "frame", "ph": "X", "ts": 0, "dur": 10, "tid": 1 },
+            { "name": "update", "ph": "X", "ts": 1, "dur": 4, "tid": 1 },
+            { "name": "physics", "ph": "X", "ts": 2, "dur": 1, "tid": 1 },
+            { "name": "render", "ph": "X", "ts": 6, "dur": 4, "tid": 1 },
+            { "name": "empty", "ph": "X", "ts": 7, "tid": 1 }
+        ]"#;
+        let capture = read_chrome_json(&mut text.as_bytes()).unwrap();
+        let summary: Vec<(TraceEventKind, &str, u64, u32)> = capture.threads[0]
+            .events
+            .iter()
+            .map(|event| {
+                let name = capture.get_point(event).name.as_str();
+                (event.kind, name, event.timestamp / 1000, event.depth)
+            })
+            .collect();
+        use TraceEventKind::*;
+        assert_eq!(
+            summary,
+            vec![
+                (Enter, "frame", 0, 0),
+                (Enter, "update", 1, 1),
+                (Enter, "physics", 2, 2),
+                (Leave, "physics", 3, 2),
+                (Leave, "update", 5, 1),
+                (Enter, "render", 6, 1),
+                (Enter, "empty", 7, 2),
+                (Leave, "empty", 7, 2),
+                (Leave, "render", 10, 1),
+                (Leave, "frame", 10, 0),
+            ]
+        );
+
+        // timestamps are monotonic, so the capture can be saved in the binary format
+        let mut buffer = Vec::new();
+        crate::trace_binary::write_binary(&capture, &mut buffer).unwrap();
+        let loaded = crate::trace_binary::read_binary(&mut buffer.as_slice()).unwrap();
+        assert_eq!(loaded, capture);
+    }
+}
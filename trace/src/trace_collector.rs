@@ -0,0 +1,325 @@
+use crate::{TraceEvent, TraceEventKind, TracePointInfo, TraceValue};
+use lazy_static::lazy_static;
+use std::cell::{Cell, UnsafeCell};
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+const CHUNK_CAPACITY: usize = 4096;
+
+lazy_static! {
+    static ref EPOCH: Instant = Instant::now();
+    static ref THREAD_BUFFERS: Mutex<Vec<Arc<ThreadBuffer>>> = Mutex::new(Vec::new());
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static THREAD_ID_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static WRITER: ThreadWriter = ThreadWriter::new();
+}
+
+// events recorded by a single thread, in the recording order
+#[derive(Debug)]
+pub struct ThreadEvents {
+    pub thread_id: u64,
+    pub thread_name: Option<String>,
+    pub events: Vec<TraceEvent>,
+}
+
+// fixed size block of events, the owning thread appends events and publishes them by `len`,
+// the collector reads published events and frees the block once the thread moved to the next one
+struct Chunk {
+    events: Box<[UnsafeCell<MaybeUninit<TraceEvent>>]>,
+    len: AtomicUsize,
+    next: AtomicPtr<Chunk>,
+}
+
+impl Chunk {
+    fn allocate() -> *mut Chunk {
+        let events = (0..CHUNK_CAPACITY)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect();
+        Box::into_raw(Box::new(Chunk {
+            events,
+            len: AtomicUsize::new(0),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+struct ReadCursor {
+    chunk: *mut Chunk,
+    position: usize,
+}
+
+// the chunk pointer is owned by the cursor, the writer never touches chunks behind it
+unsafe impl Send for ReadCursor {}
+
+// single producer single consumer list of chunks
+struct ThreadBuffer {
+    thread_id: u64,
+    thread_name: Option<String>,
+    reader: Mutex<ReadCursor>,
+}
+
+impl ThreadBuffer {
+    fn drain(&self, events: &mut Vec<TraceEvent>) {
+        let mut reader = self.reader.lock().expect("trace collector panicked");
+        loop {
+            let chunk = unsafe { &*reader.chunk };
+            let len = chunk.len.load(Ordering::Acquire);
+            for i in reader.position..len {
+                events.push(unsafe { (*chunk.events[i].get()).as_ptr().read() });
+            }
+            reader.position = len;
+            if len < CHUNK_CAPACITY {
+                return;
+            }
+
+            // the next pointer is set after the last write to a full chunk
+            let next = chunk.next.load(Ordering::Acquire);
+            if next.is_null() {
+                return;
+            }
+            unsafe { drop(Box::from_raw(reader.chunk)) };
+            reader.chunk = next;
+            reader.position = 0;
+        }
+    }
+}
+
+impl Drop for ThreadBuffer {
+    fn drop(&mut self) {
+        // events are `Copy`, only chunks have to be freed
+        let mut chunk = self
+            .reader
+            .get_mut()
+            .map(|reader| reader.chunk)
+            .unwrap_or_else(|e| e.into_inner().chunk);
+        while !chunk.is_null() {
+            let next = unsafe { (*chunk).next.load(Ordering::Acquire) };
+            unsafe { drop(Box::from_raw(chunk)) };
+            chunk = next;
+        }
+    }
+}
+
+struct ThreadWriter {
+    buffer: Arc<ThreadBuffer>,
+    chunk: Cell<*mut Chunk>,
+    depth: Cell<u32>,
+}
+
+impl ThreadWriter {
+    fn new() -> ThreadWriter {
+        let chunk = Chunk::allocate();
+        let buffer = Arc::new(ThreadBuffer {
+            thread_id: THREAD_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
+            thread_name: std::thread::current().name().map(String::from),
+            reader: Mutex::new(ReadCursor { chunk, position: 0 }),
+        });
+        THREAD_BUFFERS
+            .lock()
+            .expect("trace collector panicked")
+            .push(buffer.clone());
+        ThreadWriter {
+            buffer,
+            chunk: Cell::new(chunk),
+            depth: Cell::new(0),
+        }
+    }
+
+    fn push(&self, event: TraceEvent) {
+        let mut chunk = unsafe { &*self.chunk.get() };
+        // only this thread changes the length
+        let mut len = chunk.len.load(Ordering::Relaxed);
+        if len == CHUNK_CAPACITY {
+            let next = Chunk::allocate();
+            chunk.next.store(next, Ordering::Release);
+            self.chunk.set(next);
+            chunk = unsafe { &*next };
+            len = 0;
+        }
+        unsafe { (*chunk.events[len].get()).as_mut_ptr().write(event) };
+        chunk.len.store(len + 1, Ordering::Release);
+    }
+}
+
+pub fn is_tracing_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// trace points created while tracing is disabled record nothing, including their leave events
+pub fn set_tracing_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+// nanoseconds since the epoch of the trace
+pub fn get_timestamp() -> u64 {
+    EPOCH.elapsed().as_nanos() as u64
+}
+
+// id of the calling thread in trace events, ids are never reused
+pub fn current_thread_id() -> u64 {
+    WRITER.with(|writer| writer.buffer.thread_id)
+}
+
+// records an event on the calling thread, enter and leave events open and close spans;
+// returns false if the thread is being destroyed and can't record anything
+pub(crate) fn record(
+    kind: TraceEventKind,
+    info: &'static TracePointInfo,
+    value: Option<TraceValue>,
+) -> bool {
+    WRITER
+        .try_with(|writer| {
+            let open_spans = writer.depth.get();
+            let (depth, open_spans) = match kind {
+                TraceEventKind::Enter => (open_spans, open_spans + 1),
+                TraceEventKind::Leave => {
+                    let depth = open_spans.saturating_sub(1);
+                    (depth, depth)
+                }
+                _ => (open_spans, open_spans),
+            };
+            let event = TraceEvent::new(
+                kind,
+                info,
+                value,
+                writer.buffer.thread_id,
+                get_timestamp(),
+                depth,
+            );
+            writer.push(event);
+            writer.depth.set(open_spans);
+        })
+        .is_ok()
+}
+
+// takes the events recorded since the previous call, threads are in the order of their first event
+pub fn collect_events() -> Vec<ThreadEvents> {
+    let mut buffers = THREAD_BUFFERS.lock().expect("trace collector panicked");
+    let mut result = Vec::new();
+    buffers.retain(|buffer| {
+        // the writer of a finished thread is dropped, so its buffer is drained completely
+        let finished = Arc::strong_count(buffer) == 1;
+        let mut events = Vec::new();
+        buffer.drain(&mut events);
+        if !events.is_empty() {
+            result.push(ThreadEvents {
+                thread_id: buffer.thread_id,
+                thread_name: buffer.thread_name.clone(),
+                events,
+            });
+        }
+        !finished
+    });
+    result
+}
+
+// tests of the crate share the global collector, they take this lock to not steal events of each other
+#[cfg(test)]
+pub(crate) fn lock_collector_for_test() -> std::sync::MutexGuard<'static, ()> {
+    lazy_static! {
+        static ref TEST_LOCK: Mutex<()> = Mutex::new(());
+    }
+    let guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    collect_events();
+    guard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static INFO: TracePointInfo = TracePointInfo {
+        name: "test",
+        file: "test.rs",
+        line: 1,
+    };
+
+    fn events_of_current_thread() -> Vec<TraceEvent> {
+        let thread_id = current_thread_id();
+        collect_events()
+            .into_iter()
+            .filter(|thread| thread.thread_id == thread_id)
+            .flat_map(|thread| thread.events)
+            .collect()
+    }
+
+    #[test]
+    fn chunks_test() {
+        let _lock = lock_collector_for_test();
+        let count = CHUNK_CAPACITY * 2 + 10;
+        for i in 0..count {
+            record(
+                if i % 2 == 0 {
+                    TraceEventKind::Enter
+                } else {
+                    TraceEventKind::Leave
+                },
+                &INFO,
+                None,
+            );
+            // drain in the middle of a chunk and exactly at its end
+            if i == 10 || i == CHUNK_CAPACITY - 1 {
+                assert_eq!(
+                    events_of_current_thread().len(),
+                    if i == 10 { 11 } else { CHUNK_CAPACITY - 11 }
+                );
+            }
+        }
+        let events = events_of_current_thread();
+        assert_eq!(events.len(), count - CHUNK_CAPACITY);
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp && pair[0].kind != pair[1].kind));
+        assert!(events.iter().all(|event| event.depth == 0));
+        assert!(events_of_current_thread().is_empty());
+    }
+
+    #[test]
+    fn threads_test() {
+        let _lock = lock_collector_for_test();
+        let handle = std::thread::Builder::new()
+            .name(String::from("traced"))
+            .spawn(|| {
+                for _ in 0..3 {
+                    record(TraceEventKind::Enter, &INFO, None);
+                }
+                current_thread_id()
+            })
+            .unwrap();
+        let thread_id = handle.join().unwrap();
+        assert_ne!(thread_id, current_thread_id());
+
+        let threads = collect_events();
+        let thread = threads
+            .iter()
+            .find(|thread| thread.thread_id == thread_id)
+            .unwrap();
+        assert_eq!(thread.thread_name.as_deref(), Some("traced"));
+        assert_eq!(
+            thread
+                .events
+                .iter()
+                .map(|event| event.depth)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(thread
+            .events
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+        // the buffer of the finished thread is released after draining
+        assert!(THREAD_BUFFERS
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|buffer| buffer.thread_id != thread_id));
+    }
+}
@@ -0,0 +1,107 @@
+// static description of a trace point, one instance per call site
+#[derive(Debug)]
+pub struct TracePointInfo {
+    pub name: &'static str,
+    pub file: &'static str,
+    pub line: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TraceEventKind {
+    Enter,
+    Leave,
+    // instant event without a duration
+    Marker,
+    // new value of the counter named by the trace point
+    Counter,
+    // key and value attached to the innermost open span of the thread
+    Argument,
+}
+
+// value of counters and span arguments, integers are kept exact
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TraceValue {
+    Integer(i64),
+    Float(f64),
+}
+
+impl TraceValue {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            TraceValue::Integer(value) => value as f64,
+            TraceValue::Float(value) => value,
+        }
+    }
+}
+
+impl std::fmt::Display for TraceValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TraceValue::Integer(value) => write!(f, "{}", value),
+            TraceValue::Float(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+macro_rules! impl_integer_trace_value {
+    ($($t: ty),*) => {
+        $(impl From<$t> for TraceValue {
+            fn from(value: $t) -> Self {
+                TraceValue::Integer(value as i64)
+            }
+        })*
+    };
+}
+
+// `u64` and `usize` values above `i64::MAX` wrap
+impl_integer_trace_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl From<f32> for TraceValue {
+    fn from(value: f32) -> Self {
+        TraceValue::Float(f64::from(value))
+    }
+}
+
+impl From<f64> for TraceValue {
+    fn from(value: f64) -> Self {
+        TraceValue::Float(value)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TraceEvent {
+    pub kind: TraceEventKind,
+    pub thread_id: u64,
+    // nanoseconds since the first event of the process, monotonic
+    pub timestamp: u64,
+    pub name: &'static str,
+    pub file: &'static str,
+    pub line: u32,
+    // count of spans opened on the thread before this one, enter and leave of a span have the same depth,
+    // markers, counters and arguments have the depth of the spans opened around them
+    pub depth: u32,
+    // set for counters and arguments
+    pub value: Option<TraceValue>,
+}
+
+impl TraceEvent {
+    pub fn new(
+        kind: TraceEventKind,
+        info: &'static TracePointInfo,
+        value: Option<TraceValue>,
+        thread_id: u64,
+        timestamp: u64,
+        depth: u32,
+    ) -> Self {
+        TraceEvent {
+            kind,
+            thread_id,
+            timestamp,
+            name: info.name,
+            file: info.file,
+            line: info.line,
+            depth,
+            value,
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::trace_capture::TraceCapture;
+use crate::trace_collector::{is_tracing_enabled, record};
+use crate::{TraceEventKind, TracePointInfo, TraceValue};
+
+// the functions are called by the macros below
+
+pub fn record_marker(info: &'static TracePointInfo) {
+    if is_tracing_enabled() {
+        record(TraceEventKind::Marker, info, None);
+    }
+}
+
+pub fn record_counter(info: &'static TracePointInfo, value: TraceValue) {
+    if is_tracing_enabled() {
+        record(TraceEventKind::Counter, info, Some(value));
+    }
+}
+
+pub fn record_argument(info: &'static TracePointInfo, value: TraceValue) {
+    if is_tracing_enabled() {
+        record(TraceEventKind::Argument, info, Some(value));
+    }
+}
+
+// records an instant event, like the start of a frame
+#[macro_export]
+macro_rules! trace_marker {
+    ($name: expr) => {{
+        static INFO: $crate::TracePointInfo = $crate::TracePointInfo {
+            name: $name,
+            file: std::file!(),
+            line: std::line!(),
+        };
+        $crate::record_marker(&INFO);
+    }};
+}
+
+// records a new value of a counter, any integer or float value
+#[macro_export]
+macro_rules! trace_counter {
+    ($name: expr, $value: expr) => {{
+        static INFO: $crate::TracePointInfo = $crate::TracePointInfo {
+            name: $name,
+            file: std::file!(),
+            line: std::line!(),
+        };
+        $crate::record_counter(&INFO, $crate::TraceValue::from($value));
+    }};
+}
+
+// attaches a value to the innermost open span of the thread,
+// it is attached to the outer span if the innermost one was created while tracing was disabled
+#[macro_export]
+macro_rules! trace_argument {
+    ($key: expr, $value: expr) => {{
+        static INFO: $crate::TracePointInfo = $crate::TracePointInfo {
+            name: $key,
+            file: std::file!(),
+            line: std::line!(),
+        };
+        $crate::record_argument(&INFO, $crate::TraceValue::from($value));
+    }};
+}
+
+// values of a counter of all threads in the order of timestamps
+#[derive(Clone, PartialEq, Debug)]
+pub struct CapturedCounter {
+    pub name: String,
+    pub values: Vec<(u64, TraceValue)>,
+}
+
+impl TraceCapture {
+    // counters with the same name are one counter, like in the Chrome Trace Event Format
+    pub fn get_counters(&self) -> Vec<CapturedCounter> {
+        let mut counters: Vec<CapturedCounter> = Vec::new();
+        for thread in &self.threads {
+            for event in &thread.events {
+                let value = match (event.kind, event.value) {
+                    (TraceEventKind::Counter, Some(value)) => value,
+                    _ => continue,
+                };
+                let name = &self.get_point(event).name;
+                let index = match counters.iter().position(|counter| &counter.name == name) {
+                    Some(index) => index,
+                    None => {
+                        counters.push(CapturedCounter {
+                            name: name.clone(),
+                            values: Vec::new(),
+                        });
+                        counters.len() - 1
+                    }
+                };
+                counters[index].values.push((event.timestamp, value));
+            }
+        }
+        for counter in &mut counters {
+            counter.values.sort_by_key(|(timestamp, _)| *timestamp);
+        }
+        counters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn marker_test() {
+        let _lock = trace_collector::lock_collector_for_test();
+        {
+            trace_point!("frame", index = 3, scale = 0.5);
+            trace_marker!("frame start");
+            {
+                trace_point!("evaluate");
+                trace_counter!("nodes evaluated", 42usize);
+            }
+            trace_argument!("late", -1);
+        }
+        set_tracing_enabled(false);
+        trace_marker!("skipped");
+        set_tracing_enabled(true);
+
+        let thread_id = current_thread_id();
+        let events: Vec<TraceEvent> = collect_events()
+            .into_iter()
+            .filter(|thread| thread.thread_id == thread_id)
+            .flat_map(|thread| thread.events)
+            .collect();
+        let summary: Vec<(TraceEventKind, &str, u32, Option<TraceValue>)> = events
+            .iter()
+            .map(|event| (event.kind, event.name, event.depth, event.value))
+            .collect();
+        use TraceEventKind::*;
+        assert_eq!(
+            summary,
+            vec![
+                (Enter, "frame", 0, None),
+                (Argument, "index", 1, Some(TraceValue::Integer(3))),
+                (Argument, "scale", 1, Some(TraceValue::Float(0.5))),
+                (Marker, "frame start", 1, None),
+                (Enter, "evaluate", 1, None),
+                (Counter, "nodes evaluated", 2, Some(TraceValue::Integer(42))),
+                (Leave, "evaluate", 1, None),
+                (Argument, "late", 1, Some(TraceValue::Integer(-1))),
+                (Leave, "frame", 0, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn counters_test() {
+        let capture = TraceCapture {
+            points: vec![CapturedPoint {
+                name: String::from("nodes"),
+                file: String::from("main.rs"),
+                line: 1,
+            }],
+            threads: vec![
+                CapturedThread {
+                    id: 1,
+                    name: None,
+                    events: vec![CapturedEvent {
+                        kind: TraceEventKind::Counter,
+                        point: 0,
+                        timestamp: 20,
+                        depth: 0,
+                        value: Some(TraceValue::Integer(2)),
+                    }],
+                },
+                CapturedThread {
+                    id: 2,
+                    name: None,
+                    events: vec![CapturedEvent {
+                        kind: TraceEventKind::Counter,
+                        point: 0,
+                        timestamp: 10,
+                        depth: 0,
+                        value: Some(TraceValue::Float(1.5)),
+                    }],
+                },
+            ],
+        };
+        assert_eq!(
+            capture.get_counters(),
+            vec![CapturedCounter {
+                name: String::from("nodes"),
+                values: vec![(10, TraceValue::Float(1.5)), (20, TraceValue::Integer(2))],
+            }]
+        );
+    }
+}
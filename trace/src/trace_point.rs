@@ -1,50 +1,160 @@
+use crate::trace_collector::{is_tracing_enabled, record};
+use crate::{TraceEventKind, TracePointInfo};
+
+// records an enter event on creation and the matching leave event on drop
 pub struct TracePoint {
-    pub exit_message: &'static str,
+    info: Option<&'static TracePointInfo>,
 }
 
 impl TracePoint {
-    pub fn new(enter_message: &'static str, exit_message: &'static str) -> Self {
-        println!("{}", enter_message);
-        TracePoint {
-            exit_message: exit_message,
+    pub fn new(info: &'static TracePointInfo) -> Self {
+        if is_tracing_enabled() && record(TraceEventKind::Enter, info, None) {
+            TracePoint { info: Some(info) }
+        } else {
+            TracePoint { info: None }
         }
     }
+
+    // false if the span wasn't recorded, its arguments are skipped then
+    pub fn is_recording(&self) -> bool {
+        self.info.is_some()
+    }
 }
 
 impl Drop for TracePoint {
     fn drop(&mut self) {
-        println!("{}", self.exit_message);
+        if let Some(info) = self.info {
+            record(TraceEventKind::Leave, info, None);
+        }
     }
 }
 
+// traces the rest of the enclosing block, `trace_point!("evaluate", node = id)` attaches arguments to the span
 #[macro_export]
 macro_rules! trace_point {
-    ($function_name: expr, $trait_name: expr, $struct_name: expr) => {
-        let __trace_point = TracePoint::new(
-            concat!(
-                "ENTER: ",
-                std::file!(),
-                ":",
-                std::line!(),
-                ", ",
-                $function_name,
-                ", ",
-                $trait_name,
-                ", ",
-                struct_name,
-            ),
-            concat!(
-                "LEAVE: ",
-                std::file!(),
-                ":",
-                std::line!(),
-                ", ",
-                $function_name,
-                ", ",
-                $trait_name,
-                ", ",
-                struct_name,
-            ),
-        );
+    ($name: expr) => {
+        let __trace_point = {
+            static INFO: $crate::TracePointInfo = $crate::TracePointInfo {
+                name: $name,
+                file: std::file!(),
+                line: std::line!(),
+            };
+            $crate::TracePoint::new(&INFO)
+        };
+    };
+    ($name: expr, $($key: ident = $value: expr),+ $(,)?) => {
+        let __trace_point = {
+            static INFO: $crate::TracePointInfo = $crate::TracePointInfo {
+                name: $name,
+                file: std::file!(),
+                line: std::line!(),
+            };
+            $crate::TracePoint::new(&INFO)
+        };
+        if __trace_point.is_recording() {
+            $($crate::trace_argument!(std::stringify!($key), $value);)+
+        }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn traced_function(depth: u32) {
+        trace_point!("traced_function");
+        if depth > 0 {
+            traced_function(depth - 1);
+        }
+    }
+
+    #[test]
+    fn trace_point_test() {
+        let _lock = trace_collector::lock_collector_for_test();
+        traced_function(2);
+        {
+            trace_point!("block");
+        }
+
+        let thread_id = current_thread_id();
+        let events: Vec<TraceEvent> = collect_events()
+            .into_iter()
+            .filter(|thread| thread.thread_id == thread_id)
+            .flat_map(|thread| thread.events)
+            .collect();
+        let summary: Vec<(TraceEventKind, &str, u32)> = events
+            .iter()
+            .map(|event| (event.kind, event.name, event.depth))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (TraceEventKind::Enter, "traced_function", 0),
+                (TraceEventKind::Enter, "traced_function", 1),
+                (TraceEventKind::Enter, "traced_function", 2),
+                (TraceEventKind::Leave, "traced_function", 2),
+                (TraceEventKind::Leave, "traced_function", 1),
+                (TraceEventKind::Leave, "traced_function", 0),
+                (TraceEventKind::Enter, "block", 0),
+                (TraceEventKind::Leave, "block", 0),
+            ]
+        );
+        assert!(events
+            .iter()
+            .all(|event| event.file.ends_with("trace_point.rs") && event.line > 0));
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+    }
+
+    struct Traced;
+
+    #[trace]
+    impl Traced {
+        fn run(&self) {
+            self.step();
+        }
+
+        #[trace(skip)]
+        fn step(&self) {
+            traced_free_function();
+        }
+    }
+
+    #[trace]
+    fn traced_free_function() {}
+
+    // the attribute is a no-op unless the crate is built with `profiling_enabled`
+    #[test]
+    fn attribute_test() {
+        let _lock = trace_collector::lock_collector_for_test();
+        Traced.run();
+
+        let thread_id = current_thread_id();
+        let names: Vec<&str> = collect_events()
+            .into_iter()
+            .filter(|thread| thread.thread_id == thread_id)
+            .flat_map(|thread| thread.events)
+            .filter(|event| event.kind == TraceEventKind::Enter)
+            .map(|event| event.name)
+            .collect();
+        if cfg!(feature = "profiling_enabled") {
+            assert_eq!(names, vec!["Traced::run", "traced_free_function"]);
+        } else {
+            assert!(names.is_empty());
+        }
+    }
+
+    #[test]
+    fn disabled_test() {
+        let _lock = trace_collector::lock_collector_for_test();
+        set_tracing_enabled(false);
+        traced_function(1);
+        set_tracing_enabled(true);
+
+        let thread_id = current_thread_id();
+        assert!(collect_events()
+            .iter()
+            .all(|thread| thread.thread_id != thread_id));
+    }
+}
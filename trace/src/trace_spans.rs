@@ -0,0 +1,150 @@
+use crate::trace_capture::{CapturedEvent, CapturedThread};
+use crate::TraceEventKind;
+
+// time between matching enter and leave events of a thread
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CapturedSpan {
+    pub point: u32,
+    pub start: u64,
+    pub end: u64,
+    pub depth: u32,
+    // duration without the time of nested spans
+    pub self_time: u64,
+    // index of the enter event in the events of the thread
+    pub event: usize,
+}
+
+impl CapturedSpan {
+    pub fn get_duration(&self) -> u64 {
+        self.end - self.start
+    }
+}
+
+impl CapturedThread {
+    // spans in the order of their starts, spans left open are closed by the last event of the thread,
+    // leave events without enter events are skipped, a capture can start in the middle of a span
+    pub fn get_spans(&self) -> Vec<CapturedSpan> {
+        let mut spans: Vec<CapturedSpan> = Vec::new();
+        // indices of open spans and the time of their closed children
+        let mut stack: Vec<(usize, u64)> = Vec::new();
+        for (index, event) in self.events.iter().enumerate() {
+            match event.kind {
+                TraceEventKind::Enter => {
+                    stack.push((spans.len(), 0));
+                    spans.push(CapturedSpan {
+                        point: event.point,
+                        start: event.timestamp,
+                        end: event.timestamp,
+                        depth: stack.len() as u32 - 1,
+                        self_time: 0,
+                        event: index,
+                    });
+                }
+                TraceEventKind::Leave => {
+                    if let Some((index, children_time)) = stack.pop() {
+                        close_span(&mut spans[index], event.timestamp, children_time);
+                        if let Some((_, parent_children_time)) = stack.last_mut() {
+                            *parent_children_time += spans[index].get_duration();
+                        }
+                    }
+                }
+                TraceEventKind::Marker | TraceEventKind::Counter | TraceEventKind::Argument => {}
+            }
+        }
+
+        let last_timestamp = self.events.last().map(|event| event.timestamp).unwrap_or(0);
+        while let Some((index, children_time)) = stack.pop() {
+            close_span(&mut spans[index], last_timestamp, children_time);
+            if let Some((_, parent_children_time)) = stack.last_mut() {
+                *parent_children_time += spans[index].get_duration();
+            }
+        }
+        spans
+    }
+
+    // argument events of the span, arguments of nested spans are skipped
+    pub fn get_span_arguments(&self, span: &CapturedSpan) -> Vec<&CapturedEvent> {
+        let mut arguments = Vec::new();
+        let mut nested_spans = 0;
+        for event in &self.events[span.event + 1..] {
+            match event.kind {
+                TraceEventKind::Enter => nested_spans += 1,
+                TraceEventKind::Leave if nested_spans == 0 => break,
+                TraceEventKind::Leave => nested_spans -= 1,
+                TraceEventKind::Argument if nested_spans == 0 => arguments.push(event),
+                _ => {}
+            }
+        }
+        arguments
+    }
+}
+
+fn close_span(span: &mut CapturedSpan, end: u64, children_time: u64) {
+    span.end = end.max(span.start);
+    span.self_time = span.get_duration().saturating_sub(children_time);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn event(kind: TraceEventKind, point: u32, timestamp: u64) -> CapturedEvent {
+        CapturedEvent {
+            kind,
+            point,
+            timestamp,
+            depth: 0,
+            value: None,
+        }
+    }
+
+    #[test]
+    fn spans_test() {
+        use TraceEventKind::*;
+        let thread = CapturedThread {
+            id: 1,
+            name: None,
+            events: vec![
+                // leave of a span started before the capture
+                event(Leave, 3, 0),
+                event(Enter, 0, 10),
+                event(Enter, 1, 12),
+                event(Argument, 4, 13),
+                event(Leave, 1, 15),
+                event(Argument, 5, 15),
+                event(Enter, 2, 16),
+                event(Leave, 2, 20),
+                event(Leave, 0, 30),
+                event(Enter, 0, 40),
+                event(Enter, 1, 45),
+                event(Leave, 1, 50),
+            ],
+        };
+        let spans = thread.get_spans();
+        let summary: Vec<(u32, u64, u64, u32, u64)> = spans
+            .iter()
+            .map(|span| (span.point, span.start, span.end, span.depth, span.self_time))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, 10, 30, 0, 13),
+                (1, 12, 15, 1, 3),
+                (2, 16, 20, 1, 4),
+                (0, 40, 50, 0, 5),
+                (1, 45, 50, 1, 5),
+            ]
+        );
+
+        let points = |span| -> Vec<u32> {
+            thread
+                .get_span_arguments(span)
+                .iter()
+                .map(|event| event.point)
+                .collect()
+        };
+        assert_eq!(points(&spans[0]), vec![5]);
+        assert_eq!(points(&spans[1]), vec![4]);
+        assert!(points(&spans[4]).is_empty());
+    }
+}
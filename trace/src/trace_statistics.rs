@@ -0,0 +1,331 @@
+use crate::trace_capture::TraceCapture;
+use std::collections::HashMap;
+
+// aggregate of all spans with the same name, times are in nanoseconds;
+// recursive calls are counted in the total time of every level
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpanStatistics {
+    pub name: String,
+    pub count: u64,
+    pub total_time: u64,
+    pub self_time: u64,
+    pub min_time: u64,
+    pub max_time: u64,
+    // nearest-rank percentiles of span durations
+    pub p50_time: u64,
+    pub p95_time: u64,
+    pub p99_time: u64,
+}
+
+impl SpanStatistics {
+    pub fn get_mean_time(&self) -> f64 {
+        if self.count == 0 {
+            0.
+        } else {
+            self.total_time as f64 / self.count as f64
+        }
+    }
+}
+
+// statistics of all threads ordered by the total time, the longest first
+pub fn compute_statistics(capture: &TraceCapture) -> Vec<SpanStatistics> {
+    let mut statistics: Vec<SpanStatistics> = Vec::new();
+    let mut durations: Vec<Vec<u64>> = Vec::new();
+    let mut indices: HashMap<&str, usize> = HashMap::new();
+    for thread in &capture.threads {
+        for span in thread.get_spans() {
+            let name = capture.points[span.point as usize].name.as_str();
+            let index = *indices.entry(name).or_insert_with(|| {
+                statistics.push(SpanStatistics {
+                    name: String::from(name),
+                    count: 0,
+                    total_time: 0,
+                    self_time: 0,
+                    min_time: u64::MAX,
+                    max_time: 0,
+                    p50_time: 0,
+                    p95_time: 0,
+                    p99_time: 0,
+                });
+                durations.push(Vec::new());
+                statistics.len() - 1
+            });
+
+            let duration = span.get_duration();
+            let entry = &mut statistics[index];
+            entry.count += 1;
+            entry.total_time += duration;
+            entry.self_time += span.self_time;
+            entry.min_time = entry.min_time.min(duration);
+            entry.max_time = entry.max_time.max(duration);
+            durations[index].push(duration);
+        }
+    }
+
+    for (entry, durations) in statistics.iter_mut().zip(durations.iter_mut()) {
+        durations.sort_unstable();
+        entry.p50_time = get_percentile(durations, 50.);
+        entry.p95_time = get_percentile(durations, 95.);
+        entry.p99_time = get_percentile(durations, 99.);
+    }
+
+    statistics.sort_by(|a, b| {
+        b.total_time
+            .cmp(&a.total_time)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    statistics
+}
+
+// `sorted` is not empty
+fn get_percentile(sorted: &[u64], percentile: f64) -> u64 {
+    let rank = (percentile / 100. * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// value of statistics that is compared between runs
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatisticsMetric {
+    Count,
+    Total,
+    SelfTime,
+    Mean,
+    P50,
+    P95,
+    P99,
+}
+
+impl StatisticsMetric {
+    pub fn parse(name: &str) -> Option<StatisticsMetric> {
+        match name {
+            "count" => Some(StatisticsMetric::Count),
+            "total" => Some(StatisticsMetric::Total),
+            "self" => Some(StatisticsMetric::SelfTime),
+            "mean" => Some(StatisticsMetric::Mean),
+            "p50" => Some(StatisticsMetric::P50),
+            "p95" => Some(StatisticsMetric::P95),
+            "p99" => Some(StatisticsMetric::P99),
+            _ => None,
+        }
+    }
+
+    pub fn get(self, statistics: &SpanStatistics) -> f64 {
+        match self {
+            StatisticsMetric::Count => statistics.count as f64,
+            StatisticsMetric::Total => statistics.total_time as f64,
+            StatisticsMetric::SelfTime => statistics.self_time as f64,
+            StatisticsMetric::Mean => statistics.get_mean_time(),
+            StatisticsMetric::P50 => statistics.p50_time as f64,
+            StatisticsMetric::P95 => statistics.p95_time as f64,
+            StatisticsMetric::P99 => statistics.p99_time as f64,
+        }
+    }
+}
+
+// statistics of a span in two runs, a span can be missing in one of them
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpanComparison {
+    pub name: String,
+    pub base: Option<SpanStatistics>,
+    pub current: Option<SpanStatistics>,
+}
+
+impl SpanComparison {
+    // change of the metric in percent of the base run,
+    // none if the span is missing in one of the runs or its base value is zero
+    pub fn get_change(&self, metric: StatisticsMetric) -> Option<f64> {
+        let base = metric.get(self.base.as_ref()?);
+        let current = metric.get(self.current.as_ref()?);
+        if base == 0. {
+            None
+        } else {
+            Some((current - base) / base * 100.)
+        }
+    }
+}
+
+// spans of the current run in its order, then spans which are only in the base run
+pub fn compare_statistics(
+    base: &[SpanStatistics],
+    current: &[SpanStatistics],
+) -> Vec<SpanComparison> {
+    let mut comparisons: Vec<SpanComparison> = current
+        .iter()
+        .map(|current| SpanComparison {
+            name: current.name.clone(),
+            base: base.iter().find(|base| base.name == current.name).cloned(),
+            current: Some(current.clone()),
+        })
+        .collect();
+    comparisons.extend(
+        base.iter()
+            .filter(|base| current.iter().all(|current| current.name != base.name))
+            .map(|base| SpanComparison {
+                name: base.name.clone(),
+                base: Some(base.clone()),
+                current: None,
+            }),
+    );
+    comparisons
+}
+
+// spans whose metric has grown by more than `threshold` percent
+pub fn find_regressions(
+    comparisons: &[SpanComparison],
+    metric: StatisticsMetric,
+    threshold: f64,
+) -> Vec<&SpanComparison> {
+    comparisons
+        .iter()
+        .filter(|comparison| {
+            matches!(comparison.get_change(metric), Some(change) if change > threshold)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn point(name: &str) -> CapturedPoint {
+        CapturedPoint {
+            name: String::from(name),
+            file: String::from("main.rs"),
+            line: 1,
+        }
+    }
+
+    fn event(kind: TraceEventKind, point: u32, timestamp: u64) -> CapturedEvent {
+        CapturedEvent {
+            kind,
+            point,
+            timestamp,
+            depth: 0,
+            value: None,
+        }
+    }
+
+    #[test]
+    fn statistics_test() {
+        use TraceEventKind::*;
+        let capture = TraceCapture {
+            // the same name at two call sites is one function
+            points: vec![point("frame"), point("update"), point("update")],
+            threads: vec![
+                CapturedThread {
+                    id: 1,
+                    name: None,
+                    events: vec![
+                        event(Enter, 0, 0),
+                        event(Enter, 1, 10),
+                        event(Leave, 1, 40),
+                        event(Leave, 0, 100),
+                    ],
+                },
+                CapturedThread {
+                    id: 2,
+                    name: None,
+                    events: vec![event(Enter, 2, 0), event(Leave, 2, 10)],
+                },
+            ],
+        };
+
+        let statistics = compute_statistics(&capture);
+        assert_eq!(
+            statistics,
+            vec![
+                SpanStatistics {
+                    name: String::from("frame"),
+                    count: 1,
+                    total_time: 100,
+                    self_time: 70,
+                    min_time: 100,
+                    max_time: 100,
+                    p50_time: 100,
+                    p95_time: 100,
+                    p99_time: 100,
+                },
+                SpanStatistics {
+                    name: String::from("update"),
+                    count: 2,
+                    total_time: 40,
+                    self_time: 40,
+                    min_time: 10,
+                    max_time: 30,
+                    p50_time: 10,
+                    p95_time: 30,
+                    p99_time: 30,
+                },
+            ]
+        );
+        assert_eq!(statistics[1].get_mean_time(), 20.);
+    }
+
+    #[test]
+    fn percentile_test() {
+        let sorted: Vec<u64> = (1..=100).collect();
+        assert_eq!(get_percentile(&sorted, 50.), 50);
+        assert_eq!(get_percentile(&sorted, 95.), 95);
+        assert_eq!(get_percentile(&sorted, 99.), 99);
+        assert_eq!(get_percentile(&sorted, 0.), 1);
+        assert_eq!(get_percentile(&[7], 99.), 7);
+    }
+
+    fn single_span(name: &str, duration: u64) -> SpanStatistics {
+        SpanStatistics {
+            name: String::from(name),
+            count: 1,
+            total_time: duration,
+            self_time: duration,
+            min_time: duration,
+            max_time: duration,
+            p50_time: duration,
+            p95_time: duration,
+            p99_time: duration,
+        }
+    }
+
+    #[test]
+    fn compare_test() {
+        let base = vec![
+            single_span("evaluate", 100),
+            single_span("draw", 50),
+            single_span("removed", 10),
+        ];
+        let current = vec![
+            single_span("evaluate", 120),
+            single_span("added", 30),
+            single_span("draw", 40),
+        ];
+        let comparisons = compare_statistics(&base, &current);
+        let changes: Vec<(&str, Option<f64>)> = comparisons
+            .iter()
+            .map(|comparison| {
+                (
+                    comparison.name.as_str(),
+                    comparison.get_change(StatisticsMetric::Mean),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("evaluate", Some(20.)),
+                ("added", None),
+                ("draw", Some(-20.)),
+                ("removed", None),
+            ]
+        );
+
+        let regressions = find_regressions(&comparisons, StatisticsMetric::P95, 10.);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].name, "evaluate");
+        assert!(find_regressions(&comparisons, StatisticsMetric::P95, 20.).is_empty());
+        assert_eq!(
+            StatisticsMetric::parse("self"),
+            Some(StatisticsMetric::SelfTime)
+        );
+        assert_eq!(StatisticsMetric::parse("median"), None);
+    }
+}
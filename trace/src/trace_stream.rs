@@ -0,0 +1,367 @@
+use crate::trace_binary::{read_binary, write_binary};
+use crate::trace_capture::TraceCapture;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// how often the server collects and sends events
+pub const TRACE_STREAM_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TraceAddress {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl TraceAddress {
+    // `unix:/tmp/trace.sock` is a unix socket, anything else is a tcp address like `127.0.0.1:7878`
+    pub fn parse(address: &str) -> TraceAddress {
+        #[cfg(unix)]
+        {
+            if let Some(path) = address.strip_prefix("unix:") {
+                return TraceAddress::Unix(PathBuf::from(path));
+            }
+        }
+        TraceAddress::Tcp(String::from(address))
+    }
+}
+
+impl std::fmt::Display for TraceAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TraceAddress::Tcp(address) => write!(f, "{}", address),
+            #[cfg(unix)]
+            TraceAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn connect(address: &TraceAddress) -> std::io::Result<Stream> {
+        match address {
+            TraceAddress::Tcp(address) => TcpStream::connect(address).map(Stream::Tcp),
+            #[cfg(unix)]
+            TraceAddress::Unix(path) => UnixStream::connect(path).map(Stream::Unix),
+        }
+    }
+
+    fn try_clone(&self) -> std::io::Result<Stream> {
+        match self {
+            Stream::Tcp(stream) => stream.try_clone().map(Stream::Tcp),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.try_clone().map(Stream::Unix),
+        }
+    }
+
+    fn shutdown(&self) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.shutdown(Shutdown::Both),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.shutdown(Shutdown::Both),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    // the listener is non-blocking, so the server thread doesn't wait for clients
+    fn bind(address: &TraceAddress) -> std::io::Result<Listener> {
+        let listener = match address {
+            TraceAddress::Tcp(address) => Listener::Tcp(TcpListener::bind(address)?),
+            #[cfg(unix)]
+            TraceAddress::Unix(path) => Listener::Unix(UnixListener::bind(path)?),
+        };
+        match &listener {
+            Listener::Tcp(listener) => listener.set_nonblocking(true)?,
+            #[cfg(unix)]
+            Listener::Unix(listener) => listener.set_nonblocking(true)?,
+        }
+        Ok(listener)
+    }
+
+    fn get_address(&self, requested: &TraceAddress) -> std::io::Result<TraceAddress> {
+        match self {
+            Listener::Tcp(listener) => Ok(TraceAddress::Tcp(listener.local_addr()?.to_string())),
+            #[cfg(unix)]
+            Listener::Unix(_) => Ok(requested.clone()),
+        }
+    }
+
+    fn accept(&self) -> std::io::Result<Stream> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                Ok(Stream::Tcp(stream))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                Ok(Stream::Unix(stream))
+            }
+        }
+    }
+}
+
+// sends collected events to all connected clients, every chunk is a capture in the binary format;
+// the server takes events from the global collector, so it shouldn't be combined with `TraceFile`,
+// events collected while nobody is connected are dropped
+pub struct TraceStreamServer {
+    address: TraceAddress,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TraceStreamServer {
+    // tcp port 0 picks a free port, `get_address` returns the actual one
+    pub fn start(address: &TraceAddress) -> std::io::Result<TraceStreamServer> {
+        let listener = Listener::bind(address)?;
+        let address = listener.get_address(address)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name(String::from("trace stream"))
+            .spawn(move || serve(listener, &thread_stop))?;
+        Ok(TraceStreamServer {
+            address,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn get_address(&self) -> &TraceAddress {
+        &self.address
+    }
+}
+
+impl Drop for TraceStreamServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        #[cfg(unix)]
+        {
+            if let TraceAddress::Unix(path) = &self.address {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+fn serve(listener: Listener, stop: &AtomicBool) {
+    let mut clients: Vec<BufWriter<Stream>> = Vec::new();
+    loop {
+        loop {
+            match listener.accept() {
+                Ok(stream) => clients.push(BufWriter::new(stream)),
+                Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    log::error!("failed to accept trace stream client: {}", error);
+                    break;
+                }
+            }
+        }
+
+        // the last chunk is sent after the stop request, so spans closed before it are not lost
+        let stopping = stop.load(Ordering::Relaxed);
+        let capture = TraceCapture::collect();
+        if !capture.threads.is_empty() {
+            clients = clients
+                .into_iter()
+                .filter_map(|mut client| write_binary(&capture, &mut client).ok().map(|_| client))
+                .collect();
+        }
+        if stopping {
+            break;
+        }
+        std::thread::sleep(TRACE_STREAM_INTERVAL);
+    }
+}
+
+// receives chunks of a stream on a background thread
+pub struct TraceStreamReceiver {
+    stream: Stream,
+    receiver: Receiver<TraceCapture>,
+    connected: Arc<AtomicBool>,
+}
+
+impl TraceStreamReceiver {
+    pub fn connect(address: &TraceAddress) -> std::io::Result<TraceStreamReceiver> {
+        let stream = Stream::connect(address)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let (sender, receiver) = channel();
+        let connected = Arc::new(AtomicBool::new(true));
+        let thread_connected = connected.clone();
+        std::thread::Builder::new()
+            .name(String::from("trace receiver"))
+            .spawn(move || {
+                loop {
+                    match read_binary(&mut reader) {
+                        Ok(capture) => {
+                            if sender.send(capture).is_err() {
+                                break;
+                            }
+                        }
+                        // the server closes the connection between chunks
+                        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                        Err(error) => {
+                            log::error!("failed to read trace stream: {}", error);
+                            break;
+                        }
+                    }
+                }
+                thread_connected.store(false, Ordering::Relaxed);
+            })?;
+        Ok(TraceStreamReceiver {
+            stream,
+            receiver,
+            connected,
+        })
+    }
+
+    // merges chunks received since the previous call, returns true if there were any
+    pub fn receive(&self, capture: &mut TraceCapture) -> bool {
+        let mut received = false;
+        while let Ok(chunk) = self.receiver.try_recv() {
+            capture.merge(chunk);
+            received = true;
+        }
+        received
+    }
+
+    // false after the server has closed the connection, received chunks can still be pending
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for TraceStreamReceiver {
+    fn drop(&mut self) {
+        // unblocks the reading thread
+        let _ = self.stream.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use std::time::Instant;
+
+    fn loopback(address: &TraceAddress) {
+        let _lock = trace_collector::lock_collector_for_test();
+        let server = TraceStreamServer::start(address).unwrap();
+        let receiver = TraceStreamReceiver::connect(server.get_address()).unwrap();
+
+        // the server could collect the spans before it has accepted the client
+        std::thread::sleep(TRACE_STREAM_INTERVAL * 3);
+        let worker = std::thread::Builder::new()
+            .name(String::from("streamed"))
+            .spawn(|| {
+                for _ in 0..3 {
+                    trace_point!("streamed span");
+                }
+            })
+            .unwrap();
+        worker.join().unwrap();
+
+        let mut capture = TraceCapture::new();
+        let start = Instant::now();
+        let streamed = loop {
+            receiver.receive(&mut capture);
+            let streamed = capture
+                .threads
+                .iter()
+                .find(|thread| thread.name.as_deref() == Some("streamed"));
+            if let Some(thread) = streamed {
+                if thread.events.len() == 6 {
+                    break thread;
+                }
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "spans weren't streamed"
+            );
+            std::thread::sleep(TRACE_STREAM_INTERVAL);
+        };
+        assert!(streamed
+            .events
+            .iter()
+            .all(|event| capture.get_point(event).name == "streamed span"));
+
+        drop(server);
+        let start = Instant::now();
+        while receiver.is_connected() {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "connection wasn't closed"
+            );
+            std::thread::sleep(TRACE_STREAM_INTERVAL);
+        }
+    }
+
+    #[test]
+    fn tcp_loopback_test() {
+        loopback(&TraceAddress::parse("127.0.0.1:0"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_loopback_test() {
+        let path =
+            std::env::temp_dir().join(format!("trace_stream_test_{}.sock", std::process::id()));
+        let address = TraceAddress::parse(&format!("unix:{}", path.display()));
+        assert_eq!(address, TraceAddress::Unix(path));
+        loopback(&address);
+    }
+}
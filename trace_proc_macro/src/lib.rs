@@ -1,24 +1,243 @@
 extern crate proc_macro;
 
-use proc_macro::*;
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Block, Ident, ImplItem, Item, ItemImpl, LitStr, Path, Signature, Type};
 
+// `#[trace]` records a span for every call of the function,
+// on an impl block it records spans of all methods, `#[trace(skip)]` excludes a method;
+// span names are `function`, `Type::method` or `<Type as Trait>::method`
 #[proc_macro_attribute]
-#[cfg(feature = "profiling_enabled")]
-pub fn trace(_: TokenStream, input: TokenStream) -> TokenStream {
-    let function_name = "func";
-    let trait_name = "trait";
-    let struct_name = "struct";
-    let string_to_insert = format!("trace_point!({}, {}, {});", function_name, trait_name, struct_name);
+pub fn trace(
+    attr: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    if !cfg!(feature = "profiling_enabled") {
+        return input;
+    }
+    expand(attr.into(), input.into())
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
 
-    let mut input = input.to_string();
-    if let Some(idx) = input.find("{") {
-        input.insert_str(idx+1, &string_to_insert);
+#[derive(Default)]
+struct TraceArgs {
+    skip: bool,
+}
+
+impl Parse for TraceArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.is_empty() {
+            return Ok(TraceArgs::default());
+        }
+        let ident: Ident = input.parse()?;
+        if ident != "skip" || !input.is_empty() {
+            return Err(syn::Error::new(
+                ident.span(),
+                "expected `#[trace]` or `#[trace(skip)]`",
+            ));
+        }
+        Ok(TraceArgs { skip: true })
     }
-    input.parse().unwrap()
 }
 
-#[proc_macro_attribute]
-#[cfg(not(feature = "profiling_enabled"))]
-pub fn trace(_: TokenStream, input: TokenStream) -> TokenStream {
-    input
+fn expand(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+    let args: TraceArgs = syn::parse2(attr)?;
+    if args.skip {
+        return Ok(input);
+    }
+
+    match syn::parse2(input)? {
+        Item::Fn(mut function) => {
+            if let Some(error) = get_signature_error(&function.sig) {
+                return Err(error);
+            }
+            let name = function.sig.ident.to_string();
+            insert_trace_point(&mut function.block, &name);
+            Ok(function.into_token_stream())
+        }
+        Item::Impl(mut item_impl) => {
+            instrument_impl(&mut item_impl)?;
+            Ok(item_impl.into_token_stream())
+        }
+        item => Err(syn::Error::new_spanned(
+            item,
+            "`#[trace]` can be applied only to functions and impl blocks",
+        )),
+    }
+}
+
+// trace points can't be evaluated in const context and would be held across awaits by async functions
+fn get_signature_error(signature: &Signature) -> Option<syn::Error> {
+    let constness = signature
+        .constness
+        .as_ref()
+        .map(|constness| syn::Error::new_spanned(constness, "const functions can't be traced"));
+    let asyncness = signature
+        .asyncness
+        .as_ref()
+        .map(|asyncness| syn::Error::new_spanned(asyncness, "async functions can't be traced"));
+    constness.or(asyncness)
+}
+
+fn instrument_impl(item_impl: &mut ItemImpl) -> syn::Result<()> {
+    let type_name = get_type_name(&item_impl.self_ty);
+    let prefix = match &item_impl.trait_ {
+        Some((_, trait_path, _)) => format!("<{} as {}>", type_name, get_path_name(trait_path)),
+        None => type_name,
+    };
+
+    for impl_item in &mut item_impl.items {
+        let method = match impl_item {
+            ImplItem::Method(method) => method,
+            _ => continue,
+        };
+
+        // trace attributes of methods are handled here, otherwise they would instrument the method twice
+        let mut args = TraceArgs::default();
+        for attr in method.attrs.iter().filter(|attr| is_trace_attribute(attr)) {
+            if !attr.tokens.is_empty() {
+                args.skip |= attr.parse_args::<TraceArgs>()?.skip;
+            }
+        }
+        method.attrs.retain(|attr| !is_trace_attribute(attr));
+
+        if !args.skip && get_signature_error(&method.sig).is_none() {
+            let name = format!("{}::{}", prefix, method.sig.ident);
+            insert_trace_point(&mut method.block, &name);
+        }
+    }
+    Ok(())
+}
+
+fn insert_trace_point(block: &mut Block, name: &str) {
+    let name = LitStr::new(name, Span::call_site());
+    block
+        .stmts
+        .insert(0, syn::parse_quote!(::trace::trace_point!(#name);));
+}
+
+fn is_trace_attribute(attr: &Attribute) -> bool {
+    attr.path
+        .segments
+        .last()
+        .map(|segment| segment.ident == "trace")
+        .unwrap_or(false)
+}
+
+// generic arguments are omitted, `Vec<T>` is named `Vec`
+fn get_path_name(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn get_type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => get_path_name(&type_path.path),
+        ty => ty.to_token_stream().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn expand_to_string(attr: TokenStream, input: TokenStream) -> String {
+        expand(attr, input).unwrap().to_string()
+    }
+
+    fn trace_point(name: &str) -> String {
+        quote!(::trace::trace_point!(#name);).to_string()
+    }
+
+    #[test]
+    fn function_test() {
+        let output = expand_to_string(
+            quote!(),
+            quote!(
+                pub fn evaluate(x: u32) -> u32 {
+                    x + 1
+                }
+            ),
+        );
+        assert!(output.contains(&format!("{{ {} x + 1 }}", trace_point("evaluate"))));
+
+        // a method marked alone is named without its type
+        let output = expand_to_string(
+            quote!(),
+            quote!(
+                fn get(&self) -> u32 {
+                    1
+                }
+            ),
+        );
+        assert!(output.contains(&trace_point("get")));
+
+        let skipped = quote!(
+            fn evaluate() {}
+        );
+        assert_eq!(
+            expand_to_string(quote!(skip), skipped.clone()),
+            skipped.to_string()
+        );
+
+        assert!(expand(
+            quote!(),
+            quote!(
+                const fn evaluate() {}
+            )
+        )
+        .is_err());
+        assert!(expand(
+            quote!(),
+            quote!(
+                struct Document;
+            )
+        )
+        .is_err());
+        assert!(expand(
+            quote!(unknown),
+            quote!(
+                fn evaluate() {}
+            )
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn impl_test() {
+        let output = expand_to_string(
+            quote!(),
+            quote! {
+                impl<T> Document<T> {
+                    fn load(&mut self) {}
+                    #[trace(skip)]
+                    fn get_name(&self) -> &str { "" }
+                    #[trace]
+                    fn save(&self) {}
+                    const fn version() -> u32 { 1 }
+                }
+            },
+        );
+        assert!(output.contains(&trace_point("Document::load")));
+        assert!(output.contains(&trace_point("Document::save")));
+        assert!(!output.contains("get_name\""));
+        assert!(!output.contains("version\""));
+        assert!(!output.contains("# [trace"));
+
+        let output = expand_to_string(
+            quote!(),
+            quote! {
+                impl nodes_engine::Node for Constant {
+                    fn invoke(&self) {}
+                }
+            },
+        );
+        assert!(output.contains(&trace_point("<Constant as nodes_engine::Node>::invoke")));
+    }
 }
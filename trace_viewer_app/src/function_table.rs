@@ -0,0 +1,179 @@
+use crate::timeline::format_time;
+use crate::viewer::Viewer;
+use imgui::*;
+use std::cmp::Ordering;
+use trace::SpanStatistics;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Column {
+    Name,
+    Count,
+    Total,
+    SelfTime,
+    Mean,
+    Min,
+    Max,
+    P50,
+    P95,
+    P99,
+}
+
+const COLUMNS: [(Column, &str); 10] = [
+    (Column::Name, "Function"),
+    (Column::Count, "Count"),
+    (Column::Total, "Total"),
+    (Column::SelfTime, "Self"),
+    (Column::Mean, "Mean"),
+    (Column::Min, "Min"),
+    (Column::Max, "Max"),
+    (Column::P50, "P50"),
+    (Column::P95, "P95"),
+    (Column::P99, "P99"),
+];
+
+// sort order of the aggregate table, a click on the sorted column reverses it
+pub struct FunctionTable {
+    pub column: Column,
+    pub descending: bool,
+}
+
+impl FunctionTable {
+    pub fn new() -> FunctionTable {
+        FunctionTable {
+            column: Column::Total,
+            descending: true,
+        }
+    }
+
+    pub fn select(&mut self, column: Column) {
+        if self.column == column {
+            self.descending = !self.descending;
+        } else {
+            self.column = column;
+            // names read best from a to z, times from the longest
+            self.descending = column != Column::Name;
+        }
+    }
+
+    pub fn sort(&self, statistics: &mut [SpanStatistics]) {
+        let column = self.column;
+        statistics.sort_by(|a, b| {
+            let ordering = match column {
+                Column::Name => a.name.cmp(&b.name),
+                Column::Count => a.count.cmp(&b.count),
+                Column::Total => a.total_time.cmp(&b.total_time),
+                Column::SelfTime => a.self_time.cmp(&b.self_time),
+                Column::Mean => a
+                    .get_mean_time()
+                    .partial_cmp(&b.get_mean_time())
+                    .unwrap_or(Ordering::Equal),
+                Column::Min => a.min_time.cmp(&b.min_time),
+                Column::Max => a.max_time.cmp(&b.max_time),
+                Column::P50 => a.p50_time.cmp(&b.p50_time),
+                Column::P95 => a.p95_time.cmp(&b.p95_time),
+                Column::P99 => a.p99_time.cmp(&b.p99_time),
+            };
+            let ordering = if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+            ordering.then_with(|| a.name.cmp(&b.name))
+        });
+    }
+}
+
+pub fn function_table_ui(ui: &Ui, viewer: &mut Viewer) {
+    Window::new(im_str!("Functions"))
+        .position([10.0, 520.0], Condition::FirstUseEver)
+        .size([1000.0, 300.0], Condition::FirstUseEver)
+        .build(ui, || {
+            ui.columns(COLUMNS.len() as i32, im_str!("functions"), true);
+            for (column, title) in COLUMNS.iter() {
+                let arrow = match (viewer.table.column == *column, viewer.table.descending) {
+                    (false, _) => "",
+                    (true, true) => " v",
+                    (true, false) => " ^",
+                };
+                let label = ImString::new(format!("{}{}", title, arrow));
+                if Selectable::new(&label).build(ui) {
+                    viewer.table.select(*column);
+                    viewer.table.sort(&mut viewer.statistics);
+                }
+                ui.next_column();
+            }
+            ui.separator();
+
+            for statistics in &viewer.statistics {
+                ui.text(&statistics.name);
+                ui.next_column();
+                ui.text(format!("{}", statistics.count));
+                ui.next_column();
+                ui.text(format_time(statistics.total_time as f64));
+                ui.next_column();
+                ui.text(format_time(statistics.self_time as f64));
+                ui.next_column();
+                ui.text(format_time(statistics.get_mean_time()));
+                ui.next_column();
+                ui.text(format_time(statistics.min_time as f64));
+                ui.next_column();
+                ui.text(format_time(statistics.max_time as f64));
+                ui.next_column();
+                ui.text(format_time(statistics.p50_time as f64));
+                ui.next_column();
+                ui.text(format_time(statistics.p95_time as f64));
+                ui.next_column();
+                ui.text(format_time(statistics.p99_time as f64));
+                ui.next_column();
+            }
+            ui.columns(1, im_str!("functions"), false);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statistics(name: &str, count: u64, total_time: u64) -> SpanStatistics {
+        SpanStatistics {
+            name: String::from(name),
+            count,
+            total_time,
+            self_time: total_time,
+            min_time: 0,
+            max_time: total_time,
+            p50_time: total_time,
+            p95_time: total_time,
+            p99_time: total_time,
+        }
+    }
+
+    #[test]
+    fn sort_test() {
+        let mut rows = vec![
+            statistics("b", 1, 10),
+            statistics("a", 4, 20),
+            statistics("c", 2, 10),
+        ];
+        let names = |rows: &[SpanStatistics]| -> Vec<String> {
+            rows.iter().map(|row| row.name.clone()).collect()
+        };
+
+        let mut table = FunctionTable::new();
+        table.sort(&mut rows);
+        assert_eq!(names(&rows), vec!["a", "b", "c"]);
+
+        table.select(Column::Name);
+        assert!(!table.descending);
+        table.sort(&mut rows);
+        assert_eq!(names(&rows), vec!["a", "b", "c"]);
+
+        table.select(Column::Name);
+        table.sort(&mut rows);
+        assert_eq!(names(&rows), vec!["c", "b", "a"]);
+
+        table.select(Column::Mean);
+        table.sort(&mut rows);
+        assert_eq!(names(&rows), vec!["b", "a", "c"]);
+    }
+}
@@ -0,0 +1,398 @@
+use crate::viewer::{ThreadTimeline, Viewer};
+use imgui::*;
+use trace::{CapturedCounter, TraceCapture, TraceValue};
+
+const ROW_HEIGHT: f32 = 20.0;
+const COUNTER_HEIGHT: f32 = 40.0;
+const MARKER_SIZE: f32 = 8.0;
+const MARKER_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+const MIN_DURATION: f64 = 100.0;
+const ZOOM_STEP: f64 = 1.25;
+
+// visible time range in nanoseconds
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TimelineView {
+    pub start: f64,
+    pub duration: f64,
+}
+
+impl TimelineView {
+    pub fn new() -> TimelineView {
+        TimelineView {
+            start: 0.0,
+            duration: 1_000_000.0,
+        }
+    }
+
+    pub fn fit(&mut self, begin: u64, end: u64) {
+        self.start = begin as f64;
+        self.duration = ((end - begin.min(end)) as f64).max(MIN_DURATION);
+    }
+
+    pub fn time_to_x(&self, timestamp: f64, width: f32) -> f32 {
+        ((timestamp - self.start) / self.duration * width as f64) as f32
+    }
+
+    pub fn x_to_time(&self, x: f32, width: f32) -> f64 {
+        self.start + x as f64 / width as f64 * self.duration
+    }
+
+    // keeps the time under `x` in place
+    pub fn zoom(&mut self, factor: f64, x: f32, width: f32) {
+        let anchor = self.x_to_time(x, width);
+        self.duration = (self.duration * factor).max(MIN_DURATION);
+        self.start = anchor - x as f64 / width as f64 * self.duration;
+    }
+
+    pub fn pan(&mut self, dx: f32, width: f32) {
+        self.start -= dx as f64 / width as f64 * self.duration;
+    }
+}
+
+pub fn timeline_ui(ui: &Ui, viewer: &mut Viewer) {
+    Window::new(im_str!("Timeline"))
+        .position([10.0, 110.0], Condition::FirstUseEver)
+        .size([1000.0, 400.0], Condition::FirstUseEver)
+        .build(ui, || {
+            ui.text(format!(
+                "{} .. {}, wheel to zoom, drag to pan",
+                format_time(viewer.view.start),
+                format_time(viewer.view.start + viewer.view.duration)
+            ));
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Fit")) {
+                viewer.fit_view();
+                viewer.follow = false;
+            }
+            ui.separator();
+
+            let canvas_pos = ui.cursor_screen_pos();
+            let width = ui.content_region_avail()[0].max(100.0);
+            let height: f32 = viewer.counters.len() as f32 * COUNTER_HEIGHT
+                + viewer
+                    .threads
+                    .iter()
+                    .map(|thread| (thread.max_depth + 2) as f32 * ROW_HEIGHT)
+                    .sum::<f32>();
+            let height = height.max(ROW_HEIGHT);
+            ui.invisible_button(im_str!("timeline"), [width, height]);
+
+            let mouse_pos = ui.io().mouse_pos;
+            let mouse_x = mouse_pos[0] - canvas_pos[0];
+            if ui.is_item_hovered() && ui.io().mouse_wheel != 0.0 {
+                let factor = ZOOM_STEP.powf(-ui.io().mouse_wheel as f64);
+                viewer.view.zoom(factor, mouse_x, width);
+            }
+            if ui.is_item_active() && ui.io().mouse_delta[0] != 0.0 {
+                viewer.view.pan(ui.io().mouse_delta[0], width);
+                // dragging inspects the past of a live capture
+                viewer.follow = false;
+            }
+
+            let canvas = Canvas {
+                draw_list: ui.get_window_draw_list(),
+                pos: canvas_pos,
+                width,
+                view: viewer.view,
+                mouse_pos: if ui.is_item_hovered() {
+                    Some(mouse_pos)
+                } else {
+                    None
+                },
+            };
+            let canvas_end = [canvas_pos[0] + width, canvas_pos[1] + height];
+            let mut hovered = None;
+            canvas
+                .draw_list
+                .with_clip_rect_intersect(canvas_pos, canvas_end, || {
+                    let mut y = canvas_pos[1];
+                    for (index, counter) in viewer.counters.iter().enumerate() {
+                        if let Some(value) = draw_counter(&canvas, counter, y) {
+                            hovered = Some(Hovered::Counter(index, value));
+                        }
+                        y += COUNTER_HEIGHT;
+                    }
+                    for (index, thread) in viewer.threads.iter().enumerate() {
+                        if let Some(item) = draw_thread(&canvas, &viewer.capture, thread, index, y)
+                        {
+                            hovered = Some(item);
+                        }
+                        y += (thread.max_depth + 2) as f32 * ROW_HEIGHT;
+                    }
+                });
+
+            match hovered {
+                Some(Hovered::Span(thread_index, span_index)) => {
+                    let thread = &viewer.threads[thread_index];
+                    let span = &thread.spans[span_index];
+                    let point = &viewer.capture.points[span.point as usize];
+                    let arguments = viewer.capture.threads[thread.thread].get_span_arguments(span);
+                    ui.tooltip(|| {
+                        ui.text(&point.name);
+                        ui.text(format!("{}:{}", point.file, point.line));
+                        ui.text(format!(
+                            "duration {}",
+                            format_time(span.get_duration() as f64)
+                        ));
+                        ui.text(format!("self {}", format_time(span.self_time as f64)));
+                        for argument in arguments {
+                            if let Some(value) = argument.value {
+                                let key = &viewer.capture.get_point(argument).name;
+                                ui.text(format!("{} = {}", key, value));
+                            }
+                        }
+                    });
+                }
+                Some(Hovered::Marker(thread_index, marker_index)) => {
+                    let (timestamp, point) = viewer.threads[thread_index].markers[marker_index];
+                    let point = &viewer.capture.points[point as usize];
+                    ui.tooltip(|| {
+                        ui.text(&point.name);
+                        ui.text(format!("{}:{}", point.file, point.line));
+                        ui.text(format!("at {}", format_time(timestamp as f64)));
+                    });
+                }
+                Some(Hovered::Counter(counter_index, value)) => {
+                    let counter = &viewer.counters[counter_index];
+                    ui.tooltip(|| {
+                        ui.text(format!("{} = {}", counter.name, value));
+                    });
+                }
+                None => {}
+            }
+        });
+}
+
+// item under the mouse, threads and counters are referred by their indices in the viewer
+enum Hovered {
+    Span(usize, usize),
+    Marker(usize, usize),
+    Counter(usize, TraceValue),
+}
+
+struct Canvas<'a> {
+    draw_list: WindowDrawList<'a>,
+    pos: [f32; 2],
+    width: f32,
+    view: TimelineView,
+    // set while the canvas is hovered
+    mouse_pos: Option<[f32; 2]>,
+}
+
+impl Canvas<'_> {
+    fn is_hovered(&self, p1: [f32; 2], p2: [f32; 2]) -> bool {
+        match self.mouse_pos {
+            Some(mouse) => {
+                mouse[0] >= p1[0] && mouse[0] < p2[0] && mouse[1] >= p1[1] && mouse[1] < p2[1]
+            }
+            None => false,
+        }
+    }
+
+    fn get_x(&self, timestamp: u64) -> f32 {
+        self.pos[0] + self.view.time_to_x(timestamp as f64, self.width)
+    }
+}
+
+// step graph of counter values scaled to the track, returns the value under the mouse
+fn draw_counter(canvas: &Canvas<'_>, counter: &CapturedCounter, y: f32) -> Option<TraceValue> {
+    let draw_list = &canvas.draw_list;
+    let end_x = canvas.pos[0] + canvas.width;
+    draw_list
+        .add_rect(
+            [canvas.pos[0], y],
+            [end_x, y + COUNTER_HEIGHT - 1.0],
+            [0.15, 0.15, 0.18, 1.0],
+        )
+        .filled(true)
+        .build();
+
+    let (min, max) = counter
+        .values
+        .iter()
+        .map(|(_, value)| value.as_f64())
+        .fold((0.0f64, 0.0f64), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+    let range = if max > min { max - min } else { 1.0 };
+    let get_y = |value: TraceValue| {
+        y + COUNTER_HEIGHT - 3.0 - ((value.as_f64() - min) / range) as f32 * (COUNTER_HEIGHT - 6.0)
+    };
+
+    let color = span_color(&counter.name);
+    let mut hovered = None;
+    for (index, (timestamp, value)) in counter.values.iter().enumerate() {
+        let x1 = canvas.get_x(*timestamp);
+        let x2 = counter
+            .values
+            .get(index + 1)
+            .map_or(end_x, |(next, _)| canvas.get_x(*next));
+        if x2 < canvas.pos[0] || x1 > end_x {
+            continue;
+        }
+        let value_y = get_y(*value);
+        draw_list
+            .add_line([x1, value_y], [x2, value_y], color)
+            .thickness(2.0)
+            .build();
+        if let Some((next_timestamp, next_value)) = counter.values.get(index + 1) {
+            let x = canvas.get_x(*next_timestamp);
+            draw_list
+                .add_line([x, value_y], [x, get_y(*next_value)], color)
+                .build();
+        }
+        if canvas.is_hovered([x1, y], [x2, y + COUNTER_HEIGHT]) {
+            hovered = Some(*value);
+        }
+    }
+    draw_list.add_text(
+        [canvas.pos[0] + 4.0, y + 3.0],
+        [1.0, 1.0, 1.0, 1.0],
+        &counter.name,
+    );
+    hovered
+}
+
+// a header with the thread name and markers, then rows of nested spans
+fn draw_thread(
+    canvas: &Canvas<'_>,
+    capture: &TraceCapture,
+    thread: &ThreadTimeline,
+    thread_index: usize,
+    y: f32,
+) -> Option<Hovered> {
+    let draw_list = &canvas.draw_list;
+    let end_x = canvas.pos[0] + canvas.width;
+    let mut hovered = None;
+    draw_list
+        .add_rect(
+            [canvas.pos[0], y],
+            [end_x, y + ROW_HEIGHT],
+            [0.2, 0.2, 0.25, 1.0],
+        )
+        .filled(true)
+        .build();
+    draw_list.add_text(
+        [canvas.pos[0] + 4.0, y + 3.0],
+        [1.0, 1.0, 1.0, 1.0],
+        &thread.name,
+    );
+
+    let spans_y = y + ROW_HEIGHT;
+    for (span_index, span) in thread.spans.iter().enumerate() {
+        let x1 = canvas.get_x(span.start);
+        let x2 = canvas.get_x(span.end);
+        if x2 < canvas.pos[0] || x1 > end_x {
+            continue;
+        }
+        // too short spans are still visible
+        let x2 = x2.max(x1 + 1.0);
+        let p1 = [
+            x1.max(canvas.pos[0]),
+            spans_y + span.depth as f32 * ROW_HEIGHT,
+        ];
+        let p2 = [x2.min(end_x), p1[1] + ROW_HEIGHT - 1.0];
+        let name = &capture.points[span.point as usize].name;
+        draw_list
+            .add_rect(p1, p2, span_color(name))
+            .filled(true)
+            .build();
+        if p2[0] - p1[0] > 20.0 {
+            draw_list.with_clip_rect_intersect(p1, p2, || {
+                draw_list.add_text([p1[0] + 3.0, p1[1] + 3.0], [0.0, 0.0, 0.0, 1.0], name);
+            });
+        }
+        if canvas.is_hovered(p1, p2) {
+            hovered = Some(Hovered::Span(thread_index, span_index));
+        }
+    }
+
+    // flags in the header with lines through the spans
+    let spans_end_y = spans_y + (thread.max_depth + 1) as f32 * ROW_HEIGHT;
+    for (marker_index, (timestamp, _)) in thread.markers.iter().enumerate() {
+        let x = canvas.get_x(*timestamp);
+        if x < canvas.pos[0] - MARKER_SIZE || x > end_x {
+            continue;
+        }
+        draw_list
+            .add_line([x, y], [x, spans_end_y], MARKER_COLOR)
+            .build();
+        draw_list
+            .add_triangle(
+                [x, y + 2.0],
+                [x + MARKER_SIZE, y + 2.0 + MARKER_SIZE / 2.0],
+                [x, y + 2.0 + MARKER_SIZE],
+                MARKER_COLOR,
+            )
+            .filled(true)
+            .build();
+        if canvas.is_hovered([x - 2.0, y], [x + MARKER_SIZE, y + ROW_HEIGHT]) {
+            hovered = Some(Hovered::Marker(thread_index, marker_index));
+        }
+    }
+    hovered
+}
+
+// the same function has the same color in all threads
+fn span_color(name: &str) -> [f32; 4] {
+    // FNV-1a
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3)
+    });
+    let hue = (hash % 360) as f32 / 60.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    // pastel colors keep black labels readable
+    [0.5 + r * 0.45, 0.5 + g * 0.45, 0.5 + b * 0.45, 1.0]
+}
+
+pub fn format_time(nanoseconds: f64) -> String {
+    let abs = nanoseconds.abs();
+    if abs >= 1e9 {
+        format!("{:.3} s", nanoseconds / 1e9)
+    } else if abs >= 1e6 {
+        format!("{:.3} ms", nanoseconds / 1e6)
+    } else if abs >= 1e3 {
+        format!("{:.3} us", nanoseconds / 1e3)
+    } else {
+        format!("{:.0} ns", nanoseconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_test() {
+        let mut view = TimelineView::new();
+        view.fit(1000, 3000);
+        assert_eq!(view.time_to_x(2000.0, 100.0), 50.0);
+
+        // the time under the cursor stays in place
+        view.zoom(0.5, 25.0, 100.0);
+        assert_eq!(view.duration, 1000.0);
+        assert_eq!(view.x_to_time(25.0, 100.0), 1500.0);
+
+        view.pan(50.0, 100.0);
+        assert_eq!(view.start, 750.0);
+
+        view.zoom(0.0, 0.0, 100.0);
+        assert_eq!(view.duration, MIN_DURATION);
+    }
+
+    #[test]
+    fn format_time_test() {
+        assert_eq!(format_time(999.0), "999 ns");
+        assert_eq!(format_time(1500.0), "1.500 us");
+        assert_eq!(format_time(2.5e6), "2.500 ms");
+        assert_eq!(format_time(3e9), "3.000 s");
+    }
+}
@@ -0,0 +1,225 @@
+use crate::function_table::FunctionTable;
+use crate::timeline::TimelineView;
+use imgui::*;
+use std::path::Path;
+use trace::{
+    CapturedCounter, CapturedSpan, SpanStatistics, TraceAddress, TraceCapture, TraceEventKind,
+    TraceStreamReceiver,
+};
+
+// events older than this are dropped from a live capture
+const LIVE_HISTORY: u64 = 30_000_000_000;
+
+// spans and markers of one thread prepared for drawing
+pub struct ThreadTimeline {
+    // index of the thread in the capture
+    pub thread: usize,
+    pub name: String,
+    pub spans: Vec<CapturedSpan>,
+    pub max_depth: u32,
+    // timestamps and points of markers
+    pub markers: Vec<(u64, u32)>,
+}
+
+pub struct Viewer {
+    pub path: ImString,
+    pub address: ImString,
+    pub capture: TraceCapture,
+    pub threads: Vec<ThreadTimeline>,
+    pub counters: Vec<CapturedCounter>,
+    pub statistics: Vec<SpanStatistics>,
+    pub view: TimelineView,
+    pub table: FunctionTable,
+    pub error: Option<String>,
+    pub receiver: Option<TraceStreamReceiver>,
+    // the timeline scrolls with the newest events of a live capture
+    pub follow: bool,
+}
+
+impl Viewer {
+    pub fn new() -> Viewer {
+        let mut address = ImString::with_capacity(256);
+        address.push_str("127.0.0.1:7878");
+        Viewer {
+            path: ImString::with_capacity(1024),
+            address,
+            capture: TraceCapture::new(),
+            threads: Vec::new(),
+            counters: Vec::new(),
+            statistics: Vec::new(),
+            view: TimelineView::new(),
+            table: FunctionTable::new(),
+            error: None,
+            receiver: None,
+            follow: true,
+        }
+    }
+
+    pub fn open(&mut self, path: &Path) {
+        self.path.clear();
+        self.path.push_str(&path.to_string_lossy());
+        match TraceCapture::load(path) {
+            Ok(capture) => {
+                self.receiver = None;
+                self.set_capture(capture);
+                self.error = None;
+            }
+            Err(error) => {
+                log::error!("failed to load trace {:?}: {}", path, error);
+                self.error = Some(format!("failed to load {}: {}", path.display(), error));
+            }
+        }
+    }
+
+    // replaces the capture with events streamed from `address`
+    pub fn connect(&mut self, address: &str) {
+        self.address.clear();
+        self.address.push_str(address);
+        match TraceStreamReceiver::connect(&TraceAddress::parse(address)) {
+            Ok(receiver) => {
+                self.receiver = Some(receiver);
+                self.set_capture(TraceCapture::new());
+                self.follow = true;
+                self.error = None;
+            }
+            Err(error) => {
+                log::error!("failed to connect to {}: {}", address, error);
+                self.error = Some(format!("failed to connect to {}: {}", address, error));
+            }
+        }
+    }
+
+    // takes events received from a live stream, called every frame
+    pub fn update(&mut self) {
+        let received = match &self.receiver {
+            Some(receiver) => receiver.receive(&mut self.capture),
+            None => return,
+        };
+        if !received {
+            return;
+        }
+
+        let newest = self.get_newest_timestamp();
+        let oldest = newest.saturating_sub(LIVE_HISTORY);
+        for thread in &mut self.capture.threads {
+            thread.events.retain(|event| event.timestamp >= oldest);
+        }
+        self.update_timelines();
+        if self.follow {
+            self.view.start = newest as f64 - self.view.duration;
+        }
+    }
+
+    pub fn set_capture(&mut self, capture: TraceCapture) {
+        self.capture = capture;
+        self.update_timelines();
+        self.fit_view();
+    }
+
+    fn update_timelines(&mut self) {
+        self.threads = self
+            .capture
+            .threads
+            .iter()
+            .enumerate()
+            .map(|(index, thread)| {
+                let spans = thread.get_spans();
+                ThreadTimeline {
+                    thread: index,
+                    name: thread
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| format!("thread {}", thread.id)),
+                    max_depth: spans.iter().map(|span| span.depth).max().unwrap_or(0),
+                    spans,
+                    markers: thread
+                        .events
+                        .iter()
+                        .filter(|event| event.kind == TraceEventKind::Marker)
+                        .map(|event| (event.timestamp, event.point))
+                        .collect(),
+                }
+            })
+            .collect();
+        self.counters = self.capture.get_counters();
+        self.statistics = trace::compute_statistics(&self.capture);
+        self.table.sort(&mut self.statistics);
+    }
+
+    fn get_newest_timestamp(&self) -> u64 {
+        self.capture
+            .threads
+            .iter()
+            .filter_map(|thread| thread.events.last())
+            .map(|event| event.timestamp)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // shows the whole capture
+    pub fn fit_view(&mut self) {
+        let begin = self
+            .threads
+            .iter()
+            .filter_map(|thread| thread.spans.first())
+            .map(|span| span.start)
+            .min()
+            .unwrap_or(0);
+        let end = self
+            .threads
+            .iter()
+            .flat_map(|thread| thread.spans.iter())
+            .map(|span| span.end)
+            .max()
+            .unwrap_or(0);
+        self.view.fit(begin, end);
+    }
+}
+
+pub fn file_ui(ui: &Ui, viewer: &mut Viewer) {
+    Window::new(im_str!("Trace"))
+        .position([10.0, 10.0], Condition::FirstUseEver)
+        .size([600.0, 110.0], Condition::FirstUseEver)
+        .build(ui, || {
+            let entered = ui
+                .input_text(im_str!("##path"), &mut viewer.path)
+                .enter_returns_true(true)
+                .build();
+            ui.same_line(0.0);
+            if ui.button(im_str!("Open"), [0.0, 0.0]) || entered {
+                let path = viewer.path.to_str().to_owned();
+                viewer.open(Path::new(&path));
+            }
+
+            let entered = ui
+                .input_text(im_str!("##address"), &mut viewer.address)
+                .enter_returns_true(true)
+                .build();
+            ui.same_line(0.0);
+            if viewer.receiver.is_some() {
+                if ui.button(im_str!("Disconnect"), [0.0, 0.0]) {
+                    viewer.receiver = None;
+                }
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("Follow"), &mut viewer.follow);
+            } else if ui.button(im_str!("Connect"), [0.0, 0.0]) || entered {
+                let address = viewer.address.to_str().to_owned();
+                viewer.connect(&address);
+            }
+
+            let status = match &viewer.receiver {
+                Some(receiver) if receiver.is_connected() => ", live",
+                Some(_) => ", disconnected",
+                None => "",
+            };
+            match &viewer.error {
+                Some(error) => ui.text_colored([1.0, 0.4, 0.4, 1.0], error),
+                None => ui.text(format!(
+                    "{} threads, {} trace points{}",
+                    viewer.capture.threads.len(),
+                    viewer.capture.points.len(),
+                    status
+                )),
+            }
+        });
+}